@@ -0,0 +1,30 @@
+// NOTE: animations never hold up the game. A line clear collapses the board
+// immediately, and the clear is then animated from the game's record of it.
+
+use std::time::Duration;
+
+use crate::game::{LineClear, Motion};
+
+/// How long a move takes to ease in, see [`move_ease`]
+pub const MOVE_EASE: Duration = Duration::from_millis(50);
+
+/// How long cleared rows flash for
+pub const LINE_CLEAR_FLASH: Duration = Duration::from_millis(100);
+
+/// The opacity of the flash over a line clear's rows, `None` once finished
+///
+/// `now` is the game's active time, see [`crate::game::Game::active_elapsed`].
+pub fn line_clear_flash(clear: &LineClear, now: Duration) -> Option<f32> {
+    let progress = now.checked_sub(clear.at)?.as_secs_f32() / LINE_CLEAR_FLASH.as_secs_f32();
+    (progress < 1.0).then_some(1.0 - progress)
+}
+
+/// How much of a move is left to ease in, from 1 when it happens down to 0,
+/// `None` once finished
+///
+/// `now` is the game's active time, see [`line_clear_flash`].
+pub fn move_ease(motion: &Motion, now: Duration) -> Option<f32> {
+    let progress = now.checked_sub(motion.at)?.as_secs_f32() / MOVE_EASE.as_secs_f32();
+    // eases out, quick at first
+    (progress < 1.0).then(|| (1.0 - progress).powi(2))
+}
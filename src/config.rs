@@ -0,0 +1,274 @@
+// NOTE: every field is defaulted, so files from older versions missing newer
+// settings still load, & unknown fields from newer versions are ignored.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{game, key, styling, Action};
+
+const FILE_NAME: &str = "config.toml";
+
+/// The settings kept between launches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    pub theme: styling::Theme,
+    /// See [`styling::Settings::theme_file`]
+    pub theme_file: Option<String>,
+    pub handling: game::Handling,
+    /// See [`game::Setup::countdown`]
+    pub countdown: u32,
+    pub skip_countdown: bool,
+    pub free_first_hold: bool,
+    pub rotate_180: game::Rotate180,
+    pub cascade: bool,
+    /// See [`game::Setup::fade`]
+    pub fade: u32,
+    pub ghost_outline: bool,
+    /// See [`styling::Settings::preview_count`]
+    pub preview_count: u32,
+    pub grid: bool,
+    pub smooth_fall: bool,
+    pub smooth_move: bool,
+    pub input_display: bool,
+    pub gradients: bool,
+    pub patterns: bool,
+    pub letterbox: bool,
+    pub scale_filter: styling::ScaleFilter,
+    /// See [`styling::Settings::render_scale`]
+    pub render_scale: f32,
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+    pub pause_when_hidden: bool,
+    /// See [`styling::Sizing::border_width`]
+    pub border_width: u32,
+    /// See [`styling::Sizing::max_block_size`]
+    pub max_block_size: u32,
+    /// A TTF or OTF font text is drawn in, loaded at startup, the bundled
+    /// font when unset or it fails to load
+    pub font_path: Option<PathBuf>,
+    /// The most frames a second are drawn at, however fast the monitor
+    /// refreshes
+    pub max_render_rate: u32,
+    /// The binds played with, [`key::Preset::Custom`] being `keybinds`
+    pub key_preset: key::Preset,
+    pub keybinds: Vec<Keybind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keybind {
+    pub key: key::Key,
+    /// The modifiers held with the key as bits, 1 for shift, 2 ctrl, 4 alt &
+    /// 8 super, matching whatever's held when left out
+    #[serde(default, skip_serializing_if = "key::Mods::is_empty")]
+    pub mods: key::Mods,
+    pub action: Action,
+}
+
+impl Keybind {
+    pub fn new(chord: key::Chord, action: Action) -> Self {
+        Self {
+            key: chord.key,
+            mods: chord.mods,
+            action,
+        }
+    }
+
+    pub fn chord(&self) -> key::Chord {
+        key::Chord {
+            key: self.key,
+            mods: self.mods,
+        }
+    }
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        let settings = styling::Settings::default();
+        let setup = game::Setup::default();
+        Self {
+            theme: settings.theme,
+            theme_file: settings.theme_file,
+            handling: setup.handling,
+            countdown: setup.countdown,
+            skip_countdown: setup.skip_countdown,
+            free_first_hold: setup.free_first_hold,
+            rotate_180: setup.rotate_180,
+            cascade: setup.cascade,
+            fade: setup.fade,
+            ghost_outline: settings.ghost_outline,
+            preview_count: settings.preview_count,
+            grid: settings.grid,
+            smooth_fall: settings.smooth_fall,
+            smooth_move: settings.smooth_move,
+            input_display: settings.input_display,
+            gradients: settings.gradients,
+            patterns: settings.patterns,
+            letterbox: settings.letterbox,
+            scale_filter: settings.scale_filter,
+            render_scale: settings.render_scale,
+            high_contrast: settings.high_contrast,
+            reduced_motion: settings.reduced_motion,
+            pause_when_hidden: settings.pause_when_hidden,
+            border_width: settings.sizing.border_width,
+            max_block_size: settings.sizing.max_block_size,
+            font_path: None,
+            max_render_rate: 144,
+            key_preset: settings.key_preset,
+            keybinds: key::default_binds()
+                .into_iter()
+                .map(|(chord, action)| Keybind::new(chord, action))
+                .collect(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// Sets up the settings & new games from the config
+    pub fn apply(&self, settings: &mut styling::Settings, setup: &mut game::Setup) {
+        settings.theme = self.theme;
+        settings.theme_file = self.theme_file.clone();
+        settings.high_contrast = self.high_contrast;
+        settings.reduced_motion = self.reduced_motion;
+        settings.pause_when_hidden = self.pause_when_hidden;
+        settings.sizing.border_width = self.border_width;
+        settings.sizing.max_block_size = self
+            .max_block_size
+            .clamp(styling::MIN_BLOCK_SIZE, styling::MAX_BLOCK_SIZE);
+        // theme files are loaded by the app, see `crate::themes`
+        settings.set_palette(self.theme.palette());
+        settings.ghost_outline = self.ghost_outline;
+        settings.preview_count = self.preview_count.min(styling::layout::NEXT_COUNT);
+        settings.grid = self.grid;
+        settings.smooth_fall = self.smooth_fall;
+        settings.smooth_move = self.smooth_move;
+        settings.input_display = self.input_display;
+        settings.key_preset = self.key_preset;
+        settings.gradients = self.gradients;
+        settings.patterns = self.patterns;
+        settings.letterbox = self.letterbox;
+        settings.scale_filter = self.scale_filter;
+        settings.render_scale = self.render_scale.clamp(1.0, 2.0);
+        setup.handling = self.handling;
+        setup.countdown = self.countdown;
+        setup.skip_countdown = self.skip_countdown;
+        setup.free_first_hold = self.free_first_hold;
+        setup.rotate_180 = self.rotate_180;
+        setup.cascade = self.cascade;
+        setup.fade = self.fade;
+    }
+
+    /// Updates the config from the settings & what new games start with
+    pub fn capture(&mut self, settings: &styling::Settings, setup: &game::Setup) {
+        self.theme = settings.theme;
+        self.theme_file = settings.theme_file.clone();
+        self.ghost_outline = settings.ghost_outline;
+        self.preview_count = settings.preview_count;
+        self.grid = settings.grid;
+        self.smooth_fall = settings.smooth_fall;
+        self.smooth_move = settings.smooth_move;
+        self.input_display = settings.input_display;
+        self.key_preset = settings.key_preset;
+        self.gradients = settings.gradients;
+        self.patterns = settings.patterns;
+        self.letterbox = settings.letterbox;
+        self.scale_filter = settings.scale_filter;
+        self.render_scale = settings.render_scale;
+        self.high_contrast = settings.high_contrast;
+        self.reduced_motion = settings.reduced_motion;
+        self.pause_when_hidden = settings.pause_when_hidden;
+        self.border_width = settings.sizing.border_width;
+        self.max_block_size = settings.sizing.max_block_size;
+        self.handling = setup.handling;
+        self.countdown = setup.countdown;
+        self.skip_countdown = setup.skip_countdown;
+        self.free_first_hold = setup.free_first_hold;
+        self.rotate_180 = setup.rotate_180;
+        self.cascade = setup.cascade;
+        self.fade = setup.fade;
+    }
+
+    pub fn key_store(&self) -> key::KeyStore {
+        let custom = self.keybinds.iter().map(|bind| (bind.chord(), bind.action));
+        key::KeyStore::from_preset(self.key_preset, custom)
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("wgputris").join(FILE_NAME))
+}
+
+/// Loads the saved config, the default when it's missing or unreadable
+pub fn load() -> UserConfig {
+    let Some(path) = path() else {
+        return UserConfig::default();
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return UserConfig::default(),
+        Err(err) => {
+            log::error!("failed to read config: {err}");
+            return UserConfig::default();
+        }
+    };
+    toml::from_str(&text).unwrap_or_else(|err| {
+        log::error!("ignoring invalid config: {err}");
+        UserConfig::default()
+    })
+}
+
+pub fn save(config: &UserConfig) -> anyhow::Result<()> {
+    let path = path().context("no config directory")?;
+    write_atomic(&path, &toml::to_string(config)?)
+}
+
+/// Writes a temporary file next to `path` & renames it over, so a crash
+/// mid-write leaves the old file intact
+pub fn write_atomic(path: &Path, text: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, text).with_context(|| format!("failed to write {}", path.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_presets_are_saved() {
+        let config = UserConfig {
+            key_preset: key::Preset::Jstris,
+            ..Default::default()
+        };
+        let text = toml::to_string(&config).unwrap();
+        assert!(text.contains("key_preset = \"jstris\""), "{text}");
+        let loaded: UserConfig = toml::from_str(&text).unwrap();
+        assert_eq!(loaded, config);
+        let keys = loaded.key_store();
+        let shift = key::Key::Code(winit::keyboard::KeyCode::ShiftLeft).into();
+        assert_eq!(keys.action(shift), Some(Action::Hold));
+    }
+
+    #[test]
+    fn older_configs_keep_their_binds() {
+        let text = r#"
+            [[keybinds]]
+            key = { Code = "KeyH" }
+            action = "Hold"
+        "#;
+        let config: UserConfig = toml::from_str(text).unwrap();
+        assert_eq!(config.key_preset, key::Preset::Custom);
+        let keys = config.key_store();
+        let h = key::Key::Code(winit::keyboard::KeyCode::KeyH).into();
+        assert_eq!(keys.action(h), Some(Action::Hold));
+        assert_eq!(keys.custom().len(), 1);
+    }
+}
@@ -0,0 +1,108 @@
+// NOTE: the daily challenge is the same game for everyone on a UTC day, its
+// seed hashed from the date. Only the first attempt of a day is recorded,
+// later ones being practice.
+
+use std::fmt;
+
+use crate::game;
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+/// A day of the proleptic gregorian calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// The current day in UTC
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_days((secs / 86_400) as i64)
+    }
+
+    /// The day this many days after the unix epoch
+    pub fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+        Self { year, month, day }
+    }
+
+    /// The seed of the day's game, the FNV-1a hash of its `YYYY-MM-DD`
+    pub fn seed(self) -> u64 {
+        self.written()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// The date as `YYYY-MM-DD`, written out without allocating
+    pub fn written(self) -> Written {
+        let mut written = Written {
+            bytes: [0; 24],
+            len: 0,
+        };
+        let _ = fmt::Write::write_fmt(&mut written, format_args!("{self}"));
+        written
+    }
+}
+
+/// A date written out on the stack, see [`Date::written`]
+///
+/// Room's left for any year, sign included.
+#[derive(Debug, Clone, Copy)]
+pub struct Written {
+    bytes: [u8; 24],
+    len: usize,
+}
+
+impl std::ops::Deref for Written {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // only ever written whole strs
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for Written {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// The day's game, only the handling & countdown being kept from `setup`
+pub fn setup(date: Date, setup: &game::Setup) -> game::Setup {
+    game::Setup {
+        mode: game::Mode::Daily,
+        seed: Some(date.seed()),
+        handling: setup.handling,
+        countdown: setup.countdown,
+        skip_countdown: setup.skip_countdown,
+        ..Default::default()
+    }
+}
@@ -1,189 +1,803 @@
-use crate::{game, rend, styling};
+use crate::time::Instant;
+use crate::{anim, game, rend, styling};
 
-// TODO: create drawing context
+pub mod effects;
+pub mod inputs;
+pub mod popups;
 
-fn quad(colour: styling::Colour, x: u32, y: u32, width: u32, height: u32) -> rend::Quad {
+/// The hidden rows drawn above the board, so pieces spawning or rotating at
+/// the top stay in sight
+const PEEK_ROWS: i32 = 1;
+
+/// What a frame is drawn from
+#[derive(Debug, Clone, Copy)]
+pub struct DrawContext<'a> {
+    pub settings: &'a styling::Settings,
+    pub game: &'a game::RenderSnapshot,
+    /// The wall clock, for effects that keep going while the game's behind
+    pub now: Instant,
+    /// The progress between the last tick & the next
+    pub alpha: f32,
+}
+
+impl DrawContext<'_> {
+    pub fn palette(&self) -> &styling::Palette {
+        &self.settings.palette
+    }
+
+    pub fn board(&self) -> rend::Rect {
+        self.settings.sizing.layout.board
+    }
+
+    pub fn pitch(&self) -> u32 {
+        self.settings.sizing.pitch()
+    }
+
+    /// The top left of a cell of the board, `row` counting from the top
+    /// visible row, so hidden rows are above the board
+    pub fn cell(&self, x: i32, row: i32) -> (i32, i32) {
+        let board = self.board();
+        let pitch = self.pitch() as i32;
+        (board.x as i32 + x * pitch, board.y as i32 + row * pitch)
+    }
+
+    /// A cell of the board filled with `colour`
+    pub fn fill_cell(&self, x: i32, row: i32, colour: styling::Colour) -> rend::Quad {
+        let (x, y) = self.cell(x, row);
+        let size = self.settings.sizing.block_size;
+        quad(colour, x, y, size, size)
+    }
+}
+
+pub(crate) fn quad(colour: styling::Colour, x: i32, y: i32, width: u32, height: u32) -> rend::Quad {
     rend::Quad {
         colour,
         x,
         y,
         width,
         height,
+        uv: None,
+        border: 0.0,
+        border_colour: styling::Colour::default(),
+        radius: 0.0,
+        bevel: 0.0,
+        hollow: false,
+        gradient: None,
+    }
+}
+
+/// The marks of a piece's pattern, by its colour, as x, y, width & height
+/// within a cell of `size`
+///
+/// Marks are a fifth of the cell thick, so they stay legible in small cells.
+fn pattern_marks(colour: usize, size: u32) -> [Option<[u32; 4]>; 2] {
+    let u = (size / 5).max(2);
+    let mid = size.saturating_sub(u) / 2;
+    let far = size.saturating_sub(2 * u);
+    let long = size.saturating_sub(2 * u);
+    let bar = [u, mid, long, u];
+    let stripe = [mid, u, u, long];
+    let dot = |x, y| Some([x, y, u, u]);
+    match colour {
+        0 => [Some(bar), None],
+        1 => [Some(stripe), None],
+        2 => [Some(bar), Some(stripe)],
+        3 => [dot(mid, mid), None],
+        4 => [dot(u, u), dot(far, far)],
+        5 => [dot(far, u), dot(u, far)],
+        _ => [dot(u, mid), dot(far, mid)],
     }
 }
 
-pub fn base_quads(
-    styling::Settings {
-        palette: styling,
-        sizing:
-            styling::Sizing {
-                game_x,
-                game_y,
-                block_size,
-            },
-    }: &styling::Settings,
-    base_layer: &mut rend::QuadLayer,
+/// The skin sprite of a block
+fn block_sprite(block: Option<game::Block>) -> usize {
+    block.map_or(rend::skin::EMPTY_SPRITE, |b| {
+        rend::skin::EMPTY_SPRITE + 1 + b.colour()
+    })
+}
+
+pub fn base_quads(ctx: &DrawContext, base_layer: &mut impl rend::QuadSink) {
+    let settings = ctx.settings;
+    let board = ctx.board();
+    base_layer.set_pipeline(gradient_pipeline(settings));
+    let quad = rend::Quad {
+        // left out when off, as expanded flat layers draw it too
+        gradient: Some(settings.palette.board_bottom).filter(|_| settings.gradients),
+        ..quad(
+            settings.palette.bg2,
+            board.x as i32,
+            board.y as i32,
+            board.width,
+            board.height,
+        )
+    };
+    base_layer.write_quads(|quads| quads.push(quad));
+}
+
+/// The pending garbage, bottom up in a segment each, greyed out while it's
+/// still within its delay
+pub fn garbage_quads(ctx: &DrawContext, garbage_layer: &mut impl rend::QuadSink) {
+    let DrawContext { settings, game, .. } = *ctx;
+    garbage_layer.set_pipeline(gradient_pipeline(settings));
+    let meter = settings.sizing.layout.garbage_meter;
+    let pitch = ctx.pitch();
+    let palette = ctx.palette();
+    let now = game.active_elapsed();
+    let rules = game.versus_rules();
+    // the meter's colour at a height, the gradient spanning the whole meter
+    let shade = |y: u32| {
+        let t = (y - meter.y) as f32 / meter.height.max(1) as f32;
+        palette.garbage_top.lerp(palette.garbage_bottom, t)
+    };
+    garbage_layer.write_quads(|quads| {
+        let mut bottom = meter.y + meter.height;
+        for garbage in game.garbage().pending() {
+            let height = (garbage.rows * pitch).min(bottom - meter.y);
+            if height == 0 {
+                break;
+            }
+            // a pixel's gap between segments
+            let gap = (height > 1) as u32;
+            let top = bottom - height + gap;
+            let segment = quad(
+                palette.fg2,
+                meter.x as i32,
+                top as i32,
+                meter.width,
+                bottom - top,
+            );
+            quads.push(if !game::GarbageQueue::is_ready(garbage, now, rules) {
+                segment
+            } else if settings.gradients {
+                rend::Quad {
+                    colour: shade(top),
+                    gradient: Some(shade(bottom)),
+                    ..segment
+                }
+            } else {
+                rend::Quad {
+                    colour: palette.z,
+                    ..segment
+                }
+            });
+            bottom -= height;
+        }
+    });
+}
+
+/// Draws 1px lines along every cell boundary of the visible board
+pub fn grid_quads(ctx: &DrawContext, grid_layer: &mut impl rend::QuadSink) {
+    let board = ctx.board();
+    grid_layer.set_visible(ctx.settings.grid);
+    if !ctx.settings.grid {
+        return;
+    }
+    let colour = ctx.palette().grid;
+    let (board_x, board_y) = ctx.cell(0, 0);
+    let columns = (0..=game::BOARD_WIDTH as i32).map(|x| {
+        let x = ctx.cell(x, 0).0.min(board_x + board.width as i32);
+        quad(colour, x, board_y, 1, board.height)
+    });
+    let rows = (0..=game::BOARD_VISIBLE_HEIGHT as i32).map(|row| {
+        let y = ctx.cell(0, row).1.min(board_y + board.height as i32);
+        quad(colour, board_x, y, board.width, 1)
+    });
+    grid_layer.write_quads(|quads| quads.extend(columns.chain(rows)));
+}
+
+/// Highlights the selected entry of a menu, `line_height` apart
+pub fn menu_quads(
+    settings: &styling::Settings,
+    selected: usize,
+    line_height: f32,
+    menu_layer: &mut impl rend::QuadSink,
 ) {
+    let menu = settings.sizing.layout.menu;
+    let colour = settings.palette.fg2.with_alpha(0.3);
     let quad = quad(
-        styling.bg2,
-        *game_x,
-        *game_y,
-        block_size * game::BOARD_WIDTH as u32,
-        block_size * game::BOARD_VISIBLE_HEIGHT as u32,
+        colour,
+        menu.x as i32,
+        menu.y as i32 + (selected as f32 * line_height).round() as i32,
+        menu.width,
+        line_height.round() as u32,
     );
-    base_layer.set_quads(vec![quad]);
+    menu_layer.write_quads(|quads| quads.push(quad));
 }
 
-pub fn game_quads(
-    settings: &styling::Settings,
-    game: &game::Game,
-    game_layer: &mut rend::QuadLayer,
-) {
-    QDraw {
-        settings,
-        game,
-        game_layer,
-        quads: Vec::new(),
+/// Dims the board behind the menus of a paused or finished game
+pub fn overlay_quads(ctx: &DrawContext, overlay_layer: &mut impl rend::QuadSink) {
+    let board = ctx.board();
+    let colour = ctx.palette().bg.with_alpha(0.7);
+    let quad = quad(
+        colour,
+        board.x as i32,
+        board.y as i32,
+        board.width,
+        board.height,
+    );
+    overlay_layer.write_quads(|quads| quads.push(quad));
+}
+
+/// Whether the game looks different from one frame to the next, even while
+/// nothing changes
+pub fn is_animating(ctx: &DrawContext) -> bool {
+    let DrawContext { settings, game, .. } = *ctx;
+    let flashing = settings.animates()
+        && game
+            .last_clear()
+            .and_then(|clear| anim::line_clear_flash(clear, game.active_elapsed()))
+            .is_some();
+    let falling =
+        settings.smooth_fall && settings.animates() && game.mino().pos.y - game.prev_pos().y == 1;
+    // garbage turns from waiting to ready with no change to the game
+    let now = game.active_elapsed();
+    let waiting = game
+        .garbage()
+        .pending()
+        .any(|garbage| !game::GarbageQueue::is_ready(garbage, now, game.versus_rules()));
+    let moving = settings.smooth_move
+        && settings.animates()
+        && game
+            .motion()
+            .is_some_and(|motion| anim::move_ease(&motion, now).is_some());
+    flashing || falling || moving || waiting || game.is_fading()
+}
+
+/// Draws the game as it is `ctx.alpha` of the way to the next tick
+pub fn game_quads(ctx: &DrawContext, game_layer: &mut impl rend::QuadSink) {
+    game_layer.set_pipeline(pipeline(ctx.settings));
+    game_layer.write_quads(|quads| QDraw::new(ctx, quads).draw_game());
+}
+
+/// Draws the next queue, clipped to the height of the board
+pub fn next_quads(ctx: &DrawContext, next_layer: &mut impl rend::QuadSink) {
+    next_layer.set_pipeline(pipeline(ctx.settings));
+    next_layer.set_scissor(Some(ctx.settings.sizing.layout.next_queue));
+    next_layer.write_quads(|quads| QDraw::new(ctx, quads).draw_next());
+}
+
+/// Blocks are drawn from the skin when one is set
+fn pipeline(settings: &styling::Settings) -> rend::QuadPipeline {
+    if settings.skin.is_some() {
+        rend::QuadPipeline::Textured
+    } else {
+        rend::QuadPipeline::Flat
+    }
+}
+
+/// Gradients are only drawn with the setting on, flat otherwise
+fn gradient_pipeline(settings: &styling::Settings) -> rend::QuadPipeline {
+    if settings.gradients {
+        rend::QuadPipeline::Gradient
+    } else {
+        rend::QuadPipeline::Flat
     }
-    .draw_game();
 }
 
+/// Draws into a sink's quads, see [`rend::QuadSink::write_quads`]
 struct QDraw<'a> {
+    ctx: &'a DrawContext<'a>,
     settings: &'a styling::Settings,
-    game: &'a game::Game,
-    game_layer: &'a mut rend::QuadLayer,
-    quads: Vec<super::rend::Quad>,
+    game: &'a game::RenderSnapshot,
+    quads: &'a mut Vec<rend::Quad>,
 }
 
-impl QDraw<'_> {
+impl<'a> QDraw<'a> {
+    fn new(ctx: &'a DrawContext<'a>, quads: &'a mut Vec<rend::Quad>) -> Self {
+        Self {
+            ctx,
+            settings: ctx.settings,
+            game: ctx.game,
+            quads,
+        }
+    }
+
     pub fn draw_game(mut self) {
         self.draw_board();
-        self.draw_next();
         self.draw_held();
-        self.draw_mino(self.game.ghost(), |c| c * 0.3);
-        self.draw_mino(self.game.mino(), |c| c);
-
-        self.game_layer.set_quads(self.quads);
+        let (mino_offset, ghost_offset) = self.move_offsets();
+        if self.settings.ghost_outline {
+            self.draw_mino(self.game.ghost(), |c| c, ghost_offset, true);
+        } else {
+            let palette = &self.settings.palette;
+            self.draw_mino(
+                self.game.ghost(),
+                |c| palette.ghost_colour(c),
+                ghost_offset,
+                false,
+            );
+        }
+        let (x, y) = mino_offset;
+        let offset = (x, y + self.fall_offset());
+        self.draw_mino(self.game.mino(), |c| c, offset, false);
+        self.draw_line_clear();
     }
 
     fn draw_board(&mut self) {
-        let styling::Settings {
-            palette,
-            sizing:
-                styling::Sizing {
-                    game_x,
-                    game_y,
-                    block_size,
-                },
-        } = self.settings;
         self.quads.reserve(game::TOTAL_BLOCKS as usize);
-        let mut cx = *game_x;
-        let mut cy = *game_y;
 
         // skip first four non-visible lines
         for line in game::VISIBLE_START..game::BOARD_HEIGHT {
-            for b in self.game.blocks(line) {
-                self.push_square(palette.colour_block(b), cx, cy);
-                cx += block_size;
+            let row = (line - game::VISIBLE_START) as i32;
+            let cells = self.game.blocks(line).zip(self.game.cell_meta(line));
+            for (x, (b, meta)) in (0..).zip(cells) {
+                let (cx, cy) = self.ctx.cell(x, row);
+                let visibility = b.map_or(1.0, |_| self.game.visibility(meta));
+                // a fading block is drawn over the empty cell it'll leave
+                let b = b.filter(|_| visibility > 0.0);
+                let fading = b.is_some() && visibility < 1.0;
+                // the grid is drawn below the board, so empty cells are left out
+                if (b.is_none() || fading) && !self.settings.grid {
+                    self.push_block(None, |c| c, cx, cy);
+                }
+                if b.is_some() {
+                    let fade = |c: styling::Colour| c.with_alpha(c.a * visibility);
+                    self.push_block(b, fade, cx, cy);
+                }
             }
-            cy += block_size;
-            cx = *game_x;
-        }
-    }
-
-    fn draw_next(&mut self) {
-        let styling::Settings {
-            palette,
-            sizing:
-                styling::Sizing {
-                    game_x,
-                    game_y,
-                    block_size,
-                },
-        } = self.settings;
-        let next_x = *game_x + block_size * game::BOARD_WIDTH as u32 + block_size / 2;
-        let mut next_y = *game_y;
-
-        for &b in &self.game.bag().minos[..5] {
-            for game::Point { x, y } in b.points(Default::default()) {
-                self.push_square(
-                    palette.colour_block(Some(b)),
-                    next_x + x as u32 * block_size,
-                    next_y + y as u32 * block_size,
+        }
+    }
+
+    pub fn draw_next(mut self) {
+        let layout = self.settings.sizing.layout;
+        let pitch = self.settings.sizing.pitch();
+        let minos = self
+            .game
+            .next()
+            .iter()
+            .take(self.settings.preview_count as usize);
+
+        for (i, &b) in (0..).zip(minos) {
+            let next = layout.next(i);
+            for game::Point { x, y } in self.game.pieces().points(b, Default::default()) {
+                self.push_block(
+                    Some(b),
+                    |c| c,
+                    (next.x + x as u32 * pitch) as i32,
+                    (next.y + y as u32 * pitch) as i32,
                 );
             }
-            // 2.5 * block_size
-            next_y += block_size * 2 + block_size / 2;
         }
     }
 
+    /// Draws the held mino, dimmed like the ghost while the hold's spent
     fn draw_held(&mut self) {
-        let styling::Settings {
-            palette,
-            sizing:
-                styling::Sizing {
-                    game_x,
-                    game_y,
-                    block_size,
-                },
-        } = self.settings;
-        // gmae_x - 4.5 * block_size
-        let Some(held_x) = game_x.checked_sub(block_size * 4 + block_size / 2) else {
+        let hold = self.settings.sizing.layout.hold;
+        let pitch = self.settings.sizing.pitch();
+        let palette = &self.settings.palette;
+        let Some(held) = self.game.held() else {
             return;
         };
-        let held_y = game_y;
-        let Some(held) = self.game.bag().held else {
+        let spent = !self.game.can_hold();
+        for game::Point { x, y } in self.game.pieces().points(held, Default::default()) {
+            self.push_block(
+                Some(held),
+                |c| if spent { palette.ghost_colour(c) } else { c },
+                (hold.x + x as u32 * pitch) as i32,
+                (hold.y + y as u32 * pitch) as i32,
+            );
+        }
+    }
+
+    /// Flashes the rows of a recent line clear white
+    fn draw_line_clear(&mut self) {
+        let board = self.ctx.board();
+        if !self.settings.animates() {
+            return;
+        }
+        let Some(clear) = self.game.last_clear() else {
             return;
         };
-        for game::Point { x, y } in held.points(Default::default()) {
-            self.push_square(
-                palette.colour_block(Some(held)),
-                held_x + x as u32 * block_size,
-                held_y + y as u32 * block_size,
-            );
+        let Some(opacity) = anim::line_clear_flash(clear, self.game.active_elapsed()) else {
+            return;
+        };
+        let colour = styling::colours::PURE_WHITE.with_alpha(opacity);
+        for &row in &clear.rows {
+            let Some(row) = row.checked_sub(game::VISIBLE_START) else {
+                continue;
+            };
+            self.quads.push(rend::Quad {
+                width: board.width,
+                ..self.ctx.fill_cell(0, row as i32, colour)
+            });
+        }
+    }
+
+    /// How many pixels above its row the mino is drawn while falling into it
+    fn fall_offset(&self) -> f32 {
+        if !self.settings.smooth_fall || !self.settings.animates() {
+            return 0.0;
+        }
+        // only single row gravity steps are smoothed
+        if self.game.mino().pos.y - self.game.prev_pos().y != 1 {
+            return 0.0;
         }
+        (1.0 - self.ctx.alpha.clamp(0.0, 1.0)) * self.ctx.pitch() as f32
     }
 
-    fn draw_mino(&mut self, mino: game::Mino, colour: impl Fn(styling::Colour) -> styling::Colour) {
-        let styling::Settings {
-            palette,
-            sizing:
-                styling::Sizing {
-                    game_x,
-                    game_y,
-                    block_size,
-                },
-        } = self.settings;
+    /// How many pixels back towards where they were the mino & ghost are
+    /// drawn, easing into place after a move
+    ///
+    /// Only moves are eased, through cells that were free. Rotations snap, as
+    /// kicks can jump through the stack, & so does the ghost when it changes
+    /// row or moves more than a column.
+    fn move_offsets(&self) -> ((f32, f32), (f32, f32)) {
+        let still = ((0.0, 0.0), (0.0, 0.0));
+        if !self.settings.smooth_move || !self.settings.animates() {
+            return still;
+        }
+        let Some(motion) = self.game.motion() else {
+            return still;
+        };
+        let mino = self.game.mino();
+        if motion.from_ori != mino.ori {
+            return still;
+        }
+        let since_tick = self
+            .game
+            .tick_duration()
+            .mul_f32(self.ctx.alpha.clamp(0.0, 1.0));
+        let Some(left) = anim::move_ease(&motion, self.game.active_elapsed() + since_tick) else {
+            return still;
+        };
+        let pitch = self.ctx.pitch() as f32;
+        let offset = |from: game::IPoint, to: game::IPoint| {
+            (
+                (to.x - from.x) as f32 * left * pitch,
+                (to.y - from.y) as f32 * left * pitch,
+            )
+        };
+        let ghost = self.game.ghost().pos;
+        let ghost_from = motion.ghost_from;
+        let ghost_offset = if ghost_from.y == ghost.y && (ghost_from.x - ghost.x).abs() == 1 {
+            offset(ghost_from, ghost)
+        } else {
+            (0.0, 0.0)
+        };
+        (offset(motion.from, mino.pos), ghost_offset)
+    }
+
+    fn draw_mino(
+        &mut self,
+        mino: game::Mino,
+        colour: impl Fn(styling::Colour) -> styling::Colour,
+        (x_offset, y_offset): (f32, f32),
+        hollow: bool,
+    ) {
         let Some(points) = mino.real_points() else {
             return;
         };
         for p in points {
-            self.push_square(
-                colour(palette.colour_block(Some(mino.block))),
-                game_x + p.x as u32 * block_size,
-                game_y + p.y.saturating_sub(game::VISIBLE_START) as u32 * block_size,
+            // rows above the peek rows are skipped, rather than drawn over
+            // the top row
+            let row = p.y as i32 - game::VISIBLE_START as i32;
+            if row < -PEEK_ROWS {
+                continue;
+            }
+            let (x, y) = self.ctx.cell(p.x as i32, row);
+            let mut quad = self.block_quad(
+                Some(mino.block),
+                &colour,
+                (x as f32 - x_offset).round() as i32,
+                (y as f32 - y_offset).round() as i32,
             );
+            if hollow {
+                // outlines are twice as thick as borders to stay visible
+                quad.border = (self.settings.block_border() * 2.0).max(1.0);
+                quad.hollow = true;
+            }
+            let (x, y) = (quad.x, quad.y);
+            self.quads.push(quad);
+            if !hollow {
+                self.push_pattern(Some(mino.block), &colour, x, y);
+            }
         }
     }
-    fn push_square(&mut self, colour: styling::Colour, x: u32, y: u32) {
+
+    fn push_block(
+        &mut self,
+        block: Option<game::Block>,
+        tint: impl Fn(styling::Colour) -> styling::Colour,
+        x: i32,
+        y: i32,
+    ) {
+        let quad = self.block_quad(block, &tint, x, y);
+        self.quads.push(quad);
+        self.push_pattern(block, tint, x, y);
+    }
+
+    /// Marks a piece's cell with its pattern, when patterns are on
+    fn push_pattern(
+        &mut self,
+        block: Option<game::Block>,
+        tint: impl Fn(styling::Colour) -> styling::Colour,
+        x: i32,
+        y: i32,
+    ) {
+        let size = self.settings.sizing.block_size;
+        let Some(block) = block.filter(|_| self.settings.patterns) else {
+            return;
+        };
+        // too small a cell to fit a mark inside
+        if size < 8 {
+            return;
+        }
+        let colour = tint(self.settings.palette.colour_block(Some(block))).darken(0.6);
+        for [mx, my, width, height] in pattern_marks(block.colour(), size).into_iter().flatten() {
+            self.quads
+                .push(quad(colour, x + mx as i32, y + my as i32, width, height));
+        }
+    }
+
+    /// A block's cell, drawn from the skin when one is set
+    fn block_quad(
+        &self,
+        block: Option<game::Block>,
+        tint: impl Fn(styling::Colour) -> styling::Colour,
+        x: i32,
+        y: i32,
+    ) -> rend::Quad {
         let s = self.settings.sizing.block_size;
-        self.quads.push(quad(colour, x, y, s, s));
-        // borders
-        let tint = styling::Colour {
-            r: 0.0,
-            g: 0.0,
-            b: 0.0,
-            a: 0.5,
-        };
-        if s != 0 {
-            for quad in [
-                quad(tint, x, y, s, 1),         // up
-                quad(tint, x, y, 1, s),         // left
-                quad(tint, x, y + s - 1, s, 1), // down
-                quad(tint, x + s - 1, y, 1, s), // right
-            ] {
-                self.quads.push(quad);
-            }
+        let shaped = |q| rend::Quad {
+            radius: self.settings.block_radius,
+            bevel: self.settings.block_bevel,
+            ..q
+        };
+        if self.settings.skin.is_none() {
+            let colour = tint(self.settings.palette.colour_block(block));
+            return shaped(rend::Quad {
+                border: self.settings.block_border(),
+                border_colour: self.settings.palette.block_border,
+                ..quad(colour, x, y, s, s)
+            });
+        }
+        shaped(rend::Quad {
+            uv: Some(rend::skin::sprite_uv(block_sprite(block))),
+            ..quad(tint(styling::colours::PURE_WHITE), x, y, s, s)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::styling::{Colour, Palette, Settings};
+
+    /// Light with a grid & a filled ghost, fit to a 500x500 window, blocks
+    /// being 20px & the board's top left at 150, 50
+    fn settings() -> Settings {
+        let mut settings = Settings {
+            palette: Palette::light(),
+            grid: true,
+            ghost_outline: false,
+            ..Default::default()
+        };
+        settings.sizing.resize(game::BoardDims::default(), 500, 500);
+        settings
+    }
+
+    /// Draws a layer's quads, e.g. [`base_quads`]
+    type Draw = fn(&DrawContext, &mut Vec<rend::Quad>);
+
+    fn draw(settings: &Settings, game: &game::Game, f: Draw) -> Vec<rend::Quad> {
+        let snapshot = game::RenderSnapshot::new(game);
+        let ctx = DrawContext {
+            settings,
+            game: &snapshot,
+            now: Instant::now(),
+            alpha: 0.0,
+        };
+        let mut quads = Vec::new();
+        f(&ctx, &mut quads);
+        quads
+    }
+
+    fn block(settings: &Settings, colour: Colour, x: i32, y: i32) -> rend::Quad {
+        rend::Quad {
+            border: settings.block_border(),
+            border_colour: settings.palette.block_border,
+            ..quad(colour, x, y, 20, 20)
+        }
+    }
+
+    #[test]
+    fn base_covers_the_board() {
+        let settings = settings();
+        let game = game::Game::new(Some(0), crate::time::DEFAULT_TICK_RATE);
+        let quads = draw(&settings, &game, base_quads);
+        assert_eq!(quads, [quad(settings.palette.bg2, 150, 50, 200, 400)]);
+    }
+
+    #[test]
+    fn game_quads_snapshot() {
+        let settings = settings();
+        let board = game::Board::from_rows(&["T.........", "XX........"]).unwrap();
+        let game = game::Game::with_board(
+            board,
+            &[game::Block::O],
+            None,
+            0,
+            crate::time::DEFAULT_TICK_RATE,
+        );
+        let quads = draw(&settings, &game, game_quads);
+
+        let palette = &settings.palette;
+        let t = palette.colour_block(Some(game::Block::T));
+        let z = palette.colour_block(Some(game::Block::Z));
+        let o = palette.colour_block(Some(game::Block::O));
+        let ghost = palette.ghost_colour(o);
+        let expected = [
+            // the stack, in the bottom two rows
+            block(&settings, t, 150, 410),
+            block(&settings, z, 150, 430),
+            block(&settings, z, 170, 430),
+            // the ghost, on the floor in columns 4 & 5
+            block(&settings, ghost, 230, 410),
+            block(&settings, ghost, 230, 430),
+            block(&settings, ghost, 250, 410),
+            block(&settings, ghost, 250, 430),
+            // the O, its top half in the peek row
+            block(&settings, o, 230, 30),
+            block(&settings, o, 230, 50),
+            block(&settings, o, 250, 30),
+            block(&settings, o, 250, 50),
+        ];
+        assert_eq!(quads, expected);
+    }
+
+    #[test]
+    fn outline_ghost_is_hollow() {
+        let settings = Settings {
+            ghost_outline: true,
+            ..settings()
+        };
+        let game = game::Game::with_board(
+            game::Board::default(),
+            &[game::Block::O],
+            None,
+            0,
+            crate::time::DEFAULT_TICK_RATE,
+        );
+        let quads = draw(&settings, &game, game_quads);
+        let o = settings.palette.colour_block(Some(game::Block::O));
+        let outline = |x, y| rend::Quad {
+            border: (settings.block_border() * 2.0).max(1.0),
+            hollow: true,
+            ..block(&settings, o, x, y)
+        };
+        assert_eq!(
+            quads[..4],
+            [
+                outline(230, 410),
+                outline(230, 430),
+                outline(250, 410),
+                outline(250, 430),
+            ]
+        );
+    }
+
+    #[test]
+    fn next_queue_snapshot() {
+        let settings = settings();
+        let game = game::Game::with_board(
+            game::Board::default(),
+            &[game::Block::O, game::Block::I, game::Block::T],
+            None,
+            0,
+            crate::time::DEFAULT_TICK_RATE,
+        );
+        let quads = draw(&settings, &game, next_quads);
+        let palette = &settings.palette;
+        let i = palette.colour_block(Some(game::Block::I));
+        let t = palette.colour_block(Some(game::Block::T));
+        // right of the board, a mino every 50px, only the first two being set
+        assert_eq!(
+            quads[..8],
+            [
+                block(&settings, i, 360, 70),
+                block(&settings, i, 380, 70),
+                block(&settings, i, 400, 70),
+                block(&settings, i, 420, 70),
+                block(&settings, t, 380, 100),
+                block(&settings, t, 360, 120),
+                block(&settings, t, 380, 120),
+                block(&settings, t, 400, 120),
+            ]
+        );
+        assert_eq!(quads.len(), 4 * styling::layout::NEXT_COUNT as usize);
+    }
+
+    #[test]
+    fn preview_count_trims_the_queue() {
+        let settings = styling::Settings {
+            preview_count: 2,
+            ..settings()
+        };
+        let game = game::Game::new(Some(0), crate::time::DEFAULT_TICK_RATE);
+        let quads = draw(&settings, &game, next_quads);
+        assert_eq!(quads.len(), 4 * 2);
+        let settings = styling::Settings {
+            preview_count: 0,
+            ..settings
+        };
+        assert!(draw(&settings, &game, next_quads).is_empty());
+    }
+
+    #[test]
+    fn peek_row_shows_what_spawns_above_it() {
+        let settings = settings();
+        // only Os, spawning a row higher than usual, at y 2 & 3
+        let standard = game::PieceSet::standard();
+        let o = game::Piece {
+            spawn: game::IPoint::new(3, 2),
+            ..standard.piece(game::Block::O).clone()
+        };
+        let setup = game::Setup {
+            seed: Some(0),
+            countdown: 0,
+            ..Default::default()
+        };
+        let pieces = std::sync::Arc::new(game::PieceSet::new(vec![o]));
+        let game = game::Game::with_pieces(setup, pieces, crate::time::DEFAULT_TICK_RATE);
+        assert_eq!(game.mino().pos.y, 2);
+        let quads = draw(&settings, &game, game_quads);
+
+        let colour = settings.palette.colour_block(Some(game.mino().block));
+        let ghost = settings.palette.ghost_colour(colour);
+        // the ghost hasn't dropped yet, so both skip y 2 & draw y 3 a row
+        // above the board
+        assert_eq!(
+            quads,
+            [
+                block(&settings, ghost, 230, 30),
+                block(&settings, ghost, 250, 30),
+                block(&settings, colour, 230, 30),
+                block(&settings, colour, 250, 30),
+            ]
+        );
+    }
+
+    /// Drawing the same frame again reuses the quads, allocating nothing
+    #[test]
+    fn redrawing_allocates_nothing() {
+        let settings = Settings {
+            gradients: true,
+            ..settings()
+        };
+        let board = game::Board::from_rows(&["T.....I...", "TT..SSI.OO", "TXXSS.IXOO"]).unwrap();
+        let game = game::Game::with_board(
+            board,
+            &[game::Block::L, game::Block::J],
+            Some(game::Block::Z),
+            0,
+            crate::time::DEFAULT_TICK_RATE,
+        );
+        let snapshot = game::RenderSnapshot::new(&game);
+        let ctx = DrawContext {
+            settings: &settings,
+            game: &snapshot,
+            now: Instant::now(),
+            alpha: 0.0,
+        };
+        let draws: [(&str, Draw); 6] = [
+            ("base", base_quads),
+            ("grid", grid_quads),
+            ("game", game_quads),
+            ("garbage", garbage_quads),
+            ("next", next_quads),
+            ("overlay", overlay_quads),
+        ];
+        let mut quads = Vec::new();
+        for (name, draw) in draws {
+            draw(&ctx, &mut quads);
+            let allocations = crate::alloc_count::allocations();
+            draw(&ctx, &mut quads);
+            let allocations = crate::alloc_count::allocations() - allocations;
+            assert_eq!(allocations, 0, "{name} allocated");
         }
+        menu_quads(&settings, 1, 24.0, &mut quads);
+        let allocations = crate::alloc_count::allocations();
+        menu_quads(&settings, 1, 24.0, &mut quads);
+        assert_eq!(crate::alloc_count::allocations() - allocations, 0);
     }
 }
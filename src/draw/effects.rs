@@ -0,0 +1,128 @@
+// NOTE: effects are timed by the wall clock rather than the game, so they
+// keep fading out while the game thread is behind.
+
+use std::time::Duration;
+
+use super::DrawContext;
+use crate::time::Instant;
+use crate::{game, rend};
+
+/// How long a hard drop's trail takes to fade out
+pub const HARD_DROP_TRAIL: Duration = Duration::from_millis(150);
+/// The opacity of a trail when it starts fading
+const TRAIL_OPACITY: f32 = 0.4;
+/// How long the hold box flashes after a hold's denied
+pub const HOLD_DENIED_FLASH: Duration = Duration::from_millis(200);
+
+/// Short lived effects, spawned by game events
+#[derive(Debug, Default)]
+pub struct Effects {
+    effects: Vec<Effect>,
+}
+
+#[derive(Debug)]
+struct Effect {
+    event: game::GameEvent,
+    start: Instant,
+}
+
+impl Effects {
+    pub fn push(&mut self, event: game::GameEvent, now: Instant) {
+        self.effects.push(Effect { event, start: now });
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Draws every running effect, removing the expired ones
+    pub fn draw(&mut self, ctx: &DrawContext, effects_layer: &mut impl rend::QuadSink) {
+        let now = ctx.now;
+        self.effects
+            .retain(|effect| now.saturating_duration_since(effect.start) < effect.duration());
+        // effects are only for show, so reduced motion drops them
+        if !ctx.settings.animates() {
+            self.effects.clear();
+            return;
+        }
+        effects_layer.write_quads(|quads| {
+            for effect in &self.effects {
+                let progress = now.saturating_duration_since(effect.start).as_secs_f32()
+                    / effect.duration().as_secs_f32();
+                // cascades & garbage aren't animated yet
+                match &effect.event {
+                    game::GameEvent::HardDropped {
+                        block,
+                        from_y,
+                        to_y,
+                        columns,
+                    } => trail_quads(
+                        ctx,
+                        *block,
+                        (*from_y, *to_y),
+                        columns,
+                        1.0 - progress,
+                        quads,
+                    ),
+                    game::GameEvent::HoldDenied => {
+                        quads.push(hold_denied_quad(ctx, 1.0 - progress));
+                    }
+                    _ => (),
+                }
+            }
+        });
+    }
+}
+
+impl Effect {
+    fn duration(&self) -> Duration {
+        match self.event {
+            game::GameEvent::HardDropped { .. } => HARD_DROP_TRAIL,
+            game::GameEvent::HoldDenied => HOLD_DENIED_FLASH,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// A red flash over the hold box
+fn hold_denied_quad(ctx: &DrawContext, fade: f32) -> rend::Quad {
+    let hold = ctx.settings.sizing.layout.hold;
+    let colour = ctx.palette().z.with_alpha(TRAIL_OPACITY * fade);
+    super::quad(
+        colour,
+        hold.x as i32,
+        hold.y as i32,
+        hold.width,
+        hold.height,
+    )
+}
+
+/// A streak down each column, from where the mino started to where it landed
+fn trail_quads(
+    ctx: &DrawContext,
+    block: game::Block,
+    (from_y, to_y): (u8, u8),
+    columns: &[u8],
+    fade: f32,
+    quads: &mut Vec<rend::Quad>,
+) {
+    let from_y = from_y.max(game::VISIBLE_START) - game::VISIBLE_START;
+    let Some(rows) = (to_y.max(game::VISIBLE_START) - game::VISIBLE_START).checked_sub(from_y)
+    else {
+        return;
+    };
+    let colour = ctx
+        .palette()
+        .colour_block(Some(block))
+        .with_alpha(TRAIL_OPACITY * fade);
+    for &x in columns {
+        quads.push(rend::Quad {
+            height: ctx.settings.sizing.span(rows as u32),
+            ..ctx.fill_cell(x as i32, from_y as i32, colour)
+        });
+    }
+}
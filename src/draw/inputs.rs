@@ -0,0 +1,84 @@
+// NOTE: the input display mirrors the actions held on the game thread, which
+// only shares a bitmask of them & of those pressed since the last frame, see
+// `Action::bit`.
+
+use std::time::Duration;
+
+use super::DrawContext;
+use crate::time::Instant;
+use crate::{rend, Action};
+
+/// How long a button flashes after it's pressed
+const TAP_FLASH: Duration = Duration::from_millis(150);
+
+/// The buttons, a row each
+const BUTTONS: [[Action; 4]; 2] = [
+    [
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Rotate180,
+        Action::Hold,
+    ],
+    [
+        Action::MoveLeft,
+        Action::MoveDown,
+        Action::MoveRight,
+        Action::Place,
+    ],
+];
+
+/// When each action was last pressed, to flash its button
+#[derive(Debug, Default)]
+pub struct InputDisplay {
+    tapped: [Option<Instant>; 16],
+}
+
+impl InputDisplay {
+    /// Picks up the actions pressed since the last frame
+    pub fn tap(&mut self, taps: u16, now: Instant) {
+        for (bit, tapped) in self.tapped.iter_mut().enumerate() {
+            if taps & 1 << bit != 0 {
+                *tapped = Some(now);
+            }
+        }
+    }
+
+    /// Whether any button is still flashing
+    pub fn is_flashing(&self, now: Instant) -> bool {
+        self.tapped
+            .iter()
+            .flatten()
+            .any(|&at| now.saturating_duration_since(at) < TAP_FLASH)
+    }
+
+    /// A button per action, lit while held & flashing once pressed
+    pub fn draw(&self, ctx: &DrawContext, held: u16, inputs_layer: &mut impl rend::QuadSink) {
+        let DrawContext { settings, now, .. } = *ctx;
+        let rect = settings.sizing.layout.inputs;
+        let pitch = settings.sizing.pitch();
+        let size = settings.sizing.block_size;
+        let palette = &settings.palette;
+        inputs_layer.write_quads(|quads| {
+            for (row, buttons) in (0..).zip(BUTTONS) {
+                for (column, action) in (0..).zip(buttons) {
+                    let x = (rect.x + column * pitch) as i32;
+                    let y = (rect.y + row * pitch) as i32;
+                    let colour = if held & action.bit() != 0 {
+                        palette.fg2
+                    } else {
+                        palette.e
+                    };
+                    quads.push(super::quad(colour, x, y, size, size));
+                    let flash = self.tapped[action as usize]
+                        .map(|at| now.saturating_duration_since(at).as_secs_f32())
+                        .map(|since| 1.0 - since / TAP_FLASH.as_secs_f32())
+                        .filter(|&flash| flash > 0.0 && settings.animates());
+                    if let Some(flash) = flash {
+                        let colour = palette.fg.with_alpha(flash);
+                        quads.push(super::quad(colour, x, y, size, size));
+                    }
+                }
+            }
+        });
+    }
+}
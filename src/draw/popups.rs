@@ -0,0 +1,88 @@
+// NOTE: popups name the notable clears & fade out over the board, timed by the
+// wall clock like effects. The events of a frame compose into one popup, a
+// line each, replacing whichever was showing.
+
+use std::time::Duration;
+
+use crate::time::Instant;
+use crate::{game, styling};
+
+/// How long a popup shows for, fading out
+pub const POPUP_DURATION: Duration = Duration::from_secs(1);
+
+/// The popup showing, if any
+#[derive(Debug, Default)]
+pub struct Popups {
+    /// The popup's lines & when it appeared
+    popup: Option<(String, Instant)>,
+}
+
+impl Popups {
+    /// Shows what's notable about an event, on the popup of the same frame
+    /// when there's one
+    pub fn push(&mut self, event: &game::GameEvent, now: Instant) {
+        let Some(lines) = describe(event) else {
+            return;
+        };
+        match &mut self.popup {
+            Some((text, at)) if *at == now => {
+                text.push('\n');
+                text.push_str(&lines);
+            }
+            popup => *popup = Some((lines, now)),
+        }
+    }
+
+    pub fn is_showing(&self) -> bool {
+        self.popup.is_some()
+    }
+
+    /// The popup's text & opacity, removing it once it's faded out
+    ///
+    /// It holds before fading, & with reduced motion shows until it's gone.
+    pub fn current(&mut self, settings: &styling::Settings, now: Instant) -> Option<(&str, f32)> {
+        let at = self.popup.as_ref()?.1;
+        let progress =
+            now.saturating_duration_since(at).as_secs_f32() / POPUP_DURATION.as_secs_f32();
+        if progress >= 1.0 {
+            self.popup = None;
+            return None;
+        }
+        let opacity = if settings.animates() {
+            1.0 - progress.powi(3)
+        } else {
+            1.0
+        };
+        self.popup
+            .as_ref()
+            .map(|(text, _)| (text.as_str(), opacity))
+    }
+}
+
+/// The lines naming an event, when it's notable
+fn describe(event: &game::GameEvent) -> Option<String> {
+    match *event {
+        game::GameEvent::LinesCleared {
+            lines,
+            combo,
+            back_to_back,
+            perfect,
+        } => {
+            let mut text = Vec::new();
+            match lines {
+                4.. if back_to_back => text.push("B2B TETRIS".to_string()),
+                4.. => text.push("TETRIS".to_string()),
+                _ => (),
+            }
+            if combo != 0 {
+                text.push(format!("COMBO {combo}"));
+            }
+            if perfect {
+                text.push("PERFECT CLEAR".to_string());
+            }
+            (!text.is_empty()).then(|| text.join("\n"))
+        }
+        game::GameEvent::ChainCleared { chain, .. } => Some(format!("CHAIN {chain}")),
+        _ => None,
+    }
+}
@@ -0,0 +1,235 @@
+// NOTE: cascades empty full lines in place rather than collapsing them, what's
+// left then falling in groups of connected blocks, which can fill more lines
+// & chain.
+
+use std::cmp::Reverse;
+
+use super::{Block, Board, CellMeta, Line, Point, BOARD_HEIGHT, BOARD_WIDTH};
+
+/// A group of connected blocks that fell in a cascade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fall {
+    /// The group's cells before falling
+    pub cells: Vec<Point>,
+    pub distance: u8,
+}
+
+impl Board {
+    /// Empties the full lines, leaving the lines above where they are
+    pub fn empty_full_lines(&mut self) {
+        for y in 0..self.lines.len() {
+            if self.is_full(y) {
+                self.lines[y] = Line::default();
+                self.occupancy[y] = 0;
+            }
+        }
+        debug_assert!(self.in_sync());
+    }
+
+    /// The groups of filled cells connected by their sides
+    pub fn groups(&self) -> Vec<Vec<Point>> {
+        let mut seen = [[false; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize];
+        let mut groups = Vec::new();
+        for y in 0..BOARD_HEIGHT {
+            for x in 0..BOARD_WIDTH {
+                if seen[y as usize][x as usize] || self.block((x, y)).is_none() {
+                    continue;
+                }
+                seen[y as usize][x as usize] = true;
+                let mut group = Vec::new();
+                let mut stack = vec![Point::new(x, y)];
+                while let Some(p) = stack.pop() {
+                    group.push(p);
+                    let neighbours = [
+                        p.x.checked_sub(1).map(|x| Point::new(x, p.y)),
+                        (p.x + 1 < BOARD_WIDTH).then(|| Point::new(p.x + 1, p.y)),
+                        p.y.checked_sub(1).map(|y| Point::new(p.x, y)),
+                        (p.y + 1 < BOARD_HEIGHT).then(|| Point::new(p.x, p.y + 1)),
+                    ];
+                    for n in neighbours.into_iter().flatten() {
+                        let seen = &mut seen[n.y as usize][n.x as usize];
+                        if !*seen && self.block(n).is_some() {
+                            *seen = true;
+                            stack.push(n);
+                        }
+                    }
+                }
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    /// Drops every floating group until everything rests
+    ///
+    /// Returns the falls in the order they happened, lower groups falling
+    /// first.
+    pub fn settle(&mut self) -> Vec<Fall> {
+        let mut falls = Vec::new();
+        loop {
+            let mut groups = self.groups();
+            groups.sort_by_key(|group| Reverse(group.iter().map(|p| p.y).max()));
+            let mut moved = false;
+            for group in groups {
+                let distance = self.drop_distance(&group);
+                if distance == 0 {
+                    continue;
+                }
+                let cells: Vec<(Option<Block>, CellMeta)> = group
+                    .iter()
+                    .map(|&p| (self.block(p), self.cell_meta(p)))
+                    .collect();
+                for &p in &group {
+                    self.set_block(p, None);
+                }
+                for (&p, (block, meta)) in group.iter().zip(cells) {
+                    self.set_cell(Point::new(p.x, p.y + distance), block, meta);
+                }
+                falls.push(Fall {
+                    cells: group,
+                    distance,
+                });
+                moved = true;
+            }
+            // falling groups can land on & join others, so settle again
+            if !moved {
+                return falls;
+            }
+        }
+    }
+
+    /// How far a group can fall, the rest of the board staying put
+    fn drop_distance(&self, group: &[Point]) -> u8 {
+        let fits = |distance: u8| {
+            group.iter().all(|p| {
+                let below = Point::new(p.x, p.y + distance);
+                below.y < BOARD_HEIGHT && (group.contains(&below) || self.block(below).is_none())
+            })
+        };
+        let mut distance = 0;
+        while fits(distance + 1) {
+            distance += 1;
+        }
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, GameEvent, IPoint, Mino, Ori};
+
+    fn board(rows: &[&str]) -> Board {
+        Board::from_rows(rows).unwrap()
+    }
+
+    fn sorted(mut cells: Vec<Point>) -> Vec<Point> {
+        cells.sort_by_key(|p| (p.y, p.x));
+        cells
+    }
+
+    fn points(cells: &[(u8, u8)]) -> Vec<Point> {
+        sorted(cells.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    #[test]
+    fn groups_join_by_sides_only() {
+        let board = board(&["T.........", "T...OO....", ".X..OO...I", "XXX......I"]);
+        let groups: Vec<_> = board.groups().into_iter().map(sorted).collect();
+        assert_eq!(
+            groups,
+            [
+                points(&[(0, 20), (0, 21)]),
+                points(&[(4, 21), (5, 21), (4, 22), (5, 22)]),
+                points(&[(1, 22), (0, 23), (1, 23), (2, 23)]),
+                points(&[(9, 22), (9, 23)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn overhangs_fall_as_one() {
+        let mut board = board(&[".LLL......", ".L........", "..........", "XX........"]);
+        let falls = board.settle();
+        assert_eq!(falls.len(), 1);
+        assert_eq!(falls[0].distance, 1);
+        assert_eq!(
+            sorted(falls[0].cells.clone()),
+            points(&[(1, 20), (2, 20), (3, 20), (1, 21)])
+        );
+        // the overhang keeps its shape, still hanging over the gap
+        let settled = Board::from_rows(&[".LLL......", ".L........", "XX........"]);
+        assert_eq!(board.occupancy(), settled.unwrap().occupancy());
+    }
+
+    #[test]
+    fn lower_groups_fall_first() {
+        let mut board = board(&["S.........", "..........", "T.........", ".........."]);
+        let falls = board.settle();
+        assert_eq!(
+            falls,
+            [
+                Fall {
+                    cells: points(&[(0, 22)]),
+                    distance: 1,
+                },
+                Fall {
+                    cells: points(&[(0, 20)]),
+                    distance: 2,
+                },
+            ]
+        );
+        assert_eq!(board.block((0, 22)), Some(Block::S));
+        assert_eq!(board.block((0, 23)), Some(Block::T));
+        assert!(board.settle().is_empty());
+    }
+
+    #[test]
+    fn falls_fill_lines_that_chain() {
+        let board = board(&[
+            "....X.....",
+            "..........",
+            "..........",
+            "..........",
+            "XXXX.XXXX.",
+            "XXXXXXXXX.",
+        ]);
+        let mut game =
+            Game::with_board(board, &[Block::I], None, 0, crate::time::DEFAULT_TICK_RATE);
+        game.cascade = true;
+        // upright over the right column
+        game.mino = Mino {
+            ori: Ori::Left,
+            pos: IPoint::new(7, 10),
+            block: Block::I,
+            points: game.pieces.points(Block::I, Ori::Left),
+        };
+        game.apply_action(crate::Action::Place, true);
+
+        let chains: Vec<_> = game
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                GameEvent::CascadeFell { chain, falls } => {
+                    Some((chain, falls.iter().map(|f| f.distance).collect::<Vec<_>>()))
+                }
+                GameEvent::ChainCleared { chain, rows } => Some((chain, vec![rows.len() as u8])),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            chains,
+            [
+                // the bottom line's sides & the floating block
+                (1, vec![1, 1, 5]),
+                // which filled the bottom line again
+                (2, vec![1]),
+                // leaving what's left of the I
+                (2, vec![1]),
+            ]
+        );
+        assert_eq!(game.stats().lines(), 2);
+        let left = Board::from_rows(&[".........I", ".........I"]).unwrap();
+        assert_eq!(game.board.occupancy(), left.occupancy());
+    }
+}
@@ -0,0 +1,162 @@
+// NOTE: garbage received waits in a queue, where the receiver's own clears
+// can cancel it, until its delay is up. The next lock without a clear then
+// puts the ready garbage in, at most the cap's worth of rows at once, the rest
+// waiting for the lock after.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+
+use super::{Block, Board, CellMeta, Line, Point, BOARD_WIDTH};
+
+/// How garbage is exchanged in versus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VersusRules {
+    /// The chance of each garbage row's hole moving from the one before,
+    /// from 0 for a clean column to 1 for a new hole every row
+    pub messiness: f32,
+    /// How long received garbage waits, cancellable, before it can be put in,
+    /// in milliseconds
+    pub delay: u32,
+    /// The most rows of garbage put in at once
+    pub cap: u32,
+    /// Whether there's an opponent to send attack to, solo games' clears
+    /// only cancelling what they receive
+    pub opponent: bool,
+}
+
+impl Default for VersusRules {
+    fn default() -> Self {
+        Self {
+            messiness: 0.0,
+            delay: 500,
+            cap: 8,
+            opponent: false,
+        }
+    }
+}
+
+/// Garbage received in one go, waiting to be put in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingGarbage {
+    pub rows: u32,
+    /// The active play time it was received at
+    pub at: Duration,
+}
+
+/// The garbage waiting to be put in, oldest first
+#[derive(Debug, Clone)]
+pub struct GarbageQueue {
+    pending: VecDeque<PendingGarbage>,
+    /// The column of the last row's hole
+    hole: u8,
+    /// Kept apart from the bag's, so garbage doesn't change the minos dealt
+    rng: Xoshiro256Plus,
+}
+
+impl GarbageQueue {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+        // a stream of its own, apart from the bag's of the same seed
+        rng.jump();
+        Self {
+            pending: VecDeque::new(),
+            hole: rng.random_range(0..BOARD_WIDTH),
+            rng,
+        }
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &PendingGarbage> + '_ {
+        self.pending.iter()
+    }
+
+    /// The rows of garbage waiting, ready or not
+    pub fn total(&self) -> u32 {
+        self.pending.iter().map(|garbage| garbage.rows).sum()
+    }
+
+    pub fn push(&mut self, rows: u32, at: Duration) {
+        if rows != 0 {
+            self.pending.push_back(PendingGarbage { rows, at });
+        }
+    }
+
+    /// Cancels garbage with an attack, oldest first
+    ///
+    /// Returns what's left of the attack.
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while let Some(garbage) = self.pending.front_mut() {
+            if attack == 0 {
+                break;
+            }
+            let cancelled = garbage.rows.min(attack);
+            garbage.rows -= cancelled;
+            attack -= cancelled;
+            if garbage.rows == 0 {
+                self.pending.pop_front();
+            }
+        }
+        attack
+    }
+
+    /// Whether garbage received at `at` has waited out its delay by `now`
+    pub fn is_ready(garbage: &PendingGarbage, now: Duration, rules: &VersusRules) -> bool {
+        now.saturating_sub(garbage.at) >= Duration::from_millis(rules.delay.into())
+    }
+
+    /// Takes the ready garbage, up to the cap, oldest first
+    fn take_ready(&mut self, now: Duration, rules: &VersusRules) -> u32 {
+        let mut taken = 0;
+        while let Some(garbage) = self.pending.front_mut() {
+            if taken == rules.cap || !Self::is_ready(garbage, now, rules) {
+                break;
+            }
+            let rows = garbage.rows.min(rules.cap - taken);
+            garbage.rows -= rows;
+            taken += rows;
+            if garbage.rows == 0 {
+                self.pending.pop_front();
+            }
+        }
+        taken
+    }
+
+    /// Puts the ready garbage in at the bottom of the board
+    ///
+    /// Returns the rows put in & whether any blocks were pushed off the top.
+    pub fn insert(&mut self, board: &mut Board, now: Duration, rules: &VersusRules) -> (u32, bool) {
+        let rows = self.take_ready(now, rules);
+        let mut pushed_out = false;
+        for _ in 0..rows {
+            if self.rng.random::<f32>() < rules.messiness {
+                // any column but the last hole's
+                let hole = self.rng.random_range(0..BOARD_WIDTH - 1);
+                self.hole = hole + (hole >= self.hole) as u8;
+            }
+            pushed_out |= board.push_garbage(self.hole, CellMeta { placed: now });
+        }
+        (rows, pushed_out)
+    }
+}
+
+impl Board {
+    /// Raises the board a row, filling the bottom one but for its `hole`
+    ///
+    /// Returns whether the top row had blocks, pushed off the board. Garbage
+    /// is drawn like a puzzle's filled cells.
+    pub fn push_garbage(&mut self, hole: u8, meta: CellMeta) -> bool {
+        let pushed_out = self.occupancy[0] != 0;
+        self.lines.copy_within(1.., 0);
+        self.occupancy.copy_within(1.., 0);
+        let bottom = self.lines.len() - 1;
+        self.lines[bottom] = Line::default();
+        self.occupancy[bottom] = 0;
+        for x in (0..BOARD_WIDTH).filter(|&x| x != hole) {
+            let point = Point::new(x, bottom as u8);
+            self.set_cell(point, Some(Block::Z), meta);
+        }
+        pushed_out
+    }
+}
@@ -0,0 +1,227 @@
+use super::{IPoint, Ori, Point, MINO_POINTS};
+
+/// The most cells a piece can have
+pub const MAX_CELLS: usize = 6;
+/// The palette's block colours a piece can be drawn with
+pub const COLOURS: u8 = 7;
+
+/// A piece of a [`PieceSet`], or a cell left by one
+///
+/// The colour is kept alongside the piece's index, so a board's cells can be
+/// drawn without the set they came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Block {
+    piece: u8,
+    colour: u8,
+}
+
+impl Block {
+    /// cyan
+    pub const I: Block = Block::new(0, 0);
+    /// magenta
+    pub const T: Block = Block::new(1, 5);
+    /// yellow
+    pub const O: Block = Block::new(2, 3);
+    /// orange
+    pub const L: Block = Block::new(3, 2);
+    /// blue
+    pub const J: Block = Block::new(4, 1);
+    /// green
+    pub const S: Block = Block::new(5, 4);
+    /// red
+    pub const Z: Block = Block::new(6, 6);
+
+    const fn new(piece: u8, colour: u8) -> Self {
+        Self { piece, colour }
+    }
+
+    /// Parses a block of the standard set from its letter, e.g. `T`
+    pub fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            'I' => Block::I,
+            'J' => Block::J,
+            'L' => Block::L,
+            'O' => Block::O,
+            'S' => Block::S,
+            'T' => Block::T,
+            'Z' => Block::Z,
+            _ => return None,
+        })
+    }
+
+    /// The index of the piece in its set
+    pub fn index(self) -> usize {
+        self.piece as usize
+    }
+
+    /// Which of the palette's block colours it's drawn with, see
+    /// [`Piece::colour`]
+    pub fn colour(self) -> usize {
+        self.colour as usize
+    }
+}
+
+/// The cells of a piece in one orientation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Cells {
+    points: [Point; MAX_CELLS],
+    len: u8,
+}
+
+impl Cells {
+    /// Panics with more than [`MAX_CELLS`] points
+    pub fn new(points: &[Point]) -> Self {
+        assert!(
+            points.len() <= MAX_CELLS,
+            "pieces have at most {MAX_CELLS} cells"
+        );
+        let mut cells = Self {
+            len: points.len() as u8,
+            ..Default::default()
+        };
+        cells.points[..points.len()].copy_from_slice(points);
+        cells
+    }
+
+    /// The cells moved by `offset`, `None` when any leaves the board's corner
+    pub fn offset(self, offset: IPoint) -> Option<Self> {
+        let mut cells = self;
+        for p in &mut cells.points[..self.len as usize] {
+            let moved = (*p + offset)?;
+            *p = Point::new(u8::try_from(moved.x).ok()?, u8::try_from(moved.y).ok()?);
+        }
+        Some(cells)
+    }
+}
+
+impl std::ops::Deref for Cells {
+    type Target = [Point];
+
+    fn deref(&self) -> &Self::Target {
+        &self.points[..self.len as usize]
+    }
+}
+
+impl IntoIterator for Cells {
+    type Item = Point;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Point, MAX_CELLS>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.into_iter().take(self.len as usize)
+    }
+}
+
+/// The wallkicks tested when a piece rotates
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kicks {
+    /// The SRS kicks of J, L, O, S, T & Z
+    #[default]
+    Standard,
+    /// The SRS kicks of I
+    I,
+    /// Rotating in place or not at all
+    None,
+}
+
+/// How a piece looks & moves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    /// What the piece is written as, e.g. in puzzle queues
+    pub name: char,
+    /// Which of the palette's block colours it's drawn with, in the order
+    /// I, J, L, O, S, T & Z, below [`COLOURS`]
+    pub colour: u8,
+    /// The cells of each orientation, see [`Ori`]
+    pub rotations: [Cells; 4],
+    /// Where the piece's top left spawns on the board
+    pub spawn: IPoint,
+    pub kicks: Kicks,
+}
+
+/// The pieces a game is played with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceSet {
+    pieces: Vec<Piece>,
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl PieceSet {
+    /// Panics without pieces, with more than 255 or with a colour past
+    /// [`COLOURS`]
+    pub fn new(pieces: Vec<Piece>) -> Self {
+        assert!(!pieces.is_empty(), "a piece set needs pieces");
+        assert!(
+            pieces.len() <= u8::MAX as usize,
+            "a piece set has at most 255 pieces"
+        );
+        assert!(
+            pieces.iter().all(|piece| piece.colour < COLOURS),
+            "pieces are drawn with one of {COLOURS} colours"
+        );
+        Self { pieces }
+    }
+
+    /// The seven tetrominoes
+    pub fn standard() -> Self {
+        // the order shuffled from, so seeds keep their sequences
+        let pieces = [
+            ('I', 0),
+            ('T', 5),
+            ('O', 3),
+            ('L', 2),
+            ('J', 1),
+            ('S', 4),
+            ('Z', 6),
+        ]
+        .map(|(name, colour)| Piece {
+            name,
+            colour,
+            rotations: MINO_POINTS[colour as usize].map(|points| Cells::new(&points)),
+            spawn: IPoint::new(3, 3),
+            kicks: if name == 'I' {
+                Kicks::I
+            } else {
+                Kicks::Standard
+            },
+        });
+        Self::new(pieces.into())
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Every piece as a block, in the set's order
+    pub fn blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        (0..)
+            .zip(&self.pieces)
+            .map(|(i, piece)| Block::new(i, piece.colour))
+    }
+
+    /// Finds a piece by its name
+    pub fn block(&self, name: char) -> Option<Block> {
+        self.blocks().find(|&block| self.piece(block).name == name)
+    }
+
+    /// Panics for a block of another set
+    pub fn piece(&self, block: Block) -> &Piece {
+        &self.pieces[block.index()]
+    }
+
+    pub fn points(&self, block: Block, ori: Ori) -> Cells {
+        self.piece(block).rotations[ori as usize]
+    }
+}
@@ -0,0 +1,122 @@
+// NOTE: a puzzle file is toml, e.g.
+//
+// name = "Tuck"
+// queue = "TIO"
+// hold = "L"
+// objective = { kind = "lines", lines = 2 }
+// board = """
+// XXX....XXX
+// XXXX.XXXXX
+// """
+//
+// Puzzles are played in the order of their file names.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{Block, Board, Stats};
+
+/// What a puzzle has to be solved with, before its queue runs out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Objective {
+    /// Clear at least this many lines
+    Lines { lines: u32 },
+    /// Leave the board empty after clearing lines
+    PerfectClear,
+}
+
+impl Objective {
+    pub fn is_met(self, board: &Board, stats: &Stats) -> bool {
+        match self {
+            Objective::Lines { lines } => stats.lines() >= lines,
+            Objective::PerfectClear => {
+                stats.lines() != 0 && board.occupancy().iter().all(|&row| row == 0)
+            }
+        }
+    }
+}
+
+/// How a puzzle ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Solved,
+    /// Topped out or out of minos
+    Failed,
+}
+
+/// A position to solve with a fixed queue
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    /// Also what the puzzle's completion is recorded as
+    pub name: String,
+    pub board: Board,
+    /// Every mino the puzzle is played with, the first being the current one
+    pub queue: Vec<Block>,
+    pub hold: Option<Block>,
+    pub objective: Objective,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuzzleFile {
+    name: Option<String>,
+    board: String,
+    queue: String,
+    hold: Option<char>,
+    objective: Objective,
+}
+
+impl Puzzle {
+    /// Parses a puzzle file, named `name` unless it names itself
+    pub fn parse(name: &str, text: &str) -> anyhow::Result<Self> {
+        let file: PuzzleFile = toml::from_str(text)?;
+        let rows: Vec<&str> = file.board.lines().filter(|row| !row.is_empty()).collect();
+        let block = |c| Block::from_char(c).with_context(|| format!("unknown mino {c:?}"));
+        let queue = file
+            .queue
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(block)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(!queue.is_empty(), "the queue is empty");
+        Ok(Self {
+            name: file.name.unwrap_or_else(|| name.into()),
+            board: Board::from_rows(&rows)?,
+            queue,
+            hold: file.hold.map(block).transpose()?,
+            objective: file.objective,
+        })
+    }
+}
+
+/// Where puzzles are looked for when no other directory is given
+pub fn default_dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("wgputris").join("puzzles"))
+}
+
+/// Loads every `.toml` puzzle in a directory, in order of their file names
+///
+/// Puzzles that fail to load are skipped & logged.
+pub fn load_dir(dir: &Path) -> anyhow::Result<Vec<Puzzle>> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    let puzzles = paths
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy();
+            let puzzle = std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| Puzzle::parse(&name, &text));
+            puzzle
+                .inspect_err(|err| log::error!("skipping puzzle {}: {err:#}", path.display()))
+                .ok()
+        })
+        .collect();
+    Ok(puzzles)
+}
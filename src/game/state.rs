@@ -0,0 +1,277 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{
+    Block, Board, BoardDims, CellMeta, Game, GameEvent, GarbageQueue, IPoint, LineClear, Mino,
+    Mode, Motion, Objective, Outcome, Phase, PieceSet, Rotate180, Stats, VersusRules,
+};
+use crate::styling::layout::NEXT_COUNT;
+use crate::time::Instant;
+
+/// The snapshots built so far, of every game
+static VERSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// A game, with what's drawn of it kept apart
+///
+/// The game thread updates the game & regenerates the snapshot once per
+/// batch of ticks, so drawing only holds the lock long enough to clone an
+/// [`Arc`].
+#[derive(Debug)]
+pub struct GameState {
+    game: Game,
+    /// Events since the last [`GameState::take_events`]
+    events: Vec<GameEvent>,
+    /// Whether anything drawn changed since the last [`GameState::update`]
+    changed: bool,
+    snapshot: Arc<RenderSnapshot>,
+}
+
+impl GameState {
+    pub fn new(game: Game) -> Self {
+        let snapshot = Arc::new(RenderSnapshot::new(&game));
+        Self {
+            game,
+            events: Vec::new(),
+            changed: true,
+            snapshot,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The game, its snapshot only catching up on [`GameState::update`]
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Applies an action, see [`Game::apply_action`]
+    pub fn apply_action(&mut self, action: crate::Action, pressed: bool) -> bool {
+        let changed = self.game.apply_action(action, pressed);
+        self.changed |= changed;
+        changed
+    }
+
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let changed = self.game.tick(now);
+        self.changed |= changed;
+        changed
+    }
+
+    /// Regenerates the snapshot & collects the game's events
+    ///
+    /// Returns whether anything drawn changed since the last update.
+    pub fn update(&mut self) -> bool {
+        let events = self.game.take_events();
+        let changed = std::mem::take(&mut self.changed) || !events.is_empty();
+        self.events.extend(events);
+        self.snapshot = Arc::new(RenderSnapshot {
+            version: VERSIONS.fetch_add(1, Ordering::Relaxed) + 1,
+            ..RenderSnapshot::new(&self.game)
+        });
+        changed
+    }
+
+    pub fn snapshot(&self) -> Arc<RenderSnapshot> {
+        self.snapshot.clone()
+    }
+
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn dims(&self) -> BoardDims {
+        self.game.dims()
+    }
+}
+
+/// Everything drawn of a game at one point in time
+///
+/// The accessors mirror those of [`Game`].
+#[derive(Debug, Clone)]
+pub struct RenderSnapshot {
+    version: u64,
+    pieces: Arc<PieceSet>,
+    board: Board,
+    mino: Mino,
+    ghost: Mino,
+    prev_pos: IPoint,
+    /// The next queue, only the first `next_len` of which are there
+    next: [Block; NEXT_COUNT as usize],
+    next_len: usize,
+    held: Option<Block>,
+    can_hold: bool,
+    last_clear: Option<LineClear>,
+    active_elapsed: Duration,
+    fade: Option<Duration>,
+    motion: Option<Motion>,
+    tick_duration: Duration,
+    garbage: GarbageQueue,
+    versus: VersusRules,
+    phase: Phase,
+    countdown: Option<Duration>,
+    counted_down: bool,
+    over: bool,
+    stats: Stats,
+    seed: u64,
+    mode: Mode,
+    rotate_180: Rotate180,
+    objective: Option<Objective>,
+    outcome: Option<Outcome>,
+    pieces_left: Option<usize>,
+    dims: BoardDims,
+}
+
+impl RenderSnapshot {
+    pub fn new(game: &Game) -> Self {
+        let bag = game.bag();
+        let mut next = [Block::I; NEXT_COUNT as usize];
+        let mut next_len = 0;
+        for (slot, block) in next.iter_mut().zip(bag.preview(NEXT_COUNT as usize)) {
+            *slot = block;
+            next_len += 1;
+        }
+        Self {
+            version: 0,
+            pieces: game.pieces().clone(),
+            board: game.board,
+            mino: game.mino(),
+            ghost: game.ghost(),
+            prev_pos: game.prev_pos(),
+            next,
+            next_len,
+            held: bag.held(),
+            can_hold: bag.can_hold(),
+            last_clear: game.last_clear().cloned(),
+            active_elapsed: game.active_elapsed(),
+            fade: game.fade(),
+            motion: game.motion(),
+            tick_duration: game.tick_duration(),
+            garbage: game.garbage().clone(),
+            versus: *game.versus_rules(),
+            phase: game.phase(),
+            countdown: game.countdown(),
+            counted_down: game.counted_down(),
+            over: game.is_over(),
+            stats: *game.stats(),
+            seed: game.seed(),
+            mode: game.mode(),
+            rotate_180: game.rotate_180(),
+            objective: game.objective(),
+            outcome: game.outcome(),
+            // the current mino is left too, unless it's been placed
+            pieces_left: bag.remaining().map(|left| left + !game.is_over() as usize),
+            dims: game.dims(),
+        }
+    }
+
+    /// Counts up with every snapshot built, across games, 0 before a game's
+    /// first update
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    pub fn blocks(&self, y: u8) -> impl Iterator<Item = Option<Block>> + '_ {
+        self.board.lines()[y as usize].blocks().iter().copied()
+    }
+    pub fn cell_meta(&self, y: u8) -> impl Iterator<Item = CellMeta> + '_ {
+        self.board.lines()[y as usize].meta().iter().copied()
+    }
+    /// How visible a placed block is, from 1 when placed to 0 once faded
+    pub fn visibility(&self, meta: CellMeta) -> f32 {
+        let Some(fade) = self.fade else {
+            return 1.0;
+        };
+        let since = self.active_elapsed.saturating_sub(meta.placed);
+        1.0 - (since.as_secs_f32() / fade.as_secs_f32()).min(1.0)
+    }
+    /// Whether any placed block is still fading
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+            && self.board.lines().iter().any(|line| {
+                let mut cells = line.blocks().iter().zip(line.meta());
+                cells.any(|(b, &meta)| b.is_some() && self.visibility(meta) > 0.0)
+            })
+    }
+    pub fn fade(&self) -> Option<Duration> {
+        self.fade
+    }
+    pub fn motion(&self) -> Option<Motion> {
+        self.motion
+    }
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+    pub fn garbage(&self) -> &GarbageQueue {
+        &self.garbage
+    }
+    pub fn versus_rules(&self) -> &VersusRules {
+        &self.versus
+    }
+    pub fn pieces(&self) -> &PieceSet {
+        &self.pieces
+    }
+    pub fn mino(&self) -> Mino {
+        self.mino
+    }
+    pub fn ghost(&self) -> Mino {
+        self.ghost
+    }
+    pub fn prev_pos(&self) -> IPoint {
+        self.prev_pos
+    }
+    /// The upcoming minos, next first
+    pub fn next(&self) -> &[Block] {
+        &self.next[..self.next_len]
+    }
+    pub fn held(&self) -> Option<Block> {
+        self.held
+    }
+    pub fn can_hold(&self) -> bool {
+        self.can_hold
+    }
+    pub fn last_clear(&self) -> Option<&LineClear> {
+        self.last_clear.as_ref()
+    }
+    pub fn active_elapsed(&self) -> Duration {
+        self.active_elapsed
+    }
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+    pub fn countdown(&self) -> Option<Duration> {
+        self.countdown
+    }
+    pub fn counted_down(&self) -> bool {
+        self.counted_down
+    }
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn rotate_180(&self) -> Rotate180 {
+        self.rotate_180
+    }
+    pub fn objective(&self) -> Option<Objective> {
+        self.objective
+    }
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+    /// The minos left to place in a puzzle, not counting the held one
+    pub fn pieces_left(&self) -> Option<usize> {
+        self.pieces_left
+    }
+    pub fn dims(&self) -> BoardDims {
+        self.dims
+    }
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use super::GameEvent;
+
+/// Points for clearing 0 to 4 lines at once, multiplied by the level
+const LINE_CLEAR_POINTS: [u32; 5] = [0, 100, 300, 500, 800];
+/// Points for each row soft dropped
+const SOFT_DROP_POINTS: u32 = 1;
+/// The lines cleared to go up a level
+pub const LINES_PER_LEVEL: u32 = 10;
+
+/// How a game has gone so far
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    score: u32,
+    lines: u32,
+    pieces: u32,
+    /// Rows moved down by soft drop, gravity not counting
+    soft_dropped: u32,
+    /// Garbage rows of attack, sent or spent cancelling
+    attack: u32,
+    received: u32,
+    cancelled: u32,
+    /// The locks in a row that cleared lines, 0 once one doesn't
+    streak: u32,
+    /// Whether the last clear was a tetris
+    tetris: bool,
+    /// Whether the last clear was a tetris right after another
+    back_to_back: bool,
+}
+
+impl Stats {
+    /// Counts a locked piece & the lines it cleared
+    pub fn lock(&mut self, lines: u32) {
+        let points = LINE_CLEAR_POINTS[lines.min(4) as usize];
+        self.score = self.score.saturating_add(points * self.level());
+        self.lines += lines;
+        self.pieces += 1;
+        if lines == 0 {
+            self.streak = 0;
+            return;
+        }
+        self.streak += 1;
+        self.back_to_back = lines >= 4 && self.tetris;
+        self.tetris = lines >= 4;
+    }
+
+    /// Counts the lines cleared by a cascade's `chain`th clear, the points
+    /// multiplied by the chain
+    pub fn chain(&mut self, lines: u32, chain: u32) {
+        let points = LINE_CLEAR_POINTS[lines.min(4) as usize] * chain;
+        self.score = self.score.saturating_add(points * self.level());
+        self.lines += lines;
+    }
+
+    /// Counts rows the player soft dropped
+    pub fn soft_drop(&mut self, cells: u32) {
+        self.score = self.score.saturating_add(cells * SOFT_DROP_POINTS);
+        self.soft_dropped += cells;
+    }
+
+    /// Counts a garbage event, see [`GameEvent`]
+    pub fn garbage(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::GarbageSent { rows } => self.attack += rows,
+            GameEvent::GarbageCancelled { rows } => {
+                self.attack += rows;
+                self.cancelled += rows;
+            }
+            GameEvent::GarbageReceived { rows } => self.received += rows,
+            _ => (),
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    pub fn pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    pub fn soft_dropped(&self) -> u32 {
+        self.soft_dropped
+    }
+
+    pub fn attack(&self) -> u32 {
+        self.attack
+    }
+
+    /// The attack that wasn't spent cancelling
+    pub fn sent(&self) -> u32 {
+        self.attack - self.cancelled
+    }
+
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+
+    /// Received garbage cancelled by attack
+    pub fn cancelled(&self) -> u32 {
+        self.cancelled
+    }
+
+    /// The clearing locks in a row before the last, 0 unless the last
+    /// cleared lines
+    pub fn combo(&self) -> u32 {
+        self.streak.saturating_sub(1)
+    }
+
+    /// Whether the last clear was a tetris right after another
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    /// Starts at 1, going up every [`LINES_PER_LEVEL`] lines
+    pub fn level(&self) -> u32 {
+        self.lines / LINES_PER_LEVEL + 1
+    }
+
+    /// Pieces per second over the given play time
+    pub fn pps(&self, elapsed: Duration) -> f32 {
+        let secs = elapsed.as_secs_f32();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.pieces as f32 / secs
+    }
+
+    /// Attack per minute over the given play time
+    pub fn apm(&self, elapsed: Duration) -> f32 {
+        let secs = elapsed.as_secs_f32();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.attack as f32 * 60.0 / secs
+    }
+}
@@ -0,0 +1,198 @@
+// NOTE: headless rendering draws a game without a window, e.g. to compare
+// frames against golden images. Only the board's quad layers are drawn, as
+// text depends on the fonts installed, & blocks are drawn flat, there being no
+// skin.
+
+use crate::{draw, game, rend, styling, time};
+
+/// A renderer drawing games to images
+#[derive(Debug)]
+pub struct Headless {
+    rend: rend::Rend,
+}
+
+impl Headless {
+    /// Draws images of `width` by `height`, see [`rend::Rend::new_headless`]
+    pub fn new(width: u32, height: u32) -> anyhow::Result<Self> {
+        let mut rend = rend::Rend::new_headless(width, height)?;
+        for name in crate::QUAD_LAYERS {
+            rend.gen_quad_layer(name);
+        }
+        Ok(Self { rend })
+    }
+
+    /// Draws the game as it is between ticks
+    ///
+    /// `settings` should be sized to the image, see [`styling::Sizing::resize`].
+    pub fn render_to_image(
+        &mut self,
+        game: &game::Game,
+        settings: &styling::Settings,
+    ) -> anyhow::Result<rend::capture::Image> {
+        let game = game::RenderSnapshot::new(game);
+        let ctx = draw::DrawContext {
+            settings,
+            game: &game,
+            now: time::Instant::now(),
+            alpha: 1.0,
+        };
+        if let Some(layer) = self.rend.get_quad_mut("base") {
+            draw::base_quads(&ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("grid") {
+            draw::grid_quads(&ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("game") {
+            draw::game_quads(&ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("garbage") {
+            draw::garbage_quads(&ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("next") {
+            draw::next_quads(&ctx, layer);
+        }
+        self.rend.render_to_image(settings.palette.bg.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::styling::{Colour, Palette, Settings};
+
+    // NOTE: the expected pixels come from the palette rather than checked in
+    // images, so they hold whatever the adapter. Without one, e.g. on CI
+    // without a software renderer, the tests are skipped.
+
+    fn headless(width: u32, height: u32) -> Option<Headless> {
+        match Headless::new(width, height) {
+            Ok(headless) => Some(headless),
+            Err(e) => {
+                eprintln!("skipped, nothing to draw with: {e}");
+                None
+            }
+        }
+    }
+
+    /// Fit to a 500x500 image, blocks being 20px & the board's top left at
+    /// 150, 50
+    fn settings() -> Settings {
+        let mut settings = Settings {
+            palette: Palette::light(),
+            ..Default::default()
+        };
+        settings.sizing.resize(game::BoardDims::default(), 500, 500);
+        settings
+    }
+
+    /// The middle of a cell of the board, `row` counting from the top
+    /// visible row
+    fn middle(x: u32, row: u32) -> (u32, u32) {
+        (150 + x * 20 + 10, 50 + row * 20 + 10)
+    }
+
+    #[track_caller]
+    fn assert_pixel(image: &rend::capture::Image, (x, y): (u32, u32), colour: Colour) {
+        let i = ((y * image.width + x) * 4) as usize;
+        let pixel = &image.rgba[i..i + 3];
+        let expected = colour.srgba().map(|c| (c * 255.0).round() as u8);
+        assert!(
+            pixel.iter().zip(expected).all(|(&p, e)| p.abs_diff(e) <= 2),
+            "{pixel:?} at {x}, {y} isn't {:?}",
+            &expected[..3]
+        );
+    }
+
+    #[test]
+    fn golden_board() {
+        let Some(mut headless) = headless(500, 500) else {
+            return;
+        };
+        let settings = settings();
+        let board = game::Board::from_rows(&["T.........", "XX........"]).unwrap();
+        let game =
+            game::Game::with_board(board, &[game::Block::O], None, 0, time::DEFAULT_TICK_RATE);
+        let image = headless.render_to_image(&game, &settings).unwrap();
+        assert_eq!((image.width, image.height), (500, 500));
+        assert_eq!(image.rgba.len(), 500 * 500 * 4);
+
+        let palette = &settings.palette;
+        let colour = |block| palette.colour_block(Some(block));
+        assert_pixel(&image, middle(0, 18), colour(game::Block::T));
+        assert_pixel(&image, middle(0, 19), colour(game::Block::Z));
+        assert_pixel(&image, middle(1, 19), colour(game::Block::Z));
+        // the O at the top, with its ghost outlined on the floor
+        assert_pixel(&image, middle(4, 0), colour(game::Block::O));
+        assert_pixel(&image, middle(5, 0), colour(game::Block::O));
+        assert_pixel(&image, middle(4, 19), palette.e);
+        assert_pixel(&image, middle(5, 18), palette.e);
+        let (x, y) = middle(4, 19);
+        assert_pixel(&image, (x - 9, y), colour(game::Block::O));
+        // the rest of the board is empty, around it the background
+        assert_pixel(&image, middle(2, 19), palette.e);
+        assert_pixel(&image, middle(9, 10), palette.e);
+        assert_pixel(&image, (10, 490), palette.bg);
+    }
+
+    #[test]
+    fn grid_lines_are_drawn() {
+        let Some(mut headless) = headless(500, 500) else {
+            return;
+        };
+        let settings = Settings {
+            grid: true,
+            ..settings()
+        };
+        let game = game::Game::with_board(
+            game::Board::default(),
+            &[game::Block::O],
+            None,
+            0,
+            time::DEFAULT_TICK_RATE,
+        );
+        let image = headless.render_to_image(&game, &settings).unwrap();
+        let palette = &settings.palette;
+        // empty cells are left to the board's base, lined by the grid
+        assert_pixel(&image, middle(2, 10), palette.bg2);
+        // the lines are see through, blended over the base
+        let grid = palette
+            .bg2
+            .lerp(palette.grid.with_alpha(1.0), palette.grid.a);
+        assert_pixel(&image, (170, 255), grid);
+        assert_pixel(&image, (165, 250), grid);
+    }
+
+    /// The vertex fallback draws the same frame as instancing, bevels,
+    /// borders, outlines & the grid included
+    #[test]
+    fn quad_modes_draw_alike() {
+        let Some(mut headless) = headless(500, 500) else {
+            return;
+        };
+        let mut settings = Settings {
+            grid: true,
+            block_bevel: 0.5,
+            ..settings()
+        };
+        settings.sizing.border_width = 2;
+        let board = game::Board::from_rows(&["T.....I...", "TT..SSI.OO", "TXXSS.IXOO"]).unwrap();
+        let game =
+            game::Game::with_board(board, &[game::Block::L], None, 0, time::DEFAULT_TICK_RATE);
+        assert_eq!(headless.rend.quad_mode(), rend::QuadMode::Instanced);
+        let instanced = headless.render_to_image(&game, &settings).unwrap();
+        headless.rend.set_quad_mode(rend::QuadMode::Vertices);
+        let vertices = headless.render_to_image(&game, &settings).unwrap();
+        assert_pixel(
+            &instanced,
+            middle(1, 18),
+            settings.palette.colour_block(Some(game::Block::T)),
+        );
+        let differing = instanced
+            .rgba
+            .chunks(4)
+            .zip(vertices.rgba.chunks(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > 1))
+            .count();
+        assert_eq!(differing, 0, "{differing} pixels differ");
+    }
+}
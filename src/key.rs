@@ -1,63 +1,338 @@
-use dashmap::{DashMap, DashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use winit::event::KeyEvent;
 
 use crate::Action;
 
+// NOTE: a bind without modifiers is a wildcard, matching its key whatever's
+// held with it, while a chord like Shift+R only matches with exactly those
+// modifiers, winning over the wildcard. Keys held keep the action they were
+// pressed as, so a release with other modifiers held still lets go of it.
+
+/// The keys bound to actions & those held
+///
+/// Clones share their bindings, so a preset applied on the main thread
+/// reaches the game thread's next key, but each keeps the keys it has held.
 #[derive(Debug, Clone)]
 pub struct KeyStore {
-    keys: DashMap<Key, Action, ahash::RandomState>,
-    pressed: DashSet<Key, ahash::RandomState>,
+    keys: Arc<DashMap<Chord, Action, ahash::RandomState>>,
+    /// The user's own binds, kept for switching back to [`Preset::Custom`]
+    custom: Arc<Mutex<Vec<(Chord, Action)>>>,
+    /// Counts the changes to the binds, so what's shown of them is only
+    /// looked up again when they change
+    version: Arc<AtomicU64>,
+    /// The keys held & the actions they were pressed as
+    pressed: DashMap<Key, Action, ahash::RandomState>,
 }
 
 impl Default for KeyStore {
     fn default() -> Self {
+        Self::from_binds(default_binds())
+    }
+}
+
+/// The keys bound to actions out of the box, the same as [`Preset::Guideline`]
+pub fn default_binds() -> Vec<(Chord, Action)> {
+    use winit::keyboard::KeyCode;
+    use Action::*;
+    codes([
+        (KeyCode::KeyC, Hold),
+        (KeyCode::Space, Place),
+        (KeyCode::ArrowUp, RotateRight),
+        (KeyCode::KeyA, Rotate180),
+        (KeyCode::KeyZ, RotateLeft),
+        (KeyCode::KeyX, RotateRight),
+        (KeyCode::ArrowRight, MoveRight),
+        (KeyCode::ArrowLeft, MoveLeft),
+        (KeyCode::ArrowDown, MoveDown),
+        (KeyCode::Escape, Back),
+    ])
+}
+
+fn codes<const N: usize>(keys: [(winit::keyboard::KeyCode, Action); N]) -> Vec<(Chord, Action)> {
+    keys.into_iter()
+        .map(|(kc, a)| (Key::Code(kc).into(), a))
+        .collect()
+}
+
+// NOTE: every preset binds every action but quitting, which is always Ctrl+Q
+// or Cmd+Q, & the guideline preset leaves sonic drop unbound as the default
+// binds do.
+
+/// A set of binds to play with, switched between in the settings
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Up & X rotate clockwise, Z counter-clockwise & space hard drops
+    Guideline,
+    /// Shift holds & S sonic drops, leaving the rest as the guideline
+    Jstris,
+    /// Up hard drops, X & Z rotate & space sonic drops
+    Classic,
+    /// The binds in the config file
+    #[default]
+    Custom,
+}
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Guideline => "Guideline",
+            Preset::Jstris => "Jstris",
+            Preset::Classic => "Classic",
+            Preset::Custom => "Custom",
+        }
+    }
+
+    /// The preset after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Preset::Guideline => Preset::Jstris,
+            Preset::Jstris => Preset::Classic,
+            Preset::Classic => Preset::Custom,
+            Preset::Custom => Preset::Guideline,
+        }
+    }
+
+    /// The preset's binds, `None` for the user's own
+    pub fn binds(self) -> Option<Vec<(Chord, Action)>> {
         use winit::keyboard::KeyCode;
         use Action::*;
-        let keys = [
-            (KeyCode::KeyC, Hold),
-            (KeyCode::Space, Place),
-            (KeyCode::ArrowUp, Rotate180),
-            (KeyCode::KeyZ, RotateLeft),
-            (KeyCode::KeyX, RotateRight),
-            (KeyCode::ArrowRight, MoveRight),
-            (KeyCode::ArrowLeft, MoveLeft),
-            (KeyCode::ArrowDown, MoveDown),
-            (KeyCode::Escape, Exit),
-        ];
-        Self {
-            keys: keys.into_iter().map(|(kc, a)| (Key::Code(kc), a)).collect(),
-            pressed: Default::default(),
+        match self {
+            Preset::Guideline => Some(default_binds()),
+            Preset::Jstris => Some(codes([
+                (KeyCode::KeyC, Hold),
+                (KeyCode::ShiftLeft, Hold),
+                (KeyCode::Space, Place),
+                (KeyCode::KeyS, SonicDrop),
+                (KeyCode::ArrowUp, RotateRight),
+                (KeyCode::KeyA, Rotate180),
+                (KeyCode::KeyZ, RotateLeft),
+                (KeyCode::ArrowRight, MoveRight),
+                (KeyCode::ArrowLeft, MoveLeft),
+                (KeyCode::ArrowDown, MoveDown),
+                (KeyCode::Escape, Back),
+            ])),
+            Preset::Classic => Some(codes([
+                (KeyCode::KeyC, Hold),
+                (KeyCode::ArrowUp, Place),
+                (KeyCode::Space, SonicDrop),
+                (KeyCode::KeyX, RotateRight),
+                (KeyCode::KeyS, Rotate180),
+                (KeyCode::KeyZ, RotateLeft),
+                (KeyCode::ArrowRight, MoveRight),
+                (KeyCode::ArrowLeft, MoveLeft),
+                (KeyCode::ArrowDown, MoveDown),
+                (KeyCode::Escape, Back),
+            ])),
+            Preset::Custom => None,
         }
     }
 }
 
 impl KeyStore {
-    pub fn register_key(&mut self, key: Key, action: Action) -> Option<Action> {
-        self.keys.insert(key, action)
+    pub fn from_binds(binds: impl IntoIterator<Item = (Chord, Action)>) -> Self {
+        let custom: Vec<_> = binds.into_iter().collect();
+        Self {
+            keys: Arc::new(custom.iter().copied().collect()),
+            custom: Arc::new(Mutex::new(custom)),
+            version: Arc::default(),
+            pressed: Default::default(),
+        }
+    }
+
+    /// Binds the preset's keys, keeping `custom` for [`Preset::Custom`]
+    pub fn from_preset(preset: Preset, custom: impl IntoIterator<Item = (Chord, Action)>) -> Self {
+        let store = Self::from_binds(custom);
+        store.apply_preset(preset);
+        store
+    }
+
+    /// Replaces every bind with the preset's, the user's own never being lost
+    pub fn apply_preset(&self, preset: Preset) {
+        let binds = preset
+            .binds()
+            .unwrap_or_else(|| self.custom.lock().unwrap().clone());
+        self.keys.clear();
+        for (chord, action) in binds {
+            self.keys.insert(chord, action);
+        }
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The action the chord's bound to, or its key without modifiers
+    pub fn action(&self, chord: Chord) -> Option<Action> {
+        self.keys
+            .get(&chord)
+            .or_else(|| self.keys.get(&Chord::from(chord.key)))
+            .map(|a| *a)
+    }
+
+    /// The chords bound to the action
+    pub fn keys_of(&self, action: Action) -> Vec<Chord> {
+        self.keys
+            .iter()
+            .filter(|bind| *bind.value() == action)
+            .map(|bind| *bind.key())
+            .collect()
+    }
+
+    /// Every bind in use
+    pub fn binds(&self) -> Vec<(Chord, Action)> {
+        self.keys
+            .iter()
+            .map(|bind| (*bind.key(), *bind.value()))
+            .collect()
+    }
+
+    /// Changes whenever the binds do
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
     }
+
+    /// The user's own binds, see [`Preset::Custom`]
+    pub fn custom(&self) -> Vec<(Chord, Action)> {
+        self.custom.lock().unwrap().clone()
+    }
+
+    /// Binds the chord to the action in place of its other chords, unbinding
+    /// whatever the chord was bound to
+    ///
+    /// The binds in use become the user's own, so the preset's to be switched
+    /// to [`Preset::Custom`].
+    pub fn bind(&self, chord: Chord, action: Action) {
+        let mut custom = self.custom.lock().unwrap();
+        *custom = self
+            .keys
+            .iter()
+            .map(|bind| (*bind.key(), *bind.value()))
+            .filter(|&(c, a)| c != chord && a != action)
+            .collect();
+        custom.push((chord, action));
+        custom.sort_by_key(|&(_, action)| action as u8);
+        self.keys.retain(|_, a| *a != action);
+        self.keys.insert(chord, action);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn register_key(&mut self, chord: Chord, action: Action) -> Option<Action> {
+        self.version.fetch_add(1, Ordering::Relaxed);
+        self.keys.insert(chord, action)
+    }
+    /// Whether any bound key's held
     pub fn active(&self) -> bool {
         !self.pressed.is_empty()
     }
-    pub fn apply_key(&self, key: Key, pressed: bool) -> Option<(Action, bool)> {
-        let action = if pressed {
-            self.keys
-                .get(&key)
-                .filter(|ea| ea.repeatable() || !self.pressed.contains(&key))
-                .map(|a| (*a, true))
-        } else {
-            self.pressed
-                .remove(&key)
-                .and_then(|key| (self.keys.get(&key).map(|a| (*a, false))))
-        };
-        if pressed {
-            self.pressed.insert(key);
+    /// The action pressed or let go of, a held key's release going by what it
+    /// was pressed as
+    pub fn apply_key(&self, chord: Chord, pressed: bool) -> Option<(Action, bool)> {
+        if !pressed {
+            return self.pressed.remove(&chord.key).map(|(_, a)| (a, false));
         }
-        action
+        // a held key repeats as what it was pressed as, whatever's been
+        // pressed with it since
+        if let Some(held) = self.pressed.get(&chord.key).map(|held| *held) {
+            return held.repeatable().then_some((held, true));
+        }
+        let action = self.action(chord)?;
+        self.pressed.insert(chord.key, action);
+        Some((action, true))
+    }
+    /// Every action held, as bits, see [`Action::bit`]
+    pub fn held_actions(&self) -> u16 {
+        self.pressed
+            .iter()
+            .map(|held| held.value().bit())
+            .fold(0, |bits, bit| bits | bit)
     }
     pub fn get_actions(&self) -> impl Iterator<Item = Action> + '_ {
         self.pressed
             .iter()
-            .filter_map(|k| self.keys.get(&k).filter(|a| a.repeatable()).map(|a| *a))
+            .map(|held| *held.value())
+            .filter(Action::repeatable)
+    }
+}
+
+/// Whether the event toggles fullscreen, F11 or Alt+Enter
+pub fn is_fullscreen_toggle(event: &KeyEvent, modifiers: winit::keyboard::ModifiersState) -> bool {
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    if !event.state.is_pressed() || event.repeat {
+        return false;
+    }
+    match event.physical_key {
+        PhysicalKey::Code(KeyCode::F11) => true,
+        PhysicalKey::Code(KeyCode::Enter) => modifiers.alt_key(),
+        _ => false,
+    }
+}
+
+/// Whether the event quits straight away, Ctrl+Q or Cmd+Q, with no chance
+/// to back out
+pub fn is_force_quit(event: &KeyEvent, modifiers: winit::keyboard::ModifiersState) -> bool {
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    event.state.is_pressed()
+        && !event.repeat
+        && event.physical_key == PhysicalKey::Code(KeyCode::KeyQ)
+        && (modifiers.control_key() || modifiers.super_key())
+}
+
+/// Whether the event takes a screenshot, F12
+pub fn is_screenshot(event: &KeyEvent) -> bool {
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    event.state.is_pressed()
+        && !event.repeat
+        && event.physical_key == PhysicalKey::Code(KeyCode::F12)
+}
+
+/// Whether the event opens or closes the stats window, F9
+pub fn is_stats_window(event: &KeyEvent) -> bool {
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    event.state.is_pressed()
+        && !event.repeat
+        && event.physical_key == PhysicalKey::Code(KeyCode::F9)
+}
+
+/// Keys moving through the screens outside of play
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nav {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+    /// Starts over after a game ends
+    Retry,
+}
+
+impl Nav {
+    /// The navigation of a key press, only moves repeat while held
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        use winit::keyboard::{KeyCode, PhysicalKey};
+        if !event.state.is_pressed() {
+            return None;
+        }
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return None;
+        };
+        let nav = match code {
+            KeyCode::ArrowUp => Self::Up,
+            KeyCode::ArrowDown => Self::Down,
+            KeyCode::ArrowLeft => Self::Left,
+            KeyCode::ArrowRight => Self::Right,
+            KeyCode::Enter | KeyCode::Space => Self::Select,
+            KeyCode::Escape | KeyCode::Backspace => Self::Back,
+            KeyCode::KeyR => Self::Retry,
+            _ => return None,
+        };
+        (!event.repeat || matches!(nav, Self::Up | Self::Down | Self::Left | Self::Right))
+            .then_some(nav)
     }
 }
 
@@ -65,16 +340,159 @@ impl KeyStore {
 pub struct SentKey {
     pub pressed: bool,
     pub key: Key,
+    /// The modifiers held with the key
+    pub mods: Mods,
+    /// When the window got the key
+    pub at: crate::time::Instant,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Key {
     Code(winit::keyboard::KeyCode),
     Numeric(u32),
 }
 
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use winit::keyboard::KeyCode;
+        let code = match *self {
+            Key::Code(code) => code,
+            Key::Numeric(code) => return write!(f, "Scancode {code}"),
+        };
+        let name = match code {
+            KeyCode::ShiftLeft => "Left Shift",
+            KeyCode::ShiftRight => "Right Shift",
+            KeyCode::ControlLeft => "Left Ctrl",
+            KeyCode::ControlRight => "Right Ctrl",
+            KeyCode::AltLeft => "Left Alt",
+            KeyCode::AltRight => "Right Alt",
+            KeyCode::SuperLeft => "Left Super",
+            KeyCode::SuperRight => "Right Super",
+            KeyCode::Escape => "Esc",
+            KeyCode::CapsLock => "Caps Lock",
+            KeyCode::PageUp => "Page Up",
+            KeyCode::PageDown => "Page Down",
+            KeyCode::Backquote => "`",
+            KeyCode::Minus => "-",
+            KeyCode::Equal => "=",
+            KeyCode::BracketLeft => "[",
+            KeyCode::BracketRight => "]",
+            KeyCode::Backslash => "\\",
+            KeyCode::Semicolon => ";",
+            KeyCode::Quote => "'",
+            KeyCode::Comma => ",",
+            KeyCode::Period => ".",
+            KeyCode::Slash => "/",
+            _ => return fmt::Write::write_fmt(&mut CodeName(f), format_args!("{code:?}")),
+        };
+        f.write_str(name)
+    }
+}
+
+/// Writes the name of a key code as it's debug printed, in one piece
+struct CodeName<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for CodeName<'_, '_> {
+    fn write_str(&mut self, name: &str) -> fmt::Result {
+        // e.g. `KeyA`, `Digit1`, `ArrowUp` & `Numpad1` become `A`, `1`, `Up`
+        // & `Num 1`, the rest going by their names
+        match ["Key", "Digit", "Arrow"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+        {
+            Some(name) => self.0.write_str(name),
+            None => match name.strip_prefix("Numpad") {
+                Some(name) => write!(self.0, "Num {name}"),
+                None => self.0.write_str(name),
+            },
+        }
+    }
+}
+
+/// The modifiers held with a key, as bits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Mods(u8);
+
+impl Mods {
+    pub const NONE: Mods = Mods(0);
+    pub const SHIFT: Mods = Mods(1);
+    pub const CTRL: Mods = Mods(1 << 1);
+    pub const ALT: Mods = Mods(1 << 2);
+    pub const SUPER: Mods = Mods(1 << 3);
+
+    pub fn is_empty(&self) -> bool {
+        *self == Mods::NONE
+    }
+
+    pub fn contains(self, mods: Mods) -> bool {
+        self.0 & mods.0 == mods.0
+    }
+}
+
+impl std::ops::BitOr for Mods {
+    type Output = Mods;
+
+    fn bitor(self, rhs: Mods) -> Mods {
+        Mods(self.0 | rhs.0)
+    }
+}
+
+impl From<winit::keyboard::ModifiersState> for Mods {
+    fn from(state: winit::keyboard::ModifiersState) -> Self {
+        [
+            (state.shift_key(), Mods::SHIFT),
+            (state.control_key(), Mods::CTRL),
+            (state.alt_key(), Mods::ALT),
+            (state.super_key(), Mods::SUPER),
+        ]
+        .into_iter()
+        .filter(|&(held, _)| held)
+        .fold(Mods::NONE, |mods, (_, mod_)| mods | mod_)
+    }
+}
+
+/// Each modifier followed by a `+`, to go before a key, e.g. `Ctrl+Shift+`
+impl fmt::Display for Mods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (mods, name) in [
+            (Mods::CTRL, "Ctrl+"),
+            (Mods::ALT, "Alt+"),
+            (Mods::SHIFT, "Shift+"),
+            (Mods::SUPER, "Super+"),
+        ] {
+            if self.contains(mods) {
+                f.write_str(name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A key & the modifiers held with it, see [`KeyStore::action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub key: Key,
+    pub mods: Mods,
+}
+
+impl From<Key> for Chord {
+    fn from(key: Key) -> Self {
+        Self {
+            key,
+            mods: Mods::NONE,
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.mods, self.key)
+    }
+}
+
 impl SentKey {
-    pub fn from_event(event: KeyEvent) -> Option<Self> {
+    pub fn from_event(event: KeyEvent, mods: Mods) -> Option<Self> {
         use winit::keyboard::NativeKeyCode;
         use winit::keyboard::PhysicalKey;
         let key = match event.physical_key {
@@ -88,6 +506,213 @@ impl SentKey {
         Some(Self {
             pressed: event.state.is_pressed(),
             key,
+            mods,
+            at: crate::time::Instant::now(),
         })
     }
+
+    pub fn chord(&self) -> Chord {
+        Chord {
+            key: self.key,
+            mods: self.mods,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn key_names() {
+        let name = |code| Key::Code(code).to_string();
+        assert_eq!(name(KeyCode::KeyA), "A");
+        assert_eq!(name(KeyCode::Digit1), "1");
+        assert_eq!(name(KeyCode::ArrowUp), "Up");
+        assert_eq!(name(KeyCode::Numpad1), "Num 1");
+        assert_eq!(name(KeyCode::ShiftLeft), "Left Shift");
+        assert_eq!(name(KeyCode::Slash), "/");
+        assert_eq!(name(KeyCode::Space), "Space");
+        assert_eq!(Key::Numeric(97).to_string(), "Scancode 97");
+        let chord = Chord {
+            key: Key::Code(KeyCode::KeyR),
+            mods: Mods::SHIFT | Mods::CTRL,
+        };
+        assert_eq!(chord.to_string(), "Ctrl+Shift+R");
+    }
+
+    fn chord(code: KeyCode, mods: Mods) -> Chord {
+        Chord {
+            key: Key::Code(code),
+            mods,
+        }
+    }
+
+    /// R rotating, with Shift+R holding
+    fn chorded() -> KeyStore {
+        KeyStore::from_binds([
+            (chord(KeyCode::KeyR, Mods::NONE), Action::RotateRight),
+            (chord(KeyCode::KeyR, Mods::SHIFT), Action::Hold),
+        ])
+    }
+
+    #[test]
+    fn chords_win_over_wildcards() {
+        let keys = chorded();
+        let r = |mods| keys.action(chord(KeyCode::KeyR, mods));
+        assert_eq!(r(Mods::NONE), Some(Action::RotateRight));
+        assert_eq!(r(Mods::SHIFT), Some(Action::Hold));
+        // other modifiers fall back to the wildcard
+        assert_eq!(r(Mods::CTRL), Some(Action::RotateRight));
+        assert_eq!(r(Mods::SHIFT | Mods::CTRL), Some(Action::RotateRight));
+        assert_eq!(keys.action(chord(KeyCode::KeyT, Mods::SHIFT)), None);
+    }
+
+    #[test]
+    fn releases_go_by_the_press() {
+        let keys = chorded();
+        // pressed with shift, let go of after shift
+        let pressed = keys.apply_key(chord(KeyCode::KeyR, Mods::SHIFT), true);
+        assert_eq!(pressed, Some((Action::Hold, true)));
+        assert_eq!(keys.held_actions(), Action::Hold.bit());
+        let released = keys.apply_key(chord(KeyCode::KeyR, Mods::NONE), false);
+        assert_eq!(released, Some((Action::Hold, false)));
+        assert!(!keys.active());
+
+        // & the other way around
+        let pressed = keys.apply_key(chord(KeyCode::KeyR, Mods::NONE), true);
+        assert_eq!(pressed, Some((Action::RotateRight, true)));
+        let released = keys.apply_key(chord(KeyCode::KeyR, Mods::SHIFT), false);
+        assert_eq!(released, Some((Action::RotateRight, false)));
+        assert!(!keys.active());
+    }
+
+    #[test]
+    fn held_keys_keep_their_action() {
+        let keys = chorded();
+        keys.apply_key(chord(KeyCode::KeyR, Mods::NONE), true);
+        // shift pressed while R's held, R repeating, isn't a new press
+        assert_eq!(
+            keys.apply_key(chord(KeyCode::KeyR, Mods::SHIFT), true),
+            None
+        );
+        assert_eq!(keys.held_actions(), Action::RotateRight.bit());
+        assert_eq!(
+            keys.apply_key(chord(KeyCode::KeyR, Mods::SHIFT), false),
+            Some((Action::RotateRight, false))
+        );
+        // nothing's left to release
+        assert_eq!(
+            keys.apply_key(chord(KeyCode::KeyR, Mods::NONE), false),
+            None
+        );
+    }
+
+    #[test]
+    fn held_moves_repeat_through_chords() {
+        let keys = KeyStore::from_binds([
+            (chord(KeyCode::ArrowLeft, Mods::NONE), Action::MoveLeft),
+            (chord(KeyCode::ArrowLeft, Mods::SHIFT), Action::SonicDrop),
+        ]);
+        let left = |mods, pressed| keys.apply_key(chord(KeyCode::ArrowLeft, mods), pressed);
+        assert_eq!(left(Mods::NONE, true), Some((Action::MoveLeft, true)));
+        assert_eq!(left(Mods::SHIFT, true), Some((Action::MoveLeft, true)));
+        assert_eq!(keys.get_actions().collect::<Vec<_>>(), [Action::MoveLeft]);
+        assert_eq!(left(Mods::SHIFT, false), Some((Action::MoveLeft, false)));
+    }
+
+    #[test]
+    fn unbound_keys_are_ignored() {
+        let keys = chorded();
+        assert_eq!(
+            keys.apply_key(chord(KeyCode::KeyT, Mods::SHIFT), true),
+            None
+        );
+        assert!(!keys.active());
+        assert_eq!(
+            keys.apply_key(chord(KeyCode::KeyT, Mods::NONE), false),
+            None
+        );
+    }
+
+    fn code(code: KeyCode) -> Chord {
+        Key::Code(code).into()
+    }
+
+    #[test]
+    fn presets_bind_every_action() {
+        use Action::*;
+        let actions = [
+            Hold,
+            Place,
+            Rotate180,
+            RotateLeft,
+            RotateRight,
+            MoveRight,
+            MoveLeft,
+            MoveDown,
+            SonicDrop,
+            Back,
+        ];
+        for preset in [Preset::Guideline, Preset::Jstris, Preset::Classic] {
+            let keys = KeyStore::from_preset(preset, []);
+            for action in actions {
+                // the guideline leaves sonic drop unbound, as the defaults do
+                let unbound = preset == Preset::Guideline && action == SonicDrop;
+                assert_eq!(
+                    keys.keys_of(action).is_empty(),
+                    unbound,
+                    "{action:?} in {}",
+                    preset.name()
+                );
+            }
+            // quitting's always Ctrl+Q
+            assert!(keys.keys_of(Quit).is_empty());
+        }
+        assert_eq!(Preset::Guideline.binds().unwrap(), default_binds());
+    }
+
+    #[test]
+    fn presets_cycle() {
+        let mut preset = Preset::default();
+        assert_eq!(preset, Preset::Custom);
+        let mut names = Vec::new();
+        for _ in 0..4 {
+            preset = preset.next();
+            names.push(preset.name());
+        }
+        assert_eq!(names, ["Guideline", "Jstris", "Classic", "Custom"]);
+    }
+
+    #[test]
+    fn switching_presets_keeps_the_custom_binds() {
+        let custom = [
+            (code(KeyCode::KeyH), Action::Hold),
+            (code(KeyCode::KeyJ), Action::Place),
+        ];
+        let keys = KeyStore::from_preset(Preset::Custom, custom);
+        // the game thread's copy
+        let game_keys = keys.clone();
+        assert_eq!(game_keys.action(code(KeyCode::KeyH)), Some(Action::Hold));
+
+        keys.apply_preset(Preset::Jstris);
+        // seen by the very next key
+        assert_eq!(
+            game_keys.apply_key(code(KeyCode::ShiftLeft), true),
+            Some((Action::Hold, true))
+        );
+        assert_eq!(game_keys.action(code(KeyCode::KeyH)), None);
+        assert_eq!(keys.custom(), custom);
+
+        keys.apply_preset(Preset::Custom);
+        assert_eq!(game_keys.action(code(KeyCode::KeyH)), Some(Action::Hold));
+        assert_eq!(game_keys.action(code(KeyCode::KeyJ)), Some(Action::Place));
+        assert_eq!(game_keys.action(code(KeyCode::ShiftLeft)), None);
+        // the key held from before is still let go of as what it was
+        assert_eq!(
+            game_keys.apply_key(code(KeyCode::ShiftLeft), false),
+            Some((Action::Hold, false))
+        );
+    }
 }
@@ -0,0 +1,95 @@
+// NOTE: latency is followed from a key's window event, through the game
+// thread applying its action, to the first frame presented with the game's
+// snapshot from after it. Only hard drops are measured, as they always change
+// the board, & only one at a time, presses while one is in flight being left
+// out. Every timestamp is an `Instant` of this process, so they compare across
+// threads.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::time::Instant;
+
+/// A hard drop being followed
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    event: Instant,
+    logic: Instant,
+    /// The first snapshot with the drop, once it's built
+    version: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    probe: Option<Probe>,
+    event_to_logic: Vec<Duration>,
+    logic_to_present: Vec<Duration>,
+}
+
+/// The input latency measured so far
+#[derive(Debug, Default)]
+pub struct Latency {
+    samples: Mutex<Samples>,
+}
+
+impl Latency {
+    /// The game thread applied a hard drop whose key event came at `event`
+    pub fn applied(&self, event: Instant, now: Instant) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.probe.is_none() {
+            samples.probe = Some(Probe {
+                event,
+                logic: now,
+                version: None,
+            });
+        }
+    }
+
+    /// The game thread built a snapshot, see [`crate::game::RenderSnapshot::version`]
+    pub fn snapshot_built(&self, version: u64) {
+        let mut samples = self.samples.lock().unwrap();
+        if let Some(probe) = &mut samples.probe {
+            probe.version.get_or_insert(version);
+        }
+    }
+
+    /// A frame drawn from a snapshot was presented
+    pub fn presented(&self, version: u64, now: Instant) {
+        let mut samples = self.samples.lock().unwrap();
+        let Some(probe) = samples.probe else {
+            return;
+        };
+        if probe.version.is_none_or(|v| version < v) {
+            return;
+        }
+        samples.probe = None;
+        samples.event_to_logic.push(probe.logic - probe.event);
+        samples
+            .logic_to_present
+            .push(now.saturating_duration_since(probe.logic));
+    }
+
+    /// The min, average & p99 of each stage, a line each
+    pub fn summary(&self) -> String {
+        let samples = self.samples.lock().unwrap();
+        format!(
+            "Latency min/avg/p99, {} drops\nEvent to logic\n{}\nLogic to present\n{}",
+            samples.event_to_logic.len(),
+            stage(&samples.event_to_logic),
+            stage(&samples.logic_to_present),
+        )
+    }
+}
+
+/// The min, average & p99 of a stage's samples in milliseconds
+fn stage(samples: &[Duration]) -> String {
+    if samples.is_empty() {
+        return "-".into();
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let avg = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+    let p99 = sorted[(sorted.len() * 99).div_ceil(100) - 1];
+    format!("{:.1}/{:.1}/{:.1}ms", ms(sorted[0]), ms(avg), ms(p99))
+}
@@ -1,11 +1,25 @@
+#[cfg(any(debug_assertions, test))]
+pub mod alloc_count;
+pub mod anim;
+pub mod config;
+pub mod daily;
 pub mod draw;
 pub mod game;
+pub mod headless;
 pub mod key;
+pub mod latency;
+pub mod records;
 pub mod rend;
+pub mod screen;
+pub mod screenshot;
+pub mod stats_window;
 pub mod styling;
+pub mod themes;
 pub mod time;
 
+use std::fmt::Write;
 use std::sync::{
+    atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicUsize},
     mpsc::{self},
     Arc, Mutex,
 };
@@ -19,10 +33,44 @@ use winit::{
 
 use self::rend::ScreenSize;
 
+/// Counts the tests' allocations, so they can check drawing makes none
+#[cfg(test)]
+#[global_allocator]
+static ALLOC: alloc_count::CountingAlloc = alloc_count::CountingAlloc;
+
 const RUNNING_ORDER: std::sync::atomic::Ordering = std::sync::atomic::Ordering::Relaxed;
+/// How long exiting waits on the game thread before leaving it behind
+#[cfg(not(target_arch = "wasm32"))]
+const GAME_THREAD_JOIN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+/// How often exiting checks whether the game thread's finished
+#[cfg(not(target_arch = "wasm32"))]
+const GAME_THREAD_JOIN_POLL: std::time::Duration = std::time::Duration::from_millis(10);
+/// The text metrics at the default block size
+const TEXT_METRICS: glyphon::Metrics = glyphon::Metrics::new(24.0, 36.0);
+/// The metrics of the countdown before a game
+const COUNTDOWN_METRICS: glyphon::Metrics = glyphon::Metrics::new(96.0, 120.0);
+/// The offset of the countdown's shadow at the default block size, keeping it
+/// readable over the board
+const COUNTDOWN_SHADOW: f32 = 4.0;
+/// The metrics of clear popups, see [`draw::popups`]
+const POPUP_METRICS: glyphon::Metrics = glyphon::Metrics::new(40.0, 48.0);
+/// The offset of a popup's shadow at the default block size
+const POPUP_SHADOW: f32 = 2.0;
+/// The opacity of text shadows
+const SHADOW_ALPHA: u8 = 160;
+/// The quad layers, in the order they're drawn
+const QUAD_LAYERS: [&str; 9] = [
+    "base", "grid", "game", "garbage", "effects", "next", "inputs", "overlay", "menu",
+];
+/// How long the path of a saved screenshot is shown
+const SCREENSHOT_NOTICE: std::time::Duration = std::time::Duration::from_secs(2);
+/// The renders a second when the monitor's refresh rate is unknown
+const FALLBACK_RENDER_RATE: usize = 60;
+/// The window's icon, 8-bit RGBA
+const ICON: &[u8] = include_bytes!("icon.png");
 
 /// External actions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Action {
     Hold,
     Place,
@@ -32,36 +80,152 @@ pub enum Action {
     MoveRight,
     MoveLeft,
     MoveDown,
-    Exit,
+    /// Drops the mino to the ghost without locking it, unbound by default
+    SonicDrop,
+    /// Pauses a game or backs out of a menu, left to the screens
+    #[serde(alias = "Exit")]
+    Back,
+    /// Quits straight away
+    Quit,
 }
 
 impl Action {
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Hold => "Hold",
+            Action::Place => "Hard drop",
+            Action::Rotate180 => "Rotate 180",
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::MoveRight => "Move right",
+            Action::MoveLeft => "Move left",
+            Action::MoveDown => "Soft drop",
+            Action::SonicDrop => "Sonic drop",
+            Action::Back => "Back",
+            Action::Quit => "Quit",
+        }
+    }
+
     pub fn repeatable(&self) -> bool {
         use Action::*;
         matches!(self, MoveRight | MoveLeft | MoveDown)
     }
+
+    /// The action's bit in a set of them
+    pub fn bit(self) -> u16 {
+        1 << self as u16
+    }
 }
 
 pub struct State {
-    // TODO: use an overarching 'GameState' struct instead of directly
-    // handling the game struct.
     rend: rend::Rend,
+    /// Kept to make the surfaces of other windows
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     keys: mpsc::Sender<key::SentKey>,
-    game: Arc<Mutex<game::Game>>,
+    /// Shares its binds with the game thread's, see [`key::KeyStore`]
+    key_store: key::KeyStore,
+    /// The action waiting on a key to be bound to
+    rebind: Option<screen::Rebind>,
+    /// The binds shown on the keys screen & the version they were looked up
+    /// at, see [`key::KeyStore::version`]
+    binds: (u64, Vec<(key::Chord, Action)>),
+    game: Arc<Mutex<game::GameState>>,
     settings: styling::Settings,
+    effects: draw::effects::Effects,
+    inputs: draw::inputs::InputDisplay,
+    popups: draw::popups::Popups,
+    /// The version of the snapshot last drawn
+    drawn: u64,
+    /// What the window's title was last set to
+    title: screen::Title,
+    /// Whether the next frame is captured to a screenshot
+    screenshot: bool,
+    /// What a saved screenshot's notice says & when it was saved
+    notice: Option<(String, time::Instant)>,
+    /// The text being drawn, kept between frames so drawing doesn't allocate
+    text: String,
+    /// The game over summary's spans, kept the same way
+    spans: Vec<(String, rend::SpanAttrs)>,
+    screens: screen::Screens,
+    /// What new games start with
+    setup: game::Setup,
+    config: config::UserConfig,
+    records: records::Records,
+    /// Whether the last game set a new record
+    new_record: bool,
+    puzzles: Vec<game::Puzzle>,
+    /// The puzzle being played, or up next
+    puzzle: usize,
+    /// The day whose challenge is being played, if it is
+    daily: Option<daily::Date>,
+    /// Whether the last daily challenge was the day's official run
+    official: bool,
+    tick_rate: usize,
+    modifiers: winit::keyboard::ModifiersState,
+    /// The window's size & maximized state from before going fullscreen
+    windowed: Option<(winit::dpi::PhysicalSize<u32>, bool)>,
     ctx: Arc<Context>,
+    /// The windows redrawn after each frame, see [`stats_window`]
+    followers: Vec<std::sync::Weak<Window>>,
+    /// Whether the window's fully covered, see [`State::update_hidden`]
+    occluded: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    game_thread: Option<std::thread::JoinHandle<()>>,
+    /// Steps the game from the event loop, as browsers have no threads
+    #[cfg(target_arch = "wasm32")]
+    game_stepper: Option<time::Stepper<()>>,
     // NOTE: should be dropped last
     window: Arc<Window>,
 }
 
 struct Context {
     run: AtomicRunState,
+    paused: Arc<AtomicBool>,
+    /// The render interpolation alpha, as `f32` bits
+    alpha: AtomicU32,
+    /// Whether the game changed since the last redraw the game thread asked for
+    changed: AtomicBool,
+    /// Whether the last frame was mid animation, so the next one differs
+    animating: AtomicBool,
+    /// The actions held, as bits, see [`Action::bit`]
+    held: AtomicU16,
+    /// The actions pressed since the last frame, as bits
+    tapped: AtomicU16,
+    /// Measures input latency when asked to, see [`LaunchOptions::latency`]
+    latency: Option<latency::Latency>,
+    /// Whether the theme files or the system's dark mode changed since the
+    /// palette was last picked
+    themes_changed: AtomicBool,
+    /// The path of a screenshot saved since the last frame
+    saved_screenshot: Mutex<Option<std::path::PathBuf>>,
+    /// Whether the window's minimized or occluded, so nothing's drawn
+    hidden: AtomicBool,
+    /// The renders a second, following the monitor's refresh rate
+    render_rate: Arc<AtomicUsize>,
+    /// How late the game thread's woken up, shown with the other timings
+    oversleep: Mutex<time::Oversleep>,
+    /// Whether the input display's shown, so key presses alone are redrawn
+    input_display: AtomicBool,
 }
 
 impl Default for Context {
     fn default() -> Self {
         Self {
             run: AtomicRunState::new(RunState::Running),
+            paused: Arc::default(),
+            alpha: AtomicU32::new(0),
+            changed: AtomicBool::new(true),
+            animating: AtomicBool::new(false),
+            held: AtomicU16::new(0),
+            tapped: AtomicU16::new(0),
+            latency: None,
+            themes_changed: AtomicBool::new(true),
+            saved_screenshot: Mutex::new(None),
+            hidden: AtomicBool::new(false),
+            render_rate: Arc::new(AtomicUsize::new(FALLBACK_RENDER_RATE)),
+            oversleep: Mutex::default(),
+            input_display: AtomicBool::new(false),
         }
     }
 }
@@ -82,51 +246,217 @@ impl RunState {
     }
 }
 
+/// Why the renderer couldn't start
+#[derive(Debug)]
+pub enum InitError {
+    /// No adapter can draw to the window, not even a software one
+    NoAdapter,
+    DeviceRequestFailed(wgpu::RequestDeviceError),
+    /// The window can't be drawn to
+    SurfaceUnsupported(String),
+}
+
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitError::NoAdapter => f.write_str("no graphics adapter can draw to the window"),
+            InitError::DeviceRequestFailed(err) => {
+                write!(f, "failed to open the graphics device: {err}")
+            }
+            InitError::SurfaceUnsupported(reason) => {
+                write!(f, "the window can't be drawn to: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InitError::DeviceRequestFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Finds an adapter for the window, falling back to a software adapter, & to
+/// GL first when every backend's allowed
+async fn request_adapter(
+    window: &Arc<Window>,
+    gpu: &GpuOptions,
+) -> Result<(wgpu::Instance, wgpu::Surface<'static>, wgpu::Adapter), InitError> {
+    let attempts: &[_] = if gpu.backends == wgpu::Backends::all() {
+        &[
+            (wgpu::Backends::all(), false),
+            (wgpu::Backends::GL, false),
+            (wgpu::Backends::all(), true),
+        ]
+    } else {
+        &[(gpu.backends, false), (gpu.backends, true)]
+    };
+    let mut surface_err = None;
+    for &(backends, force_fallback_adapter) in attempts {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = match instance.create_surface(window.clone()) {
+            Ok(surface) => surface,
+            Err(err) => {
+                surface_err = Some(err);
+                continue;
+            }
+        };
+        let named = gpu
+            .adapter
+            .as_deref()
+            .filter(|_| !force_fallback_adapter)
+            .and_then(|name| find_adapter(&instance, &surface, backends, name));
+        let adapter = match named {
+            Some(adapter) => Some(adapter),
+            None => {
+                instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference: gpu.power_preference,
+                        compatible_surface: Some(&surface),
+                        force_fallback_adapter,
+                    })
+                    .await
+            }
+        };
+        match adapter {
+            Some(adapter) => {
+                let info = adapter.get_info();
+                log::info!(
+                    "using {} on {:?}, driver {} {}",
+                    info.name,
+                    info.backend,
+                    info.driver,
+                    info.driver_info
+                );
+                return Ok((instance, surface, adapter));
+            }
+            None => log::warn!(
+                "no adapter for {backends:?}{}",
+                if force_fallback_adapter {
+                    " in software"
+                } else {
+                    ""
+                }
+            ),
+        }
+    }
+    Err(surface_err.map_or(InitError::NoAdapter, |err| {
+        InitError::SurfaceUnsupported(err.to_string())
+    }))
+}
+
+/// The first adapter that can draw to `surface` whose name contains `name`,
+/// ignoring case
+#[cfg(not(target_arch = "wasm32"))]
+fn find_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'_>,
+    backends: wgpu::Backends,
+    name: &str,
+) -> Option<wgpu::Adapter> {
+    let name = name.to_lowercase();
+    let adapter = instance
+        .enumerate_adapters(backends)
+        .into_iter()
+        .find(|adapter| {
+            adapter.is_surface_supported(surface)
+                && adapter.get_info().name.to_lowercase().contains(&name)
+        });
+    if adapter.is_none() {
+        log::warn!("no adapter for {backends:?} named like {name:?}, using the default");
+    }
+    adapter
+}
+
+/// Browsers can't list their adapters, so the default's always used
+#[cfg(target_arch = "wasm32")]
+fn find_adapter(
+    _: &wgpu::Instance,
+    _: &wgpu::Surface<'_>,
+    _: wgpu::Backends,
+    _: &str,
+) -> Option<wgpu::Adapter> {
+    log::warn!("adapters can't be picked by name in browsers, using the default");
+    None
+}
+
 impl State {
     async fn new(
         window: Arc<Window>,
         keys: mpsc::Sender<key::SentKey>,
-        game: Arc<Mutex<game::Game>>,
+        key_store: key::KeyStore,
+        game: Arc<Mutex<game::GameState>>,
         ctx: Arc<Context>,
-    ) -> State {
+        options: &LaunchOptions,
+        config: config::UserConfig,
+    ) -> Result<State, InitError> {
         let size = window.inner_size();
         let scale = window.scale_factor();
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
-
-        let surface = instance.create_surface(window.clone()).unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                compatible_surface: Some(&surface),
-                ..Default::default()
-            })
-            .await
-            .unwrap();
+        let (instance, surface, adapter) = request_adapter(&window, &options.gpu).await?;
+        // timing's left off, rather than failing, without the features
+        let required_features = if options.gpu_timing {
+            adapter.features() & rend::timing::FEATURES
+        } else {
+            wgpu::Features::empty()
+        };
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    ..Default::default()
+                },
+                None,
+            )
             .await
-            .unwrap();
+            .map_err(InitError::DeviceRequestFailed)?;
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
             .formats
             .iter()
             .copied()
             .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+            .or(surface_caps.formats.first().copied())
+            .ok_or_else(|| InitError::SurfaceUnsupported("no supported formats".into()))?;
+        let mut settings = styling::Settings::default();
+        let mut setup = game::Setup::default();
+        config.apply(&mut settings, &mut setup);
+        options.apply(&mut setup);
+        settings.sizing.scale_factor = scale;
+        if let Some(present_mode) = options.present_mode {
+            settings.present_mode = present_mode;
+        }
+        let sample_flags = adapter.get_texture_format_features(surface_format).flags;
         let mut rend = rend::Rend::new(
             ScreenSize::new(size, scale),
             device,
             queue,
             surface_format,
-            surface,
+            rend::Output::Surface(surface),
+            settings.sample_count,
+            sample_flags,
         );
+        if let Some(path) = &config.font_path {
+            match rend.load_font(path) {
+                Ok(family) => log::info!("drawing text in {family}"),
+                Err(err) => log::error!("falling back to the bundled font: {err:#}"),
+            }
+        }
+        rend.set_gpu_timing(options.gpu_timing);
+        rend.set_target_filter(settings.target_filter());
+        rend.set_supported_present_modes(surface_caps.present_modes);
+        rend.set_present_mode(settings.present_mode);
+        settings.present_mode = rend.present_mode();
 
         rend.gen_text_layer(
-            glyphon::Metrics {
-                font_size: 24.0,
-                line_height: 36.0,
-            },
+            TEXT_METRICS,
             rend::TextLayerDesc {
-                name: "text",
+                name: "text".into(),
                 attrs: None,
                 shaping: None,
                 left: 0.0,
@@ -140,125 +470,1338 @@ impl State {
                 },
                 default_color: Some(glyphon::Color::rgb(0, 0, 0)),
                 custom_glyphs: Vec::new(),
+                shadow: None,
+            },
+        );
+        rend.gen_text_layer(
+            COUNTDOWN_METRICS,
+            rend::TextLayerDesc {
+                name: "countdown".into(),
+                scale: 1.0,
+                ..Default::default()
             },
         );
-        rend.gen_quad_layer("base");
-        rend.gen_quad_layer("game");
+        rend.gen_text_layer(
+            POPUP_METRICS,
+            rend::TextLayerDesc {
+                name: "popup".into(),
+                scale: 1.0,
+                ..Default::default()
+            },
+        );
+        for name in ["menu", "summary", "objective"] {
+            rend.gen_text_layer(
+                TEXT_METRICS,
+                rend::TextLayerDesc {
+                    name: name.into(),
+                    scale: 1.0,
+                    ..Default::default()
+                },
+            );
+        }
+        // layers are drawn in the order they're added
+        for name in QUAD_LAYERS {
+            rend.gen_quad_layer(name);
+        }
 
-        State {
+        let records = records::load();
+        let puzzles = options
+            .puzzles
+            .clone()
+            .or_else(game::puzzle::default_dir)
+            .map_or_else(Vec::new, |dir| {
+                game::puzzle::load_dir(&dir).unwrap_or_else(|err| {
+                    log::warn!("no puzzles loaded: {err:#}");
+                    Vec::new()
+                })
+            });
+        // carry on from the first unsolved puzzle
+        let puzzle = puzzles
+            .iter()
+            .position(|puzzle| !records.is_solved(&puzzle.name))
+            .unwrap_or(0);
+
+        let skin = settings.skin;
+        let binds = (key_store.version(), key_store.binds());
+        let mut state = State {
             rend,
+            instance,
+            adapter,
             keys,
+            key_store,
+            rebind: None,
+            binds,
             game,
             window,
-            settings: styling::Settings::default(),
+            settings,
+            effects: draw::effects::Effects::default(),
+            inputs: draw::inputs::InputDisplay::default(),
+            popups: draw::popups::Popups::default(),
+            drawn: 0,
+            title: screen::Title::default(),
+            screenshot: false,
+            notice: None,
+            text: String::new(),
+            spans: Vec::new(),
+            screens: screen::Screens::default(),
+            setup,
+            config,
+            records,
+            new_record: false,
+            puzzles,
+            puzzle,
+            daily: None,
+            official: false,
+            tick_rate: options.tick_rate,
+            modifiers: Default::default(),
+            windowed: None,
             ctx,
-        }
+            followers: Vec::new(),
+            occluded: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            game_thread: None,
+            #[cfg(target_arch = "wasm32")]
+            game_stepper: None,
+        };
+        state.set_skin(skin);
+        state.update_render_rate();
+        state
+            .ctx
+            .input_display
+            .store(state.settings.input_display, RUNNING_ORDER);
+        // the game waits on the menu
+        state.set_paused(true);
+        Ok(state)
     }
 
     pub fn get_window(&self) -> &Window {
         &self.window
     }
 
+    /// Changes the MSAA sample count, unsupported counts fall back to 1
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        self.rend.set_sample_count(sample_count);
+        self.settings.sample_count = self.rend.sample_count();
+        self.window.request_redraw();
+    }
+
+    /// Changes the present mode, unsupported modes falling back
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        self.rend.set_present_mode(present_mode);
+        self.settings.present_mode = self.rend.present_mode();
+        self.window.request_redraw();
+    }
+
+    /// Switches between borderless fullscreen & the window it came from
+    pub fn toggle_fullscreen(&mut self) {
+        if self.window.fullscreen().is_none() {
+            self.windowed = Some((self.window.inner_size(), self.window.is_maximized()));
+            self.window
+                .set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+            return;
+        }
+        self.window.set_fullscreen(None);
+        if let Some((size, maximized)) = self.windowed.take() {
+            self.window.set_maximized(maximized);
+            if !maximized {
+                let _ = self.window.request_inner_size(size);
+            }
+        }
+    }
+
+    /// Swaps the palette, taking effect on the next frame
+    pub fn set_palette(&mut self, palette: styling::Palette) {
+        self.settings.set_palette(palette);
+        self.window.request_redraw();
+    }
+
+    /// Swaps in the palette of the theme in use
+    ///
+    /// A theme file that fails to load keeps the palette from before.
+    fn apply_theme(&mut self) {
+        let palette = match &self.settings.theme_file {
+            Some(name) => themes::load(name),
+            None => Ok(self.settings.theme.palette()),
+        };
+        match palette {
+            Ok(palette) => self.set_palette(palette),
+            Err(err) => log::error!("keeping the last palette: {err:#}"),
+        }
+    }
+
+    /// Swaps the block skin, `None` going back to flat colours
+    pub fn set_skin(&mut self, name: Option<&'static str>) {
+        let image = name.map(|name| {
+            let bytes = rend::skin::builtin(name)
+                .ok_or_else(|| anyhow::anyhow!("no skin named {name:?}"))?;
+            rend::skin::SkinImage::decode_png(bytes)
+        });
+        match image.transpose() {
+            Ok(image) => {
+                self.rend.set_skin(image.as_ref());
+                self.settings.skin = name;
+            }
+            Err(err) => log::error!("failed to load skin: {err:#}"),
+        }
+        self.window.request_redraw();
+    }
+
+    /// Follows the window being minimized or occluded
+    ///
+    /// Nothing's drawn while it's hidden, & a game being played is paused when
+    /// set to. Once it's shown again the surface is configured afresh, in case
+    /// it went stale, & everything's redrawn.
+    fn update_hidden(&mut self) {
+        let size = self.window.inner_size();
+        let hidden = self.occluded || size.width == 0 || size.height == 0;
+        if self.ctx.hidden.swap(hidden, RUNNING_ORDER) == hidden {
+            return;
+        }
+        if !hidden {
+            self.rend.configure_output();
+            self.window.request_redraw();
+        } else if self.settings.pause_when_hidden && self.screens.screen().is_playing() {
+            self.navigate(key::Nav::Back);
+        }
+    }
+
+    /// Renders at the refresh rate of the window's monitor, up to the config's
+    /// max
+    fn update_render_rate(&self) {
+        let refresh = self
+            .window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz());
+        // e.g. 59.94Hz is rendered at 60
+        let rate = refresh.map_or(FALLBACK_RENDER_RATE, |mhz| (mhz as usize + 500) / 1000);
+        let rate = rate.clamp(1, self.config.max_render_rate.max(1) as usize);
+        if self.ctx.render_rate.swap(rate, RUNNING_ORDER) != rate {
+            log::info!("rendering at {rate}Hz");
+        }
+    }
+
+    /// Pauses or resumes the game thread
+    pub fn set_paused(&self, paused: bool) {
+        self.ctx.paused.store(paused, RUNNING_ORDER);
+        // wakes the parked thread straight away, rather than on its next poll
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (false, Some(handle)) = (paused, &self.game_thread) {
+            handle.thread().unpark();
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        self.ctx.paused.load(RUNNING_ORDER)
+    }
+
+    /// Routes a key to the game while playing, or to the menus otherwise
+    ///
+    /// Key releases always reach the game so no key is left held.
+    fn key_input(&mut self, event: winit::event::KeyEvent) {
+        let playing = self.screens.screen().is_playing();
+        // backing out & quitting belong to the screens, never reaching the game
+        let sent = key::SentKey::from_event(event.clone(), self.modifiers.into());
+        if self.rebind.is_some() && event.state.is_pressed() {
+            if let Some(sk) = sent.filter(|_| !event.repeat) {
+                self.capture_key(sk.key);
+            }
+            return;
+        }
+        let bound = sent.and_then(|sk| self.key_store.action(sk.chord()));
+        if let Some(action @ (Action::Back | Action::Quit)) = bound {
+            if event.state.is_pressed() && !event.repeat {
+                match action {
+                    Action::Quit => self.schedule_end(),
+                    _ => self.navigate(key::Nav::Back),
+                }
+            }
+            return;
+        }
+        match key::Nav::from_event(&event) {
+            Some(nav) if !playing || nav == key::Nav::Back => self.navigate(nav),
+            _ => {
+                if let Some(sk) = sent.filter(|sk| playing || !sk.pressed) {
+                    self.keys.send(sk).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Binds the key to the action being rebound, see
+    /// [`screen::Rebind::capture`]
+    fn capture_key(&mut self, key: key::Key) {
+        let Some(rebind) = &mut self.rebind else {
+            return;
+        };
+        match rebind.capture(key, &self.key_store) {
+            screen::Capture::Cancelled => self.rebind = None,
+            screen::Capture::Conflict => (),
+            screen::Capture::Bound => {
+                self.settings.key_preset = key::Preset::Custom;
+                self.rebind = None;
+                self.save_config();
+            }
+        }
+        self.window.request_redraw();
+    }
+
+    /// Moves through the screens, the game only running while it's played
+    fn navigate(&mut self, nav: key::Nav) {
+        let sizes = (
+            self.settings.letterbox,
+            self.settings.render_scale,
+            self.settings.sizing.max_block_size,
+        );
+        let theme = (self.settings.theme, self.settings.theme_file.clone());
+        let high_contrast = self.settings.high_contrast;
+        let key_preset = self.settings.key_preset;
+        let command = self
+            .screens
+            .navigate(nav, &mut self.settings, &mut self.setup);
+        match command {
+            Some(screen::Command::Quit) => self.schedule_end(),
+            Some(screen::Command::Rebind(action)) => {
+                self.rebind = Some(screen::Rebind::new(action))
+            }
+            Some(start) => {
+                match start {
+                    // the daily's only the mode when launched with it
+                    screen::Command::Start => {
+                        self.daily =
+                            (self.setup.mode == game::Mode::Daily).then(daily::Date::today);
+                    }
+                    screen::Command::Daily => self.daily = Some(daily::Date::today()),
+                    // the same kind of game again
+                    _ => (),
+                }
+                if !self.start_game() {
+                    log::warn!("there are no puzzles to play");
+                    self.screens.set_screen(screen::Screen::Menu);
+                }
+            }
+            None => (),
+        }
+        if (self.settings.theme, &self.settings.theme_file) != (theme.0, &theme.1)
+            || self.settings.high_contrast != high_contrast
+        {
+            self.apply_theme();
+        }
+        let new_sizes = (
+            self.settings.letterbox,
+            self.settings.render_scale,
+            self.settings.sizing.max_block_size,
+        );
+        if new_sizes != sizes {
+            self.resize(self.window.inner_size());
+        }
+        if self.settings.key_preset != key_preset {
+            self.key_store.apply_preset(self.settings.key_preset);
+        }
+        self.rend.set_target_filter(self.settings.target_filter());
+        self.ctx
+            .input_display
+            .store(self.settings.input_display, RUNNING_ORDER);
+        self.save_config();
+        self.set_paused(!self.screens.screen().is_playing());
+        self.window.request_redraw();
+    }
+
+    /// Saves the config if the settings changed
+    fn save_config(&mut self) {
+        let mut config = self.config.clone();
+        config.capture(&self.settings, &self.setup);
+        config.keybinds = self
+            .key_store
+            .custom()
+            .into_iter()
+            .map(|(chord, action)| config::Keybind::new(chord, action))
+            .collect();
+        if config == self.config {
+            return;
+        }
+        if let Err(err) = config::save(&config) {
+            log::error!("failed to save config: {err:#}");
+        }
+        self.config = config;
+    }
+
+    /// Replaces the game with a new one
+    ///
+    /// Returns whether there was a game to start, puzzles needing a puzzle.
+    fn start_game(&mut self) -> bool {
+        if !self.restart_game() {
+            return false;
+        }
+        self.resize(self.window.inner_size());
+        true
+    }
+
+    /// Replaces the game with a new one, without laying out for it
+    fn restart_game(&mut self) -> bool {
+        if let Some(date) = self.daily {
+            let game = game::Game::from_setup(daily::setup(date, &self.setup), self.tick_rate);
+            *self.game.lock().unwrap() = game::GameState::new(game);
+            self.effects = Default::default();
+            return true;
+        }
+        let game = match self.setup.mode {
+            game::Mode::Puzzle => match self.puzzles.get(self.puzzle) {
+                Some(puzzle) => game::Game::from_puzzle(puzzle, self.setup, self.tick_rate),
+                None => return false,
+            },
+            _ => game::Game::from_setup(self.setup, self.tick_rate),
+        };
+        *self.game.lock().unwrap() = game::GameState::new(game);
+        self.effects = Default::default();
+        true
+    }
+
+    /// Takes the game's latest snapshot & its events since the last
+    fn take_snapshot(&self) -> (Arc<game::RenderSnapshot>, Vec<game::GameEvent>) {
+        // the lock is only held to pick up the snapshot
+        let mut game = self.game.lock().unwrap();
+        (game.snapshot(), game.take_events())
+    }
+
+    fn text_metrics(&self) -> glyphon::Metrics {
+        TEXT_METRICS.scale(self.settings.sizing.scale())
+    }
+
+    /// Moves to a new scale factor, e.g. after the window changes monitor
+    pub fn rescale(&mut self, scale_factor: f64) {
+        self.settings.sizing.scale_factor = scale_factor;
+        self.resize(self.window.inner_size());
+    }
+
+    /// Lays everything out for the new size
+    ///
+    /// A minimized window, with a zero size, keeps its last layout & surface
+    /// until it's restored, the next resize bringing everything up to date.
+    /// Letterboxed, the layout only changes with the target's size, the
+    /// window's only moving where the target's drawn. Above a render scale of
+    /// 1, everything is laid out at the scaled size, so text is shaped at it
+    /// rather than scaled up.
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.settings.sizing.resize(new_size.width, new_size.height);
-            self.rend
-                .resize(ScreenSize::new(new_size, self.window.scale_factor()));
+            let window_scale = self.window.scale_factor();
+            let dims = self.game.lock().unwrap().dims();
+            let letterbox = self.settings.letterbox;
+            let sizing = &mut self.settings.sizing;
+            sizing.scale_factor = window_scale;
+            let unscaled = if letterbox {
+                sizing.target_size(dims)
+            } else {
+                new_size
+            };
+            // the target can't be larger than the device allows
+            let largest = unscaled.width.max(unscaled.height).max(1);
+            let render_scale = (self.settings.render_scale as f64)
+                .min(self.rend.max_target_size() as f64 / largest as f64)
+                .max(1.0);
+            sizing.scale_factor = window_scale * render_scale;
+            let drawn = if letterbox {
+                sizing.target_size(dims)
+            } else {
+                winit::dpi::PhysicalSize::new(
+                    (new_size.width as f64 * render_scale).round() as u32,
+                    (new_size.height as f64 * render_scale).round() as u32,
+                )
+            };
+            let target = (letterbox || drawn != new_size)
+                .then(|| ScreenSize::new(drawn, sizing.scale_factor));
+            self.rend.resize(ScreenSize::new(new_size, window_scale));
+            if target.is_some() && target == self.rend.target_size() {
+                return;
+            }
+            self.rend.set_target(target);
+            self.settings.sizing.resize(dims, drawn.width, drawn.height);
+            self.relayout();
             self.draw();
         }
     }
 
+    /// Moves & scales the text layers to the layout
+    ///
+    /// The countdown & popups are centred as they're drawn, so only their
+    /// metrics & shadows change.
+    fn relayout(&mut self) {
+        let metrics = self.text_metrics();
+        let layout = self.settings.sizing.layout;
+        for (name, rect) in [
+            ("text", layout.score_text),
+            ("objective", layout.objective),
+            ("menu", layout.menu),
+            ("summary", layout.summary),
+        ] {
+            if let Some(layer) = self.rend.get_text_mut(name) {
+                layer.set_rect(rect);
+                layer.set_metrics(metrics);
+            }
+        }
+        if let Some(layer) = self.rend.get_text_mut("countdown") {
+            let scale = self.settings.sizing.scale();
+            layer.set_metrics(COUNTDOWN_METRICS.scale(scale));
+            let colour = glyphon::Color::rgba(0, 0, 0, SHADOW_ALPHA);
+            layer.shadow = Some((COUNTDOWN_SHADOW * scale, colour));
+        }
+        if let Some(layer) = self.rend.get_text_mut("popup") {
+            let scale = self.settings.sizing.scale();
+            layer.set_metrics(POPUP_METRICS.scale(scale));
+            let colour = glyphon::Color::rgba(0, 0, 0, SHADOW_ALPHA);
+            layer.shadow = Some((POPUP_SHADOW * scale, colour));
+        }
+    }
+
     fn draw(&mut self) {
+        if self.ctx.themes_changed.swap(false, RUNNING_ORDER) {
+            self.settings.themes = themes::list();
+            self.apply_theme();
+        }
+        let (mut game, mut events) = self.take_snapshot();
+        if self.screens.screen().is_playing() && game.is_over() && self.end_game(&game) {
+            (game, events) = self.take_snapshot();
+        }
+        self.drawn = game.version();
+        let title = screen::Title::new(self.screens.screen(), &game);
+        if title != self.title {
+            self.title = title;
+            self.window.set_title(&title.to_string());
+        }
+        let shows_game = self.screens.shows_game();
+        for name in ["game", "garbage", "effects", "next"] {
+            if let Some(layer) = self.rend.get_quad_mut(name) {
+                layer.set_visible(shows_game);
+            }
+        }
+        let now = time::Instant::now();
+        if let Some(path) = self.ctx.saved_screenshot.lock().unwrap().take() {
+            self.notice = Some((format!("Saved {}", path.display()), now));
+        }
+        self.notice = self
+            .notice
+            .take()
+            .filter(|(_, at)| now.saturating_duration_since(*at) < SCREENSHOT_NOTICE);
+        if let Some(layer) = self.rend.get_text_mut("text") {
+            layer.set_visible(shows_game || self.notice.is_some());
+        }
+        if let Some(layer) = self.rend.get_text_mut("objective") {
+            let puzzle = self
+                .puzzles
+                .get(self.puzzle)
+                .filter(|_| game.objective().is_some());
+            layer.set_visible(shows_game && puzzle.is_some());
+            if let Some(puzzle) = puzzle {
+                layer.default_color = self.settings.palette.fg.into();
+                self.text.clear();
+                screen::objective_text(&mut self.text, puzzle, &game);
+                layer.set_text_if_changed(&self.text);
+            }
+        }
+        if let Some(layer) = self.rend.get_text_mut("summary") {
+            let summary = self.screens.screen() == screen::Screen::GameOver
+                && game.mode() != game::Mode::Puzzle;
+            if summary {
+                let daily = self.daily.map(|date| (date, self.official));
+                screen::summary(
+                    &mut self.spans,
+                    &game,
+                    &self.records,
+                    self.new_record,
+                    daily,
+                    &self.settings.palette,
+                );
+            }
+            self.text.clear();
+            let text = match self.screens.screen() {
+                screen::Screen::GameOver if game.mode() == game::Mode::Puzzle => {
+                    self.text.push_str("All puzzles solved!\n");
+                    screen::puzzle_progress(&mut self.text, &self.records, &self.puzzles);
+                    true
+                }
+                screen::Screen::Menu
+                    if self.screens.selected_entry() == Some(screen::Entry::Daily) =>
+                {
+                    screen::daily_text(&mut self.text, &self.records, daily::Date::today());
+                    true
+                }
+                screen::Screen::Menu if self.setup.mode == game::Mode::Puzzle => {
+                    screen::puzzle_progress(&mut self.text, &self.records, &self.puzzles);
+                    true
+                }
+                screen::Screen::Menu => {
+                    screen::best_run(&mut self.text, &self.records, self.setup.mode);
+                    true
+                }
+                _ => false,
+            };
+            layer.set_visible(summary || text);
+            layer.default_color = self.settings.palette.fg.into();
+            if summary {
+                layer.set_spans(&self.spans);
+            } else if text {
+                layer.set_text_if_changed(&self.text);
+            }
+        }
+        let mut counting_down = false;
+        if let Some(layer) = self.rend.get_text_mut("countdown") {
+            self.text.clear();
+            let shown = screen::countdown_text(&mut self.text, &game);
+            counting_down = shown && self.screens.screen().is_playing();
+            layer.set_visible(counting_down);
+            if shown {
+                layer.default_color = self.settings.palette.fg.into();
+                layer.set_text_if_changed(&self.text);
+                layer.center_in(self.settings.sizing.layout.board);
+            }
+        }
+        self.draw_menu();
+        let draw_ctx = draw::DrawContext {
+            settings: &self.settings,
+            game: &game,
+            now,
+            alpha: f32::from_bits(self.ctx.alpha.load(RUNNING_ORDER)),
+        };
+        if let Some(layer) = self.rend.get_quad_mut("overlay") {
+            layer.set_visible(shows_game && !self.screens.screen().is_playing());
+            draw::overlay_quads(&draw_ctx, layer);
+        }
+
+        for event in events {
+            self.popups.push(&event, now);
+            self.effects.push(event, now);
+        }
+        if let Some(layer) = self.rend.get_text_mut("popup") {
+            let popup = self.popups.current(&self.settings, now);
+            layer.set_visible(shows_game && popup.is_some());
+            if let Some((text, opacity)) = popup {
+                let palette = &self.settings.palette;
+                layer.default_color = palette.fg.with_alpha(palette.fg.a * opacity).into();
+                if let Some((_, colour)) = &mut layer.shadow {
+                    *colour = glyphon::Color::rgba(0, 0, 0, (SHADOW_ALPHA as f32 * opacity) as u8);
+                }
+                layer.set_text_if_changed(text);
+                layer.center_in(self.settings.sizing.layout.board);
+            }
+        }
+        if let Some(layer) = self.rend.get_quad_mut("effects") {
+            self.effects.draw(&draw_ctx, layer);
+        }
         if let Some(layer) = self.rend.get_quad_mut("game") {
-            draw::game_quads(&self.settings, &self.game.lock().unwrap(), layer);
+            draw::game_quads(&draw_ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("garbage") {
+            draw::garbage_quads(&draw_ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("next") {
+            draw::next_quads(&draw_ctx, layer);
         }
         if let Some(layer) = self.rend.get_quad_mut("base") {
-            draw::base_quads(&self.settings, layer);
+            draw::base_quads(&draw_ctx, layer);
+        }
+        if let Some(layer) = self.rend.get_quad_mut("grid") {
+            draw::grid_quads(&draw_ctx, layer);
         }
+        let gpu_times = self.rend.gpu_times();
         if let Some(layer) = self.rend.get_text_mut("text") {
-            layer.set_text("Hello, World!");
+            let stats = game.stats();
+            // games with an opponent, or taking garbage, show their exchange
+            let versus = game.versus_rules().opponent || stats.received() != 0;
+            self.text.clear();
+            if let Some(latency) = &self.ctx.latency {
+                // only measured when asked for, so left allocating
+                self.text.push_str(&latency.summary());
+                self.text.push('\n');
+            }
+            if let Some(times) = gpu_times {
+                let _ = writeln!(self.text, "{times}");
+            }
+            if !self.text.is_empty() {
+                let _ = write!(self.text, "{}", self.ctx.oversleep.lock().unwrap());
+            }
+            if let Some((notice, _)) = &self.notice {
+                layer.set_text_if_changed(notice);
+            } else if !self.text.is_empty() {
+                layer.set_text_if_changed(&self.text);
+            } else if versus {
+                screen::versus_text(&mut self.text, &game);
+                layer.set_text_if_changed(&self.text);
+            } else {
+                layer.set_text_if_changed("Hello, World!");
+            }
+        }
+        if let Some(layer) = self.rend.get_quad_mut("inputs") {
+            layer.set_visible(shows_game && self.settings.input_display);
+            let tapped = self.ctx.tapped.swap(0, RUNNING_ORDER);
+            self.inputs.tap(tapped, now);
+            let held = self.ctx.held.load(RUNNING_ORDER);
+            self.inputs.draw(&draw_ctx, held, layer);
+        }
+        let animating = counting_down
+            || !self.effects.is_empty()
+            || self.popups.is_showing()
+            || (self.settings.animates() && self.inputs.is_flashing(now))
+            || draw::is_animating(&draw_ctx);
+        self.ctx.animating.store(animating, RUNNING_ORDER);
+    }
+
+    /// Moves to the game over screen, recording the run
+    ///
+    /// Puzzles go on to the next, or retry a failed one, so only finishing
+    /// the last puzzle ends the game. Returns whether a new game started.
+    fn end_game(&mut self, game: &game::RenderSnapshot) -> bool {
+        if game.mode() == game::Mode::Puzzle {
+            return self.end_puzzle(game);
+        }
+        self.screens.set_screen(screen::Screen::GameOver);
+        self.set_paused(true);
+        let run = records::Run::new(game);
+        if let Some(date) = self.daily {
+            self.new_record = false;
+            self.official = self.records.play_daily(date, run);
+            if !self.official {
+                return false;
+            }
+        } else {
+            self.new_record = self.records.update(run.mode, run);
+        }
+        if let Err(err) = records::save(&self.records) {
+            log::error!("failed to save records: {err:#}");
         }
+        false
     }
 
-    fn render(&mut self) {
+    fn end_puzzle(&mut self, game: &game::RenderSnapshot) -> bool {
+        if game.outcome() == Some(game::Outcome::Solved) {
+            let solved = &self.puzzles[self.puzzle].name;
+            if self.records.solve(solved) {
+                if let Err(err) = records::save(&self.records) {
+                    log::error!("failed to save records: {err:#}");
+                }
+            }
+            self.puzzle += 1;
+            if self.puzzle == self.puzzles.len() {
+                self.puzzle = 0;
+                self.screens.set_screen(screen::Screen::GameOver);
+                self.set_paused(true);
+                return false;
+            }
+        }
+        self.restart_game()
+    }
+
+    /// Draws the entries of the current screen, hidden while playing
+    fn draw_menu(&mut self) {
+        let entries = self.screens.screen().entries();
+        let line_height = self.text_metrics().line_height;
+        if let Some(layer) = self.rend.get_quad_mut("menu") {
+            layer.set_visible(!entries.is_empty());
+            draw::menu_quads(&self.settings, self.screens.selected(), line_height, layer);
+        }
+        if let Some(layer) = self.rend.get_text_mut("menu") {
+            layer.set_visible(!entries.is_empty());
+            layer.default_color = self.settings.palette.fg.into();
+            let version = self.key_store.version();
+            if self.binds.0 != version {
+                self.binds = (version, self.key_store.binds());
+            }
+            self.text.clear();
+            for (i, &entry) in entries.iter().enumerate() {
+                if i != 0 {
+                    self.text.push('\n');
+                }
+                match entry {
+                    screen::Entry::Bind(action) => {
+                        let keys = self.binds.1.iter().filter(|&&(_, a)| a == action);
+                        let keys = keys.map(|&(chord, _)| chord);
+                        screen::bind_label(&mut self.text, action, keys, self.rebind.as_ref())
+                    }
+                    _ => entry.label(&mut self.text, &self.settings, &self.setup),
+                }
+            }
+            layer.set_text_if_changed(&self.text);
+        }
+    }
+
+    /// Renders a frame
+    ///
+    /// Frames are skipped while the window has no area or is hidden, or when
+    /// acquiring the surface texture times out.
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 || self.ctx.hidden.load(RUNNING_ORDER) {
+            return Ok(());
+        }
+        #[cfg(debug_assertions)]
+        let allocations = alloc_count::allocations();
         self.draw();
-        self.rend.prepare();
-        let output = self.rend.surface.get_current_texture().unwrap();
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder =
-            self.rend
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("command encoder"),
+        #[cfg(debug_assertions)]
+        log::trace!(
+            "drawing allocated {} times",
+            alloc_count::allocations() - allocations
+        );
+        let clear = self.settings.palette.bg.into();
+        self.rend.render_frame(clear)?;
+        if let Some(latency) = &self.ctx.latency {
+            latency.presented(self.drawn, time::Instant::now());
+        }
+        if std::mem::take(&mut self.screenshot) {
+            self.save_screenshot(clear);
+        }
+        Ok(())
+    }
+
+    /// Renders a frame, then has the followers redraw along with it
+    fn redraw(&mut self) {
+        match self.render() {
+            Ok(()) => self.followers.retain(|window| {
+                window
+                    .upgrade()
+                    .inspect(|window| window.request_redraw())
+                    .is_some()
+            }),
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                log::error!("surface out of memory, shutting down");
+                self.schedule_end();
+            }
+            Err(err) => log::error!("failed to render frame: {err}"),
+        }
+    }
+
+    /// Captures the frame just drawn, saving it off the render thread
+    fn save_screenshot(&mut self, clear: wgpu::Color) {
+        match self.rend.capture(clear) {
+            Ok(capture) => {
+                let (ctx, window) = (self.ctx.clone(), Arc::downgrade(&self.window));
+                screenshot::save(capture, move |path| {
+                    *ctx.saved_screenshot.lock().unwrap() = Some(path);
+                    let redraw = || window.upgrade().inspect(|window| window.request_redraw());
+                    redraw();
+                    // again once the notice is over, to clear it
+                    std::thread::sleep(SCREENSHOT_NOTICE);
+                    redraw();
                 });
-        self.rend
-            .render(&mut encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("wgputris.render_pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::default()),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            }));
-        self.rend.queue.submit([encoder.finish()]);
-        output.present();
-        self.rend.finish();
+            }
+            Err(err) => log::error!("failed to capture screenshot: {err:#}"),
+        }
+    }
+
+    /// Opens a window of the game's stats, drawn on the window's device
+    fn open_stats_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+    ) -> anyhow::Result<stats_window::StatsWindow> {
+        let window = stats_window::StatsWindow::new(
+            event_loop,
+            &self.instance,
+            &self.adapter,
+            self.rend.device().clone(),
+            self.rend.queue().clone(),
+            self.game.clone(),
+        )?;
+        self.followers.push(Arc::downgrade(window.window()));
+        Ok(window)
+    }
+
+    /// Schedules the end of the app, the event loop exits on the next event
+    fn schedule_end(&self) {
+        self.ctx.run.store(RunState::EndScheduled, RUNNING_ORDER);
+        self.window.request_redraw();
+    }
+
+    /// Ends the game, there being no thread to wait on in browsers
+    #[cfg(target_arch = "wasm32")]
+    fn join_game_thread(&mut self) {
+        self.ctx.run.store(RunState::Ended, RUNNING_ORDER);
+        self.game_stepper = None;
+    }
+
+    /// Ends the game thread & waits for it, up to a timeout
+    #[cfg(not(target_arch = "wasm32"))]
+    fn join_game_thread(&mut self) {
+        let Some(handle) = self.game_thread.take() else {
+            return;
+        };
+        self.ctx.run.store(RunState::Ended, RUNNING_ORDER);
+        // a paused thread never ticks, so would never see the end
+        self.set_paused(false);
+        handle.thread().unpark();
+        let deadline = time::Instant::now() + GAME_THREAD_JOIN_TIMEOUT;
+        while !handle.is_finished() {
+            if time::Instant::now() >= deadline {
+                log::warn!("game thread didn't stop, leaving it behind");
+                return;
+            }
+            std::thread::sleep(GAME_THREAD_JOIN_POLL);
+        }
+        if handle.join().is_err() {
+            log::error!("game thread panicked");
+        }
+    }
+}
+
+/// Options from the command line, overriding the config
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    pub tick_rate: usize,
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// The seed every game starts from, random when `None`
+    pub seed: Option<u64>,
+    pub mode: Option<game::Mode>,
+    pub das: Option<u32>,
+    pub arr: Option<u32>,
+    /// The soft drop auto repeat rate
+    pub sdf: Option<u32>,
+    /// Turn on cascading clears, see [`game::Setup::cascade`]
+    pub cascade: bool,
+    /// See [`game::Setup::fade`]
+    pub fade: Option<u32>,
+    /// Start in borderless fullscreen
+    pub fullscreen: bool,
+    /// The window's inner size, maximized when `None`
+    pub windowed: Option<winit::dpi::LogicalSize<u32>>,
+    /// Where puzzles are loaded from, see [`game::puzzle::default_dir`]
+    pub puzzles: Option<std::path::PathBuf>,
+    /// Measure the latency of hard drops, shown in the side panel & printed
+    /// on exit
+    pub latency: bool,
+    /// Time the draws on the GPU, shown in the side panel, see
+    /// [`rend::Rend::set_gpu_timing`]
+    pub gpu_timing: bool,
+    pub gpu: GpuOptions,
+}
+
+/// How the graphics adapter is picked
+#[derive(Debug, Clone)]
+pub struct GpuOptions {
+    /// The backends adapters are looked for on, falling back to GL when it's
+    /// all of them
+    pub backends: wgpu::Backends,
+    /// Picks the first adapter whose name contains this, ignoring case, the
+    /// default one when none do
+    pub adapter: Option<String>,
+    /// Low power by default, as the game hardly needs a discrete GPU
+    pub power_preference: wgpu::PowerPreference,
+}
+
+impl Default for GpuOptions {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            adapter: None,
+            power_preference: wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            tick_rate: time::DEFAULT_TICK_RATE,
+            present_mode: None,
+            seed: None,
+            mode: None,
+            das: None,
+            arr: None,
+            sdf: None,
+            cascade: false,
+            fade: None,
+            fullscreen: false,
+            windowed: None,
+            puzzles: None,
+            latency: false,
+            gpu_timing: false,
+            gpu: GpuOptions::default(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    fn apply(&self, setup: &mut game::Setup) {
+        setup.seed = self.seed.or(setup.seed);
+        setup.mode = self.mode.unwrap_or(setup.mode);
+        setup.handling.das = self.das.unwrap_or(setup.handling.das);
+        setup.handling.arr = self.arr.unwrap_or(setup.handling.arr);
+        setup.handling.down_arr = self.sdf.unwrap_or(setup.handling.down_arr);
+        setup.cascade |= self.cascade;
+        setup.fade = self.fade.unwrap_or(setup.fade);
     }
 }
 
 pub struct App {
-    pub state: Option<State>,
+    /// Shared with the render thread, which draws every frame
+    pub state: Option<Arc<Mutex<State>>>,
+    /// The windows open besides the main one, drawn on its device
+    windows: std::collections::HashMap<WindowId, stats_window::StatsWindow>,
+    options: LaunchOptions,
+    #[cfg(not(target_arch = "wasm32"))]
+    render_thread: Option<RenderThread>,
+    #[cfg(not(target_arch = "wasm32"))]
+    unlocked: Option<Unlocked>,
+    /// Where the state's put once it's started, as browsers start it
+    /// asynchronously, see [`App::take_pending`]
+    #[cfg(target_arch = "wasm32")]
+    pending: std::rc::Rc<std::cell::RefCell<Option<State>>>,
 }
 
 impl App {
-    pub fn new() -> Self {
-        Self { state: None }
+    pub fn new(options: LaunchOptions) -> Self {
+        Self {
+            state: None,
+            windows: Default::default(),
+            options,
+            #[cfg(not(target_arch = "wasm32"))]
+            render_thread: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            unlocked: None,
+            #[cfg(target_arch = "wasm32")]
+            pending: Default::default(),
+        }
+    }
+
+    /// Picks up the state once it's started
+    #[cfg(target_arch = "wasm32")]
+    fn take_pending(&mut self) {
+        if self.state.is_none() {
+            self.state = self
+                .pending
+                .borrow_mut()
+                .take()
+                .map(Mutex::new)
+                .map(Arc::new);
+        }
     }
 }
 
+/// What the render thread's asked to do
+#[cfg(not(target_arch = "wasm32"))]
+enum RenderMsg {
+    Redraw,
+    /// The window was resized, the surface following before the next frame
+    Resize(winit::dpi::PhysicalSize<u32>),
+    Exit,
+}
+
+/// Renders frames off the event loop, so they keep coming while it's blocked,
+/// as it is while a window's dragged on Windows
+///
+/// The surface is only resized here, in order with the frames drawn to it.
+#[cfg(not(target_arch = "wasm32"))]
+struct RenderThread {
+    sender: mpsc::Sender<RenderMsg>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RenderThread {
+    fn spawn(
+        state: Arc<Mutex<State>>,
+        sender: mpsc::Sender<RenderMsg>,
+        receiver: mpsc::Receiver<RenderMsg>,
+    ) -> Self {
+        let handle = std::thread::Builder::new()
+            .name("render".into())
+            .spawn(move || {
+                while let Ok(msg) = receiver.recv() {
+                    // the messages that piled up are drawn as one frame, at
+                    // the last size
+                    let mut size = None;
+                    for msg in std::iter::once(msg).chain(receiver.try_iter()) {
+                        match msg {
+                            RenderMsg::Redraw => (),
+                            RenderMsg::Resize(new_size) => size = Some(new_size),
+                            RenderMsg::Exit => return,
+                        }
+                    }
+                    let mut state = state.lock().unwrap();
+                    if let Some(size) = size {
+                        state.resize(size);
+                    }
+                    state.redraw();
+                }
+            })
+            .expect("failed to spawn the render thread");
+        Self { sender, handle }
+    }
+
+    fn send(&self, msg: RenderMsg) {
+        // a send only fails once the thread's gone, when there's no one to ask
+        let _ = self.sender.send(msg);
+    }
+
+    fn join(self) {
+        self.send(RenderMsg::Exit);
+        if self.handle.join().is_err() {
+            log::error!("render thread panicked");
+        }
+    }
+}
+
+/// Handles the main window's events that don't need the state, which the
+/// render thread holds while it draws & presents, so they never wait on vsync
+///
+/// The keys of a game being played go straight to the game thread, & redraws
+/// straight to the render thread.
+#[cfg(not(target_arch = "wasm32"))]
+struct Unlocked {
+    window: WindowId,
+    ctx: Arc<Context>,
+    keys: mpsc::Sender<key::SentKey>,
+    /// Shares its binds with the state's
+    key_store: key::KeyStore,
+    /// Kept alongside the state's
+    modifiers: winit::keyboard::ModifiersState,
+    redraw: mpsc::Sender<RenderMsg>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Unlocked {
+    fn new(state: &State, redraw: mpsc::Sender<RenderMsg>) -> Self {
+        Self {
+            window: state.window.id(),
+            ctx: state.ctx.clone(),
+            keys: state.keys.clone(),
+            key_store: state.key_store.clone(),
+            modifiers: state.modifiers,
+            redraw,
+        }
+    }
+
+    /// Returns whether the event was handled, the rest being left to the state
+    fn handle(&mut self, id: WindowId, event: &WindowEvent) -> bool {
+        if id != self.window || !self.ctx.run.load(RUNNING_ORDER).running() {
+            return false;
+        }
+        match event {
+            WindowEvent::RedrawRequested => {
+                // a game being played is drawn by the timer's render step, see
+                // [`game_loop`], on the same schedule as the rest of its frames
+                if self.ctx.paused.load(RUNNING_ORDER) {
+                    // a send only fails once the thread's gone
+                    let _ = self.redraw.send(RenderMsg::Redraw);
+                } else {
+                    self.ctx.changed.store(true, RUNNING_ORDER);
+                }
+                true
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                false
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                let mods = self.modifiers;
+                let app_key = key::is_fullscreen_toggle(event, mods)
+                    || key::is_force_quit(event, mods)
+                    || key::is_screenshot(event)
+                    || key::is_stats_window(event)
+                    || key::Nav::from_event(event) == Some(key::Nav::Back);
+                !app_key
+                    && key::SentKey::from_event(event.clone(), mods.into())
+                        .is_some_and(|sent| self.send_key(sent))
+            }
+            _ => false,
+        }
+    }
+
+    /// Sends the key to the game being played, unless it backs out or quits,
+    /// as [`State::key_input`] would
+    fn send_key(&self, sent: key::SentKey) -> bool {
+        if self.ctx.paused.load(RUNNING_ORDER) {
+            return false;
+        }
+        let bound = self.key_store.action(sent.chord());
+        !matches!(bound, Some(Action::Back | Action::Quit)) && self.keys.send(sent).is_ok()
+    }
+}
+
+/// Decodes the embedded icon, logging rather than failing when it can't be
+fn window_icon() -> Option<winit::window::Icon> {
+    let decode = || -> anyhow::Result<winit::window::Icon> {
+        let mut reader = png::Decoder::new(ICON).read_info()?;
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut rgba)?;
+        anyhow::ensure!(
+            info.color_type == png::ColorType::Rgba && info.bit_depth == png::BitDepth::Eight,
+            "the icon must be 8-bit RGBA"
+        );
+        rgba.truncate(info.buffer_size());
+        Ok(winit::window::Icon::from_rgba(
+            rgba,
+            info.width,
+            info.height,
+        )?)
+    };
+    decode()
+        .inspect_err(|err| log::error!("failed to load the window icon: {err:#}"))
+        .ok()
+}
+
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = Arc::new(
-            event_loop
-                .create_window(
-                    Window::default_attributes()
-                        .with_visible(false)
-                        .with_maximized(true)
-                        .with_title("wgputris"),
-                )
-                .unwrap(),
-        );
+        let mut attributes = Window::default_attributes()
+            .with_visible(false)
+            .with_maximized(self.options.windowed.is_none())
+            .with_title(screen::Title::Menu.to_string())
+            .with_window_icon(window_icon());
+        if let Some(size) = self.options.windowed {
+            attributes = attributes.with_inner_size(size);
+        }
+        if self.options.fullscreen {
+            attributes =
+                attributes.with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+        }
+        // the canvas is added to the page's body
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            attributes = attributes.with_append(true);
+        }
+        let window = Arc::new(event_loop.create_window(attributes).unwrap());
+        let config = config::load();
+        let keys = config.key_store();
         let (sender, receiver) = mpsc::channel();
-        let game: Arc<Mutex<game::Game>> = Default::default();
-        let ctx: Arc<Context> = Arc::default();
+        let tick_rate = self.options.tick_rate;
+        let game = game::GameState::new(game::Game::new(None, tick_rate));
+        let game = Arc::new(Mutex::new(game));
+        let ctx = Arc::new(Context {
+            latency: self.options.latency.then(latency::Latency::default),
+            ..Default::default()
+        });
 
-        self.state = Some(pollster::block_on(State::new(
-            window.clone(),
-            sender,
-            game.clone(),
-            ctx.clone(),
-        )));
+        let options = self.options.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        let (render_sender, render_receiver) = mpsc::channel();
+        #[cfg(not(target_arch = "wasm32"))]
+        let redraw = render_sender.clone();
+        let start = async move {
+            let mut state = State::new(
+                window.clone(),
+                sender,
+                keys.clone(),
+                game.clone(),
+                ctx.clone(),
+                &options,
+                config,
+            )
+            .await?;
+            let dims = game.lock().unwrap().dims();
+            window.set_min_inner_size(Some(state.settings.sizing.min_window_size(dims)));
+            window.set_visible(true);
+            window.focus_window();
+            window.request_redraw();
 
-        window.set_visible(true);
-        window.focus_window();
-        window.request_redraw();
+            // browsers have no theme files to watch, nor threads to watch them
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let themes_ctx = ctx.clone();
+                let themes_window = Arc::downgrade(&window);
+                themes::watch(move || {
+                    let Some(window) = themes_window.upgrade() else {
+                        return false;
+                    };
+                    themes_ctx.themes_changed.store(true, RUNNING_ORDER);
+                    window.request_redraw();
+                    !themes_ctx.run.load(RUNNING_ORDER).ended()
+                });
+                state.game_thread = Some(game_thread(
+                    redraw, window, receiver, keys, game, ctx, tick_rate,
+                ));
+            }
+            #[cfg(target_arch = "wasm32")]
+            {
+                state.game_stepper =
+                    Some(game_stepper(window, receiver, keys, game, ctx, tick_rate));
+            }
+            Ok::<_, InitError>(state)
+        };
 
-        game_thread(window, receiver, game, ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        match pollster::block_on(start) {
+            Ok(state) => {
+                self.unlocked = Some(Unlocked::new(&state, render_sender.clone()));
+                let state = Arc::new(Mutex::new(state));
+                self.render_thread = Some(RenderThread::spawn(
+                    state.clone(),
+                    render_sender,
+                    render_receiver,
+                ));
+                self.state = Some(state);
+            }
+            Err(err) => {
+                // the game thread is never started, so there's nothing to stop
+                log::error!("failed to start: {err}");
+                eprintln!("wgputris failed to start: {err}");
+                event_loop.exit();
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let pending = self.pending.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match start.await {
+                    Ok(state) => {
+                        let window = state.window.clone();
+                        *pending.borrow_mut() = Some(state);
+                        // wakes the event loop to pick the state up
+                        window.request_redraw();
+                    }
+                    Err(err) => log::error!("failed to start: {err}"),
+                }
+            });
+        }
+    }
+
+    /// Steps the game, browsers having no thread to run it on
+    #[cfg(target_arch = "wasm32")]
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.take_pending();
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+        let Some(stepper) = &mut state.game_stepper else {
+            return;
+        };
+        if stepper.step().is_some() {
+            state.game_stepper = None;
+            return;
+        }
+        event_loop.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(
+            stepper.next_step(),
+        ));
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        self.windows.clear();
+        if let Some(state) = &self.state {
+            let mut state = state.lock().unwrap();
+            state.join_game_thread();
+            if let Some(latency) = &state.ctx.latency {
+                println!("{}", latency.summary());
+            }
+        }
+        // the game thread's gone, so nothing else asks for frames
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(render_thread) = self.render_thread.take() {
+            render_thread.join();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
         if event_loop.exiting() {
             return;
         }
-        let state = self.state.as_mut().expect("state missing");
+        #[cfg(target_arch = "wasm32")]
+        self.take_pending();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(unlocked) = &mut self.unlocked {
+            if unlocked.handle(id, &event) {
+                return;
+            }
+        }
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
         let run_state = state.ctx.run.load(RUNNING_ORDER);
         if !run_state.running() {
             if !run_state.ended() {
@@ -267,19 +1810,79 @@ impl ApplicationHandler for App {
             }
             return;
         }
+        if let Some(window) = self.windows.get_mut(&id) {
+            match event {
+                WindowEvent::CloseRequested => {
+                    self.windows.remove(&id);
+                }
+                WindowEvent::RedrawRequested => match window.render(&state.settings) {
+                    Ok(()) => (),
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        log::error!("stats window out of memory, closing it");
+                        self.windows.remove(&id);
+                    }
+                    Err(err) => log::error!("failed to render stats frame: {err}"),
+                },
+                WindowEvent::Resized(size) => window.resize(size),
+                WindowEvent::KeyboardInput { event, .. } if key::is_stats_window(&event) => {
+                    self.windows.remove(&id);
+                }
+                _ => (),
+            }
+            return;
+        }
         match event {
             WindowEvent::CloseRequested => {
+                state.ctx.run.store(RunState::Ended, RUNNING_ORDER);
                 event_loop.exit();
             }
-            WindowEvent::RedrawRequested => {
-                state.render();
+            #[cfg(not(target_arch = "wasm32"))]
+            WindowEvent::Resized(size) => {
+                state.update_hidden();
+                if let Some(render_thread) = &self.render_thread {
+                    render_thread.send(RenderMsg::Resize(size));
+                }
             }
+            #[cfg(target_arch = "wasm32")]
+            WindowEvent::RedrawRequested => state.redraw(),
+            #[cfg(target_arch = "wasm32")]
             WindowEvent::Resized(size) => {
+                state.update_hidden();
                 state.resize(size); // always followed by a redraw request
             }
+            // the window may have moved onto another monitor
+            WindowEvent::Moved(_) => state.update_render_rate(),
+            WindowEvent::Occluded(occluded) => {
+                state.occluded = occluded;
+                state.update_hidden();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                state.rescale(scale_factor);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                state.modifiers = modifiers.state();
+            }
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(sk) = key::SentKey::from_event(event) {
-                    state.keys.send(sk).unwrap();
+                if key::is_fullscreen_toggle(&event, state.modifiers) {
+                    state.toggle_fullscreen();
+                } else if key::is_force_quit(&event, state.modifiers) {
+                    state.schedule_end();
+                } else if key::is_screenshot(&event) {
+                    state.screenshot = true;
+                    state.window.request_redraw();
+                } else if key::is_stats_window(&event) {
+                    if self.windows.is_empty() {
+                        match state.open_stats_window(event_loop) {
+                            Ok(window) => {
+                                self.windows.insert(window.window().id(), window);
+                            }
+                            Err(err) => log::error!("failed to open the stats window: {err:#}"),
+                        }
+                    } else {
+                        self.windows.clear();
+                    }
+                } else {
+                    state.key_input(event);
                 }
             }
             _ => (),
@@ -287,40 +1890,193 @@ impl ApplicationHandler for App {
     }
 }
 
-fn game_thread(
+/// The game's tick & render steps, run on a thread of their own, or from the
+/// event loop in browsers
+///
+/// The render step asks for frames with `redraw`, while the window's only
+/// woken to see the end.
+fn game_loop(
+    redraw: impl Fn() + Send + 'static,
     window: Arc<Window>,
     keyr: mpsc::Receiver<key::SentKey>,
-    game: Arc<Mutex<game::Game>>,
+    keys: key::KeyStore,
+    game: Arc<Mutex<game::GameState>>,
     ctx: Arc<Context>,
+) -> (
+    impl Fn(time::TimeAction, &time::Timer) -> std::ops::ControlFlow<()> + Send + 'static,
+    impl Fn(time::TimeAction, &time::Timer) + Send + 'static,
 ) {
     use std::ops::ControlFlow;
-    let keys = key::KeyStore::default();
-    time::run(
-        move |action, _| {
-            let mut game = game.lock().unwrap();
-            for key in keyr.try_iter() {
-                if let Some((action, pressed)) = keys.apply_key(key.key, key.pressed) {
-                    if action == Action::Exit {
-                        ctx.run.store(RunState::EndScheduled, RUNNING_ORDER);
-                        return ControlFlow::Break(());
+    let render_ctx = ctx.clone();
+    let tick = move |action: time::TimeAction, _: &time::Timer| {
+        let mut game = game.lock().unwrap();
+        let input_display = ctx.input_display.load(RUNNING_ORDER);
+        for key in keyr.try_iter() {
+            if let Some((action, pressed)) = keys.apply_key(key.chord(), key.pressed) {
+                game.apply_action(action, pressed);
+                if let Some(latency) = &ctx.latency {
+                    if pressed && action == Action::Place {
+                        latency.applied(key.at, time::Instant::now());
                     }
-                    game.apply_action(action, pressed);
                 }
-            }
-            for _ in 0..action.ticks {
-                game.tick(action.now);
-                for action in keys.get_actions() {
-                    game.apply_action(action, true);
+                if pressed {
+                    ctx.tapped.fetch_or(action.bit(), RUNNING_ORDER);
+                    if input_display {
+                        ctx.changed.store(true, RUNNING_ORDER);
+                    }
                 }
             }
-            if ctx.run.load(RUNNING_ORDER).ended() {
-                return ControlFlow::Break(());
+        }
+        // the input display is redrawn as keys change, even when the game
+        // doesn't
+        let held = keys.held_actions();
+        if ctx.held.swap(held, RUNNING_ORDER) != held && input_display {
+            ctx.changed.store(true, RUNNING_ORDER);
+        }
+        if action.dropped_ticks != 0 {
+            log::warn!("dropped {} ticks after a stall", action.dropped_ticks);
+        }
+        for _ in 0..action.ticks {
+            game.tick(action.now);
+            for action in keys.get_actions() {
+                game.apply_action(action, true);
             }
-            ControlFlow::Continue(())
-        },
-        move |_, _| {
+        }
+        if game.update() {
+            ctx.changed.store(true, RUNNING_ORDER);
+        }
+        if let Some(latency) = &ctx.latency {
+            latency.snapshot_built(game.snapshot().version());
+        }
+        if !ctx.run.load(RUNNING_ORDER).running() {
             window.request_redraw();
-        },
-        120,
-    );
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    };
+    let render = move |action: time::TimeAction, timer: &time::Timer| {
+        *render_ctx.oversleep.lock().unwrap() = timer.oversleep();
+        render_ctx
+            .alpha
+            .store(action.alpha.to_bits(), RUNNING_ORDER);
+        // an idle game isn't redrawn, the window redrawing itself for
+        // resizes, menus & settings
+        let changed = render_ctx.changed.swap(false, RUNNING_ORDER);
+        let hidden = render_ctx.hidden.load(RUNNING_ORDER);
+        if (changed || render_ctx.animating.load(RUNNING_ORDER)) && !hidden {
+            redraw();
+        }
+    };
+    (tick, render)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn game_thread(
+    redraw: mpsc::Sender<RenderMsg>,
+    window: Arc<Window>,
+    keyr: mpsc::Receiver<key::SentKey>,
+    keys: key::KeyStore,
+    game: Arc<Mutex<game::GameState>>,
+    ctx: Arc<Context>,
+    tick_rate: usize,
+) -> std::thread::JoinHandle<()> {
+    let paused = ctx.paused.clone();
+    let redraw = move || {
+        // the render thread's only gone once the game's over
+        let _ = redraw.send(RenderMsg::Redraw);
+    };
+    let render_rate = ctx.render_rate.clone();
+    let timer = time::Timer::new(tick_rate, render_rate.load(RUNNING_ORDER));
+    let (tick, render) = game_loop(redraw, window, keyr, keys, game, ctx);
+    time::run(tick, render, timer, paused, render_rate)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn game_stepper(
+    window: Arc<Window>,
+    keyr: mpsc::Receiver<key::SentKey>,
+    keys: key::KeyStore,
+    game: Arc<Mutex<game::GameState>>,
+    ctx: Arc<Context>,
+    tick_rate: usize,
+) -> time::Stepper<()> {
+    let paused = ctx.paused.clone();
+    let redraw = {
+        let window = window.clone();
+        move || window.request_redraw()
+    };
+    let render_rate = ctx.render_rate.clone();
+    let timer = time::Timer::new(tick_rate, render_rate.load(RUNNING_ORDER));
+    let (tick, render) = game_loop(redraw, window, keyr, keys, game, ctx);
+    time::Stepper::new(tick, render, timer, paused, render_rate)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+
+    fn unlocked() -> (
+        Unlocked,
+        mpsc::Receiver<key::SentKey>,
+        mpsc::Receiver<RenderMsg>,
+    ) {
+        let (keys, key_receiver) = mpsc::channel();
+        let (redraw, redraw_receiver) = mpsc::channel();
+        let unlocked = Unlocked {
+            window: WindowId::from(1),
+            ctx: Arc::default(),
+            keys,
+            key_store: key::KeyStore::default(),
+            modifiers: Default::default(),
+            redraw,
+        };
+        (unlocked, key_receiver, redraw_receiver)
+    }
+
+    fn sent(code: KeyCode) -> key::SentKey {
+        key::SentKey {
+            pressed: true,
+            key: key::Key::Code(code),
+            mods: key::Mods::NONE,
+            at: time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn games_are_redrawn_by_their_render_step() {
+        let (mut unlocked, _, redraws) = unlocked();
+        let window = WindowId::from(1);
+        unlocked.ctx.changed.store(false, RUNNING_ORDER);
+        unlocked.ctx.paused.store(true, RUNNING_ORDER);
+        assert!(unlocked.handle(window, &WindowEvent::RedrawRequested));
+        assert!(matches!(redraws.try_recv(), Ok(RenderMsg::Redraw)));
+
+        unlocked.ctx.paused.store(false, RUNNING_ORDER);
+        assert!(unlocked.handle(window, &WindowEvent::RedrawRequested));
+        assert!(redraws.try_recv().is_err());
+        assert!(unlocked.ctx.changed.load(RUNNING_ORDER));
+
+        // other windows & the end are the state's
+        assert!(!unlocked.handle(WindowId::from(2), &WindowEvent::RedrawRequested));
+        unlocked
+            .ctx
+            .run
+            .store(RunState::EndScheduled, RUNNING_ORDER);
+        assert!(!unlocked.handle(window, &WindowEvent::RedrawRequested));
+    }
+
+    #[test]
+    fn game_keys_skip_the_state() {
+        let (unlocked, keys, _) = unlocked();
+        unlocked.ctx.paused.store(false, RUNNING_ORDER);
+        assert!(unlocked.send_key(sent(KeyCode::KeyC)));
+        assert_eq!(keys.try_recv().unwrap().key, key::Key::Code(KeyCode::KeyC));
+        // backing out is the screens'
+        assert!(!unlocked.send_key(sent(KeyCode::Escape)));
+        // as is every key outside of play
+        unlocked.ctx.paused.store(true, RUNNING_ORDER);
+        assert!(!unlocked.send_key(sent(KeyCode::KeyC)));
+        assert!(keys.try_recv().is_err());
+    }
 }
@@ -2,6 +2,10 @@
 
 use winit::event_loop::{ControlFlow, EventLoop};
 
+#[cfg(debug_assertions)]
+#[global_allocator]
+static ALLOC: wgputris::alloc_count::CountingAlloc = wgputris::alloc_count::CountingAlloc;
+
 // NOTE: voxel engine:
 // keep two quad buckets, one for void-like, one normal.
 // grow meshes by iterating over the void-like.
@@ -10,19 +14,146 @@ fn main() {
     #[cfg(debug_assertions)]
     setup_logging();
 
-    let mut app = wgputris::App::new();
+    let app = wgputris::App::new(launch_options());
     let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Wait); // fast rendering
+    // redraws are requested as the game changes, so the loop idles otherwise
+    event_loop.set_control_flow(ControlFlow::Wait);
+    run(event_loop, app);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(event_loop: EventLoop<()>, mut app: wgputris::App) {
     event_loop.run_app(&mut app).unwrap();
+    // the state is only missing when it failed to start
+    if app.state.is_none() {
+        std::process::exit(1);
+    }
 }
 
-// event_loop.set_control_flow(ControlFlow::Wait); // idle rendering
+/// Hands the app to the browser, returning straight away
+#[cfg(target_arch = "wasm32")]
+fn run(event_loop: EventLoop<()>, app: wgputris::App) {
+    use winit::platform::web::EventLoopExtWebSys;
+    event_loop.spawn_app(app);
+}
+
+const USAGE: &str = "\
+usage: wgputris [options]
+
+options:
+  --seed N              start every game from the same seed
+  --mode MODE           the game mode, one of zen, sprint, ultra, marathon,
+                        puzzle or daily
+  --puzzles DIR         where puzzles are loaded from
+  --das MS              delayed auto shift
+  --arr MS              auto repeat rate
+  --sdf MS              soft drop auto repeat rate
+  --cascade             let blocks fall in groups after clears, chaining
+  --fade MS             placed blocks fade out of sight after MS
+  --latency             measure & print the latency of hard drops
+  --gpu-timing          show the GPU time of each draw, when supported
+  --fullscreen          start in borderless fullscreen
+  --windowed WxH        start in a window of the given size
+  --tick-rate N         game ticks per second
+  --present-mode MODE   one of auto-vsync, auto-no-vsync, fifo, fifo-relaxed,
+                        mailbox or immediate
+  --backend NAME        one of auto, vulkan, dx12, metal or gl
+  --adapter NAME        use the adapter whose name contains NAME
+  --power low|high      prefer a low power or a high performance adapter
+  -h, --help            print this message";
+
+fn launch_options() -> wgputris::LaunchOptions {
+    let mut options = wgputris::LaunchOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| usage_error(&format!("{flag} expects a value")))
+        };
+        match flag.as_str() {
+            "--seed" => options.seed = Some(parse(&flag, value())),
+            "--mode" => {
+                let mode = value();
+                options.mode = Some(
+                    wgputris::game::Mode::parse(&mode)
+                        .unwrap_or_else(|| usage_error(&format!("unknown mode {mode:?}"))),
+                );
+            }
+            "--puzzles" => options.puzzles = Some(value().into()),
+            "--das" => options.das = Some(parse(&flag, value())),
+            "--arr" => options.arr = Some(parse(&flag, value())),
+            "--sdf" => options.sdf = Some(parse(&flag, value())),
+            "--cascade" => options.cascade = true,
+            "--fade" => options.fade = Some(parse(&flag, value())),
+            "--latency" => options.latency = true,
+            "--gpu-timing" => options.gpu_timing = true,
+            "--fullscreen" => options.fullscreen = true,
+            "--windowed" => {
+                let size = value();
+                let (width, height) = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .filter(|&(w, h)| w > 0 && h > 0)
+                    .unwrap_or_else(|| usage_error("--windowed expects a size like 1280x720"));
+                options.windowed = Some(winit::dpi::LogicalSize::new(width, height));
+            }
+            "--tick-rate" => {
+                options.tick_rate = parse(&flag, value());
+                if options.tick_rate == 0 {
+                    usage_error("--tick-rate expects a positive number of ticks per second");
+                }
+            }
+            "--present-mode" => {
+                let mode = value();
+                options.present_mode = Some(
+                    wgputris::rend::parse_present_mode(&mode)
+                        .unwrap_or_else(|| usage_error(&format!("unknown present mode {mode:?}"))),
+                );
+            }
+            "--backend" => {
+                let name = value();
+                options.gpu.backends = wgputris::rend::parse_backends(&name)
+                    .unwrap_or_else(|| usage_error(&format!("unknown backend {name:?}")));
+            }
+            "--adapter" => options.gpu.adapter = Some(value()),
+            "--power" => {
+                let name = value();
+                options.gpu.power_preference = wgputris::rend::parse_power_preference(&name)
+                    .unwrap_or_else(|| usage_error(&format!("unknown power preference {name:?}")));
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => usage_error(&format!("unknown option {flag:?}")),
+        }
+    }
+    options
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: String) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| usage_error(&format!("invalid value for {flag}: {value:?}")))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
 
 // for some reason setting env vars doesn't work when compiling for windows
 // so we have this instead
-#[cfg(debug_assertions)]
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
 fn setup_logging() {
     std::env::set_var("RUST_BACKTRACE", "1");
     std::env::set_var("RUST_LOG", "wgputris=info,wgpu=error");
     env_logger::init_from_env(env_logger::Env::new());
 }
+
+/// Logs to the browser's console, where panics go too
+#[cfg(all(debug_assertions, target_arch = "wasm32"))]
+fn setup_logging() {
+    console_error_panic_hook::set_once();
+    console_log::init_with_level(log::Level::Info).ok();
+}
@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+use std::collections::{btree_map, BTreeMap};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::{config, daily, game};
+
+/// The runs kept for each mode
+pub const MAX_RUNS: usize = 10;
+const FILE_NAME: &str = "records.toml";
+
+/// A finished run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Run {
+    pub mode: game::Mode,
+    pub score: u32,
+    pub lines: u32,
+    /// The active play time in milliseconds
+    pub time: u64,
+    /// The seed the run can be replayed from
+    pub seed: u64,
+    /// The 180 rule it was played with, also needed to replay it
+    #[serde(default)]
+    pub rotate_180: game::Rotate180,
+    /// When the run ended, in seconds since the unix epoch
+    pub at: u64,
+}
+
+impl Run {
+    /// The results of a game, ending now
+    pub fn new(game: &game::RenderSnapshot) -> Self {
+        let stats = game.stats();
+        Self {
+            mode: game.mode(),
+            score: stats.score(),
+            lines: stats.lines(),
+            time: game.active_elapsed().as_millis() as u64,
+            seed: game.seed(),
+            rotate_180: game.rotate_180(),
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        }
+    }
+}
+
+/// The best runs of each mode, best first, the solved puzzles & the daily
+/// challenges played
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Records {
+    #[serde(default)]
+    runs: Vec<Run>,
+    /// The names of the solved puzzles
+    #[serde(default)]
+    solved: Vec<String>,
+    /// The official run of each daily challenge, by its `YYYY-MM-DD`
+    #[serde(default)]
+    daily: BTreeMap<String, Run>,
+}
+
+impl Records {
+    /// The best runs of a mode, best first
+    pub fn runs(&self, mode: game::Mode) -> impl Iterator<Item = &Run> + '_ {
+        self.runs.iter().filter(move |run| run.mode == mode)
+    }
+
+    pub fn best(&self, mode: game::Mode) -> Option<&Run> {
+        self.runs(mode).next()
+    }
+
+    /// Adds a run, keeping the best [`MAX_RUNS`] of its mode
+    ///
+    /// Returns whether it's the new best.
+    pub fn update(&mut self, mode: game::Mode, run: Run) -> bool {
+        let is_best = self
+            .best(mode)
+            .is_none_or(|best| compare(mode, &run, best).is_lt());
+        self.runs.push(run);
+        // stable, so earlier runs win ties
+        self.runs
+            .sort_by(|a, b| a.mode.cmp(&b.mode).then_with(|| compare(a.mode, a, b)));
+        let mut kept = 0;
+        self.runs.retain(|run| {
+            kept += (run.mode == mode) as usize;
+            run.mode != mode || kept <= MAX_RUNS
+        });
+        is_best
+    }
+
+    pub fn is_solved(&self, puzzle: &str) -> bool {
+        self.solved.iter().any(|name| name == puzzle)
+    }
+
+    /// Records a puzzle as solved, returning whether it wasn't already
+    pub fn solve(&mut self, puzzle: &str) -> bool {
+        if self.is_solved(puzzle) {
+            return false;
+        }
+        self.solved.push(puzzle.into());
+        true
+    }
+
+    /// The official run of a day's challenge
+    pub fn daily(&self, date: daily::Date) -> Option<&Run> {
+        self.daily.get(&*date.written())
+    }
+
+    /// Records a run of a day's challenge, returning whether it's the official
+    /// one, being the first
+    ///
+    /// Later runs are practice & aren't kept.
+    pub fn play_daily(&mut self, date: daily::Date, run: Run) -> bool {
+        match self.daily.entry(date.to_string()) {
+            btree_map::Entry::Occupied(_) => false,
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(run);
+                true
+            }
+        }
+    }
+}
+
+/// Orders runs of a mode, better runs first
+///
+/// Sprints are raced, finished ones going by time & the rest after them by
+/// how far they got. Every other mode's played for score.
+fn compare(mode: game::Mode, a: &Run, b: &Run) -> Ordering {
+    match mode {
+        game::Mode::Sprint => {
+            let reached = |run: &Run| run.lines.min(game::SPRINT_LINES);
+            reached(b).cmp(&reached(a)).then(a.time.cmp(&b.time))
+        }
+        game::Mode::Zen
+        | game::Mode::Puzzle
+        | game::Mode::Daily
+        | game::Mode::Ultra
+        | game::Mode::Marathon => b.score.cmp(&a.score).then(b.lines.cmp(&a.lines)),
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("wgputris").join(FILE_NAME))
+}
+
+/// Loads the saved records, empty when they're missing or unreadable
+pub fn load() -> Records {
+    let Some(path) = path() else {
+        return Records::default();
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Records::default(),
+        Err(err) => {
+            log::error!("failed to read records: {err}");
+            return Records::default();
+        }
+    };
+    toml::from_str(&text).unwrap_or_else(|err| {
+        log::error!("ignoring corrupted records: {err}");
+        Records::default()
+    })
+}
+
+pub fn save(records: &Records) -> anyhow::Result<()> {
+    let path = path().context("no data directory")?;
+    config::write_atomic(&path, &toml::to_string(records)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mode: game::Mode, score: u32, lines: u32, secs: u64) -> Run {
+        Run {
+            mode,
+            score,
+            lines,
+            time: secs * 1000,
+            seed: 0,
+            rotate_180: Default::default(),
+            at: 0,
+        }
+    }
+
+    fn best(records: &Records, mode: game::Mode) -> (u32, u32, u64) {
+        let best = records.best(mode).unwrap();
+        (best.score, best.lines, best.time / 1000)
+    }
+
+    #[test]
+    fn sprints_are_raced() {
+        use game::Mode::Sprint;
+        let mut records = Records::default();
+        assert!(records.update(Sprint, run(Sprint, 9000, 40, 90)));
+        // a higher score doesn't beat a faster time
+        assert!(!records.update(Sprint, run(Sprint, 20000, 40, 100)));
+        assert!(records.update(Sprint, run(Sprint, 100, 41, 60)));
+        assert_eq!(best(&records, Sprint), (100, 41, 60));
+        // topping out early, however fast, isn't a finish
+        assert!(!records.update(Sprint, run(Sprint, 0, 39, 10)));
+        let times: Vec<_> = records.runs(Sprint).map(|run| run.time / 1000).collect();
+        assert_eq!(times, [60, 90, 100, 10]);
+    }
+
+    #[test]
+    fn other_modes_are_scored() {
+        for mode in [game::Mode::Ultra, game::Mode::Marathon, game::Mode::Zen] {
+            let mut records = Records::default();
+            assert!(records.update(mode, run(mode, 5000, 30, 120)));
+            assert!(!records.update(mode, run(mode, 4000, 60, 60)));
+            assert!(records.update(mode, run(mode, 6000, 10, 120)));
+            assert_eq!(best(&records, mode), (6000, 10, 120), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn only_the_best_runs_are_kept() {
+        use game::Mode::{Sprint, Ultra};
+        let mut records = Records::default();
+        records.update(Ultra, run(Ultra, 1, 0, 120));
+        for secs in 0..MAX_RUNS as u64 + 5 {
+            records.update(Sprint, run(Sprint, 0, 40, 100 - secs));
+        }
+        assert_eq!(records.runs(Sprint).count(), MAX_RUNS);
+        assert_eq!(best(&records, Sprint).2, 100 - MAX_RUNS as u64 - 4);
+        // other modes' runs aren't pushed out
+        assert_eq!(records.runs(Ultra).count(), 1);
+    }
+}
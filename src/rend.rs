@@ -1,65 +1,237 @@
-use std::rc::Rc;
-use std::sync::Mutex;
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
+use anyhow::Context;
 use bytemuck::{Pod, Zeroable};
 use indexmap::IndexMap;
 use wgpu::util::DeviceExt;
 
 use crate::styling::Colour;
 
-pub use self::quad_layer::QuadLayer;
-pub use self::text_layer::{TextLayer, TextLayerDesc};
+pub use self::quad_layer::{QuadLayer, QuadSink, QuadsMut};
+pub use self::text_layer::{SpanAttrs, TextLayer, TextLayerDesc};
 
+pub mod capture;
 pub mod quad_layer;
+pub mod skin;
+pub mod target;
 pub mod text_layer;
+pub mod timing;
 
 #[derive(Debug)]
 pub struct Rend {
+    /// The size everything is drawn at, the target's when there is one
     size: ScreenSize,
+    surface_size: ScreenSize,
     pub queue: wgpu::Queue,
     pub device: wgpu::Device,
-    pub surface: wgpu::Surface<'static>,
+    output: Output,
     surface_format: wgpu::TextureFormat,
+    sample_flags: wgpu::TextureFormatFeatureFlags,
+    multisample: wgpu::MultisampleState,
+    present_mode: wgpu::PresentMode,
+    present_modes: Vec<wgpu::PresentMode>,
+    quad_mode: QuadMode,
+    msaa_view: Option<wgpu::TextureView>,
     uniform_buffer: wgpu::Buffer,
+    uniform_layout: wgpu::BindGroupLayout,
     uniform_bind: wgpu::BindGroup,
     pipeline: wgpu::RenderPipeline,
+    textured_pipeline: wgpu::RenderPipeline,
+    gradient_pipeline: wgpu::RenderPipeline,
+    skin_layout: wgpu::BindGroupLayout,
+    skin: Option<skin::Skin>,
+    target: Option<target::Target>,
+    blit: target::Blit,
     qrend: QRend,
     trend: TRend,
+    /// Times the quad & text draws, see [`Rend::set_gpu_timing`]
+    timer: Option<timing::Timer>,
 }
 
+// frames are drawn on a thread of their own, so the renderer has to stay Send
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Rend>();
+    assert_send::<TextLayer>();
+};
+
 struct TRend {
-    font_system: Rc<Mutex<glyphon::FontSystem>>,
+    multisample: wgpu::MultisampleState,
+    /// Shared with every layer, which shapes its own text
+    font_system: Arc<Mutex<glyphon::FontSystem>>,
     swash_cache: glyphon::SwashCache,
     viewport: glyphon::Viewport,
     atlas: glyphon::TextAtlas,
     text_renderer: glyphon::TextRenderer,
-    layers: IndexMap<&'static str, TextLayer>,
+    layers: IndexMap<Cow<'static, str>, TextLayer>,
+}
+
+/// Where frames are drawn to
+#[derive(Debug)]
+pub enum Output {
+    /// A window's surface, presented every frame
+    Surface(wgpu::Surface<'static>),
+    /// A texture of its own, for drawing without a window, see
+    /// [`Rend::new_headless`]
+    Texture(wgpu::Texture),
 }
 
 #[derive(Debug, Default)]
 pub struct QRend {
-    layers: IndexMap<&'static str, QuadLayer>,
+    layers: IndexMap<Cow<'static, str>, QuadLayer>,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Quad {
     pub colour: Colour,
+    /// Negative when it's partly off the top or left, clipped by the
+    /// rasterizer
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// The skin sprite, tinted by `colour`, only drawn by textured layers
+    pub uv: Option<Uv>,
+    /// The width in pixels of the edge drawn in `border_colour`, drawn by
+    /// the shader
+    pub border: f32,
+    /// Blended over the edge by its alpha, e.g. half transparent black to
+    /// darken it
+    pub border_colour: Colour,
+    /// The corner radius in pixels
+    pub radius: f32,
+    /// The strength of the top-lit bevel, from 0 to 1
+    pub bevel: f32,
+    /// Only draws the border, in `colour` rather than `border_colour`
+    pub hollow: bool,
+    /// The colour of the bottom edge, blended from `colour` at the top, only
+    /// drawn by gradient layers
+    pub gradient: Option<Colour>,
+}
+
+impl Quad {
+    /// The border, radius, bevel & hollow flag, as passed to the shaders
+    fn shape(&self) -> [f32; 4] {
+        [
+            self.border,
+            self.radius,
+            self.bevel,
+            if self.hollow { 1.0 } else { 0.0 },
+        ]
+    }
+}
+
+/// A rectangle in physical pixels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
     pub x: u32,
     pub y: u32,
     pub width: u32,
     pub height: u32,
 }
 
+impl Rect {
+    /// Clips the rectangle to one of the given size at the origin
+    pub fn clamp(self, width: u32, height: u32) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// Texture coordinates of a quad
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Uv {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// The pipeline a quad layer is drawn with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuadPipeline {
+    #[default]
+    Flat,
+    /// Samples the skin, layers are skipped while no skin is set
+    Textured,
+    /// Blends each quad's colours from top to bottom, see [`Quad::gradient`]
+    Gradient,
+}
+
+impl QuadPipeline {
+    /// How the layer's quads are laid out, gradients always being expanded
+    /// as instances only carry one colour
+    pub fn quad_mode(self, mode: QuadMode) -> QuadMode {
+        match self {
+            QuadPipeline::Gradient => QuadMode::Vertices,
+            _ => mode,
+        }
+    }
+}
+
+/// How quads are laid out for the GPU
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuadMode {
+    /// One [`Instance`] per quad, its corners computed in the shader
+    #[default]
+    Instanced,
+    /// Six [`Vertex`]s per quad, the fallback for instanced drawing
+    Vertices,
+}
+
+impl QuadMode {
+    /// The size of a single quad in a layer's buffer
+    pub fn bytes_per_quad(self) -> usize {
+        match self {
+            QuadMode::Instanced => std::mem::size_of::<Instance>(),
+            QuadMode::Vertices => VERTICES_PER_QUAD * std::mem::size_of::<Vertex>(),
+        }
+    }
+}
+
+/// A quad as drawn by the instanced pipelines
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+pub struct Instance {
+    /// x, y, width, height
+    pub rect: [i32; 4],
+    pub colour: [u8; 4],
+    /// left, top, right, bottom
+    pub uv: [u16; 4],
+    /// See [`Quad::shape`]
+    pub shape: [f32; 4],
+    pub border_colour: [u8; 4],
+}
+
 #[repr(C)]
 #[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
 pub struct Vertex {
     pub colour: [f32; 4],
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
+    pub uv: [f32; 2],
+    /// The position within the quad
+    pub local: [f32; 2],
+    pub size: [f32; 2],
+    /// See [`Quad::shape`]
+    pub shape: [f32; 4],
+    pub border_colour: [f32; 4],
 }
 
-#[repr(C)]
-#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+/// A size in physical pixels, with the scale factor it's at
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct ScreenSize {
     width: u32,
     height: u32,
@@ -74,10 +246,43 @@ impl ScreenSize {
             scale,
         }
     }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// The quad shaders' `Uniforms`, kept apart from [`ScreenSize`] so its layout
+/// only follows the WGSL
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+struct Uniforms {
+    bounds: [u32; 2],
+    /// Uniform buffers are bound in 16 byte steps
+    _padding: [u32; 2],
+}
+
+impl Uniforms {
+    fn new(size: ScreenSize) -> Self {
+        Self {
+            bounds: [size.width, size.height],
+            _padding: [0; 2],
+        }
+    }
 }
 
 const UNIFORM_SIZE: std::num::NonZero<u64> =
-    wgpu::BufferSize::new(std::mem::size_of::<ScreenSize>() as u64).unwrap();
+    wgpu::BufferSize::new(std::mem::size_of::<Uniforms>() as u64).unwrap();
+// `bounds: vec2<u32>` at offset 0, padded to 16
+const _: () = assert!(UNIFORM_SIZE.get() == 16);
 
 impl Rend {
     pub fn new(
@@ -85,66 +290,496 @@ impl Rend {
         device: wgpu::Device,
         queue: wgpu::Queue,
         format: wgpu::TextureFormat,
-        surface: wgpu::Surface<'static>,
+        output: Output,
+        sample_count: u32,
+        sample_flags: wgpu::TextureFormatFeatureFlags,
     ) -> Self {
+        let multisample = multisample_state(valid_sample_count(sample_flags, sample_count));
         let (uniform_bind, uniform_layout, uniform_buffer) = uniform_binding(&device, size);
-        let pipeline = create_pipeline(&device, format, uniform_layout);
-        let this = Self {
+        let skin_layout = skin::bind_group_layout(&device);
+        let quad_mode = QuadMode::default();
+        let (pipeline, textured_pipeline, gradient_pipeline) = create_pipelines(
+            &device,
+            format,
+            &uniform_layout,
+            &skin_layout,
+            multisample,
+            quad_mode,
+        );
+        let mut this = Self {
             size,
+            surface_size: size,
+            target: None,
+            blit: target::Blit::new(&device, format),
             qrend: QRend::default(),
-            trend: TRend::new(&device, &queue, format),
+            trend: TRend::new(&device, &queue, format, multisample),
+            msaa_view: create_msaa_view(&device, format, size, multisample.count),
             queue,
             device,
-            surface,
+            output,
             surface_format: format,
+            sample_flags,
+            multisample,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            present_modes: Vec::new(),
+            quad_mode,
             uniform_buffer,
+            uniform_layout,
             uniform_bind,
             pipeline,
+            textured_pipeline,
+            gradient_pipeline,
+            skin_layout,
+            skin: None,
+            timer: None,
         };
-        this.configure_surface();
+        this.configure_output();
+        this.trend.resize(&this.queue, size);
         this
     }
 
-    pub fn configure_surface(&self) {
+    /// Draws to a texture rather than a window, on a software adapter when
+    /// there is one
+    ///
+    /// The texture's 8-bit sRGB RGBA, read back with [`Rend::capture`].
+    pub fn new_headless(width: u32, height: u32) -> anyhow::Result<Self> {
+        pollster::block_on(async {
+            let (adapter, device, queue) = headless_device().await?;
+            let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+            let size = ScreenSize::new(winit::dpi::PhysicalSize::new(width, height), 1.0);
+            let texture = output_texture(&device, format, size);
+            let sample_flags = adapter.get_texture_format_features(format).flags;
+            Ok(Self::new(
+                size,
+                device,
+                queue,
+                format,
+                Output::Texture(texture),
+                1,
+                sample_flags,
+            ))
+        })
+    }
+
+    /// Fits the output to the surface size, configuring the surface or
+    /// making a new texture
+    pub fn configure_output(&mut self) {
+        // a surface can't be configured without an area, so a minimized
+        // window keeps its last configuration
+        if self.surface_size.width == 0 || self.surface_size.height == 0 {
+            return;
+        }
+        let surface = match &mut self.output {
+            Output::Surface(surface) => surface,
+            Output::Texture(texture) => {
+                let size = self.surface_size;
+                if (texture.width(), texture.height()) != (size.width, size.height) {
+                    *texture = output_texture(&self.device, self.surface_format, size);
+                }
+                return;
+            }
+        };
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: self.surface_format,
             view_formats: vec![self.surface_format.add_srgb_suffix()],
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            width: self.size.width,
-            height: self.size.height,
+            width: self.surface_size.width,
+            height: self.surface_size.height,
             desired_maximum_frame_latency: 2,
-            present_mode: wgpu::PresentMode::AutoVsync,
+            present_mode: self.present_mode,
         };
-        self.surface.configure(&self.device, &surface_config);
+        surface.configure(&self.device, &surface_config);
     }
 
+    /// Acquires the view to draw the next frame to, & the surface texture to
+    /// present when drawing to a surface
+    ///
+    /// A lost or outdated surface is reconfigured and acquired once more.
+    fn acquire_frame(
+        &mut self,
+    ) -> Result<(wgpu::TextureView, Option<wgpu::SurfaceTexture>), wgpu::SurfaceError> {
+        let surface = match &self.output {
+            Output::Surface(surface) => surface,
+            Output::Texture(texture) => {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                return Ok((view, None));
+            }
+        };
+        let output = match surface.get_current_texture() {
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.configure_output();
+                let Output::Surface(surface) = &self.output else {
+                    unreachable!()
+                };
+                surface.get_current_texture()?
+            }
+            result => result?,
+        };
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        Ok((view, Some(output)))
+    }
+
+    /// Resizes the surface, everything drawn being resized along with it
+    /// unless there's a target
     pub fn resize(&mut self, size: ScreenSize) {
-        let bytes = bytemuck::bytes_of(&size);
-        self.queue
+        self.surface_size = size;
+        self.configure_output();
+        if self.target.is_none() {
+            self.set_draw_size(size);
+        }
+    }
+
+    /// Draws to an offscreen target of `size`, letterboxed onto the surface,
+    /// or straight to the surface when `None`
+    pub fn set_target(&mut self, size: Option<ScreenSize>) {
+        if self.target.as_ref().map(target::Target::size) == size {
+            return;
+        }
+        self.target = size
+            .map(|size| target::Target::new(&self.device, self.surface_format, size, &self.blit));
+        self.set_draw_size(size.unwrap_or(self.surface_size));
+    }
+
+    /// The largest width or height a target can have
+    pub fn max_target_size(&self) -> u32 {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+    /// The size of the target, if there is one
+    pub fn target_size(&self) -> Option<ScreenSize> {
+        self.target.as_ref().map(target::Target::size)
+    }
+
+    /// Changes how the target is filtered when scaled to the surface
+    pub fn set_target_filter(&mut self, filter: wgpu::FilterMode) {
+        self.blit
+            .set_filter(&self.device, filter, self.target.as_mut());
+    }
+
+    /// The size everything's drawn at, the target's when there is one
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    /// Changes the size everything is drawn at, see [`ScreenSize`]
+    fn set_draw_size(&mut self, size: ScreenSize) {
+        let uniforms = Uniforms::new(size);
+        let bytes = bytemuck::bytes_of(&uniforms);
+        match self
+            .queue
             .write_buffer_with(&self.uniform_buffer, 0, UNIFORM_SIZE)
-            .expect("invalid quad buffer size")
-            .copy_from_slice(bytes);
+        {
+            Some(mut view) => view.copy_from_slice(bytes),
+            // the staged write failed, so it's written through a copy instead
+            None => self.queue.write_buffer(&self.uniform_buffer, 0, bytes),
+        }
         self.size = size;
-        self.configure_surface();
+        self.trend.resize(&self.queue, size);
+        self.msaa_view = create_msaa_view(
+            &self.device,
+            self.surface_format,
+            size,
+            self.multisample.count,
+        );
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.present_mode
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Sets the present modes the surface supports, from its capabilities
+    pub fn set_supported_present_modes(&mut self, present_modes: Vec<wgpu::PresentMode>) {
+        self.present_modes = present_modes;
+    }
+
+    /// Changes the present mode, reconfiguring the surface
+    ///
+    /// Unsupported modes fall back to the closest supported one. Frames are
+    /// still paced by the render rate, so no vsync doesn't mean unbounded
+    /// rendering.
+    pub fn set_present_mode(&mut self, requested: wgpu::PresentMode) {
+        let present_mode = present_mode_fallbacks(requested)
+            .iter()
+            .copied()
+            .find(|&mode| self.supports_present_mode(mode))
+            .unwrap_or(wgpu::PresentMode::AutoVsync);
+        if present_mode != requested {
+            log::warn!("present mode {requested:?} is unsupported, using {present_mode:?}");
+        }
+        if present_mode == self.present_mode {
+            return;
+        }
+        self.present_mode = present_mode;
+        self.configure_output();
+    }
+
+    fn supports_present_mode(&self, present_mode: wgpu::PresentMode) -> bool {
+        use wgpu::PresentMode::*;
+        // wgpu resolves the auto modes itself, and fifo is always supported
+        matches!(present_mode, AutoVsync | AutoNoVsync | Fifo)
+            || self.present_modes.contains(&present_mode)
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.multisample.count
+    }
+
+    /// Changes the MSAA sample count, rebuilding everything that depends on it
+    ///
+    /// Counts the surface format doesn't support fall back to 1.
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        let sample_count = valid_sample_count(self.sample_flags, sample_count);
+        if sample_count == self.multisample.count {
+            return;
+        }
+        self.multisample = multisample_state(sample_count);
+        self.rebuild_pipelines();
+        self.trend.set_multisample(&self.device, self.multisample);
+        self.msaa_view =
+            create_msaa_view(&self.device, self.surface_format, self.size, sample_count);
+    }
+
+    pub fn quad_mode(&self) -> QuadMode {
+        self.quad_mode
+    }
+
+    /// Switches between instanced & expanded quads, reuploading every layer
+    pub fn set_quad_mode(&mut self, quad_mode: QuadMode) {
+        if quad_mode == self.quad_mode {
+            return;
+        }
+        self.quad_mode = quad_mode;
+        self.rebuild_pipelines();
+        for layer in self.qrend.layers.values_mut() {
+            layer.mark_changed();
+        }
+    }
+
+    fn rebuild_pipelines(&mut self) {
+        (
+            self.pipeline,
+            self.textured_pipeline,
+            self.gradient_pipeline,
+        ) = create_pipelines(
+            &self.device,
+            self.surface_format,
+            &self.uniform_layout,
+            &self.skin_layout,
+            self.multisample,
+            self.quad_mode,
+        );
+    }
+
+    /// The colour attachment for rendering to `view`
+    ///
+    /// With MSAA enabled this renders to the multisampled texture and
+    /// resolves into `view`.
+    pub fn color_attachment<'a>(
+        &'a self,
+        view: &'a wgpu::TextureView,
+        clear: wgpu::Color,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        let (view, resolve_target, store) = match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(view), wgpu::StoreOp::Discard),
+            None => (view, None, wgpu::StoreOp::Store),
+        };
+        wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(clear),
+                store,
+            },
+        }
+    }
+
+    /// Swaps the skin used by textured layers, `None` removing it
+    pub fn set_skin(&mut self, image: Option<&skin::SkinImage>) {
+        self.skin =
+            image.map(|image| skin::Skin::new(&self.device, &self.queue, &self.skin_layout, image));
+    }
+
+    pub fn has_skin(&self) -> bool {
+        self.skin.is_some()
+    }
+
+    /// Prepares every layer, draws them to the output & presents it
+    ///
+    /// Frames are skipped when acquiring the surface texture times out, other
+    /// errors being left to the caller, see [`Rend::acquire_frame`].
+    pub fn render_frame(&mut self, clear: wgpu::Color) -> Result<(), wgpu::SurfaceError> {
+        self.prepare();
+        let (view, output) = match self.acquire_frame() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::warn!("surface timed out, skipping frame");
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        self.render_to_view(&view, clear);
+        if let Some(output) = output {
+            output.present();
+        }
+        self.finish();
+        Ok(())
+    }
+
+    /// Draws a frame & reads it back, blocking until it's done, e.g. to
+    /// compare against a golden image
+    pub fn render_to_image(&mut self, clear: wgpu::Color) -> anyhow::Result<capture::Image> {
+        self.render_frame(clear)?;
+        self.capture(clear)?.read()
+    }
+
+    /// Draws every layer to `view` & submits it, the layers having been
+    /// prepared already
+    pub fn render_to_view(&mut self, view: &wgpu::TextureView, clear: wgpu::Color) {
+        let mut encoder = self.command_encoder();
+        if let Some(timer) = &mut self.timer {
+            timer.begin();
+        }
+        self.record_frame(&mut encoder, view, clear);
+        if let Some(timer) = &self.timer {
+            timer.resolve(&mut encoder);
+        }
+        self.queue.submit([encoder.finish()]);
+        if let Some(timer) = &mut self.timer {
+            timer.submitted();
+        }
+    }
+
+    /// Times the quad & text draws of each frame on the GPU, when the device
+    /// was created with the [`timing::FEATURES`]
+    ///
+    /// Devices without them time nothing, [`Rend::gpu_times`] staying `None`.
+    pub fn set_gpu_timing(&mut self, enabled: bool) {
+        self.timer = enabled
+            .then(|| timing::Timer::new(&self.device, &self.queue))
+            .flatten();
+    }
+
+    /// The average GPU time of the draws over the last frames
+    pub fn gpu_times(&self) -> Option<timing::GpuTimes> {
+        self.timer.as_ref()?.times()
+    }
+
+    fn command_encoder(&self) -> wgpu::CommandEncoder {
+        self.device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("wgputris.rend.encoder"),
+            })
+    }
+
+    /// Records the frame's passes, drawing every layer to `view` or through
+    /// the target when there is one
+    pub fn record_frame(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        clear: wgpu::Color,
+    ) {
+        let scene = self.target.as_ref().map_or(view, |target| &target.view);
+        self.render(&mut encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("wgputris.render_pass"),
+            color_attachments: &[Some(self.color_attachment(scene, clear))],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        }));
+        let Some(target) = &self.target else {
+            return;
+        };
+        let mut blit_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("wgputris.blit_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.blit.render(&mut blit_pass, target, self.surface_size);
+    }
+
+    /// Draws the last frame again into a texture that's copied back, see
+    /// [`capture::Capture::read`]
+    pub fn capture(&mut self, clear: wgpu::Color) -> anyhow::Result<capture::Capture> {
+        let capture = capture::Capture::new(&self.device, self.surface_format, self.surface_size)?;
+        let mut encoder = self.command_encoder();
+        self.record_frame(&mut encoder, &capture.view, clear);
+        capture.copy(&mut encoder);
+        self.queue.submit([encoder.finish()]);
+        Ok(capture)
     }
 
     pub fn render(&mut self, render_pass: &mut wgpu::RenderPass) {
-        render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.uniform_bind, &[]);
-        self.qrend.render(render_pass);
-        self.trend.render(&self.size, &self.queue, render_pass);
+        let textured = self
+            .skin
+            .as_ref()
+            .map(|skin| (&self.textured_pipeline, &skin.bind_group));
+        let timer = self.timer.as_ref();
+        if let Some(timer) = timer {
+            timer.write(render_pass, 0);
+        }
+        self.qrend.render(
+            render_pass,
+            self.size,
+            self.quad_mode,
+            &self.pipeline,
+            textured,
+            &self.gradient_pipeline,
+        );
+        if let Some(timer) = timer {
+            timer.write(render_pass, 1);
+        }
+        self.trend.render(render_pass);
+        if let Some(timer) = timer {
+            timer.write(render_pass, 2);
+        }
     }
 
-    pub fn create_quad_layer(&self, name: &'static str) -> QuadLayer {
+    pub fn create_quad_layer(&self, name: impl Into<Cow<'static, str>>) -> QuadLayer {
         QuadLayer::new(name, "wgputris.rend.layer", &self.device, 0)
     }
 
+    /// A quad layer starting with `quads`, see [`QuadLayer::with_quads`]
+    pub fn create_quad_layer_with(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        quads: Vec<Quad>,
+    ) -> QuadLayer {
+        QuadLayer::with_quads(
+            name,
+            "wgputris.rend.layer",
+            &self.device,
+            quads,
+            self.quad_mode,
+        )
+    }
+
     pub fn push_quad_layer(&mut self, layer: QuadLayer) {
-        self.qrend.layers.insert(layer.name(), layer);
+        self.qrend.layers.insert(layer.name.clone(), layer);
     }
 
-    pub fn gen_quad_layer(&mut self, name: &'static str) {
+    pub fn gen_quad_layer(&mut self, name: impl Into<Cow<'static, str>>) {
         self.push_quad_layer(self.create_quad_layer(name));
     }
 
@@ -158,7 +793,29 @@ impl Rend {
     }
 
     pub fn push_text_layer(&mut self, layer: TextLayer) {
-        self.trend.layers.insert(layer.name(), layer);
+        self.trend.layers.insert(layer.name.clone(), layer);
+    }
+
+    /// Loads a TTF or OTF font, making it the default family of text layers
+    ///
+    /// Returns the font's family. It's for startup, as text already shaped
+    /// keeps the font from before.
+    pub fn load_font(&mut self, path: &Path) -> anyhow::Result<String> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read font {}", path.display()))?;
+        let font_system = &mut self.trend.font_system.lock().unwrap();
+        let db = font_system.db_mut();
+        let loaded = db.len();
+        db.load_font_data(data);
+        // faces are only ever added, so the font's come after the rest
+        let (family, _) = db
+            .faces()
+            .skip(loaded)
+            .find_map(|face| face.families.first())
+            .with_context(|| format!("no fonts in {}", path.display()))?;
+        let family = family.clone();
+        db.set_sans_serif_family(family.as_str());
+        Ok(family)
     }
 
     pub fn gen_text_layer(&mut self, metrics: glyphon::Metrics, desc: TextLayerDesc) {
@@ -166,16 +823,49 @@ impl Rend {
         self.push_text_layer(layer);
     }
 
-    pub fn get_quad_mut(&mut self, label: &'static str) -> Option<&mut QuadLayer> {
+    pub fn get_quad_mut(&mut self, label: &str) -> Option<&mut QuadLayer> {
         self.qrend.layers.get_mut(label)
     }
-    pub fn get_text_mut(&mut self, label: &'static str) -> Option<&mut TextLayer> {
+    pub fn get_text_mut(&mut self, label: &str) -> Option<&mut TextLayer> {
         self.trend.layers.get_mut(label)
     }
 
+    /// Removes a quad layer, keeping the order of the rest
+    ///
+    /// Layers should be removed before [`Rend::prepare`], never between it
+    /// and the frame's submission.
+    pub fn remove_quad_layer(&mut self, label: &str) -> Option<QuadLayer> {
+        self.qrend.layers.shift_remove(label)
+    }
+
+    /// Removes a text layer, keeping the order of the rest
+    ///
+    /// Layers should be removed before [`Rend::prepare`], never between it
+    /// and the frame's submission.
+    pub fn remove_text_layer(&mut self, label: &str) -> Option<TextLayer> {
+        self.trend.layers.shift_remove(label)
+    }
+
+    /// Moves a quad layer to `index` in the draw order, later layers drawing
+    /// over earlier ones
+    ///
+    /// Returns false if there is no such layer, `index` is clamped.
+    pub fn set_layer_order(&mut self, label: &str, index: usize) -> bool {
+        move_layer(&mut self.qrend.layers, label, index)
+    }
+
+    /// Moves a text layer to `index` in the draw order, see
+    /// [`Rend::set_layer_order`]
+    pub fn set_text_layer_order(&mut self, label: &str, index: usize) -> bool {
+        move_layer(&mut self.trend.layers, label, index)
+    }
+
     pub fn prepare(&mut self) {
+        if let Some(timer) = &mut self.timer {
+            timer.collect(&self.device);
+        }
         for (_, layer) in &mut self.qrend.layers {
-            layer.prepare(&self.device, &self.queue);
+            layer.prepare(&self.device, &self.queue, self.quad_mode);
         }
         self.trend.prepare(&self.device, &self.queue);
     }
@@ -185,13 +875,66 @@ impl Rend {
     }
 }
 
+/// A device without a window, on a software adapter when there is one
+pub(crate) async fn headless_device() -> anyhow::Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue)>
+{
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let mut adapter = None;
+    for force_fallback_adapter in [true, false] {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter,
+                ..Default::default()
+            })
+            .await;
+        if adapter.is_some() {
+            break;
+        }
+    }
+    let adapter = adapter.ok_or_else(|| anyhow::anyhow!("no graphics adapter"))?;
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await?;
+    Ok((adapter, device, queue))
+}
+
+/// The texture a headless renderer draws to, copied from by captures
+fn output_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    size: ScreenSize,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("wgputris.rend.output"),
+        size: wgpu::Extent3d {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn move_layer<L>(layers: &mut IndexMap<Cow<'static, str>, L>, label: &str, index: usize) -> bool {
+    let Some(from) = layers.get_index_of(label) else {
+        return false;
+    };
+    layers.move_index(from, index.min(layers.len() - 1));
+    true
+}
+
 fn uniform_binding(
     device: &wgpu::Device,
     size: ScreenSize,
 ) -> (wgpu::BindGroup, wgpu::BindGroupLayout, wgpu::Buffer) {
     let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("wgputris.qrend.uniform.buffer"),
-        contents: bytemuck::bytes_of(&size),
+        contents: bytemuck::bytes_of(&Uniforms::new(size)),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
     let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -219,14 +962,78 @@ fn uniform_binding(
 }
 
 pub const VERTICES_PER_QUAD: usize = 6;
+/// The bytes per quad of the largest [`QuadMode`]
 pub const BYTES_PER_QUAD: usize = VERTICES_PER_QUAD * std::mem::size_of::<Vertex>();
 
+impl Instance {
+    const ATTRIBS: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array!(
+        // Position + Size
+        0 => Sint32x4,
+        // Colour
+        1 => Unorm8x4,
+        // Texture coordinates
+        2 => Unorm16x4,
+        // Border, radius, bevel, hollow
+        3 => Float32x4,
+        // Border colour
+        4 => Unorm8x4,
+    );
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+
+    fn from_quad(quad: &Quad) -> Self {
+        let &Quad {
+            colour,
+            x,
+            y,
+            width,
+            height,
+            uv,
+            border_colour,
+            ..
+        } = quad;
+        let unorm8 = |c: f32| (c.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8;
+        let unorm16 = |c: f32| (c.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+        let uv = uv.unwrap_or(skin::WHITE_UV);
+        Self {
+            rect: [x, y, width as i32, height as i32],
+            // decoded by the shaders, see `styling::srgb`
+            colour: colour.srgba().map(unorm8),
+            uv: [uv.left, uv.top, uv.right, uv.bottom].map(unorm16),
+            shape: quad.shape(),
+            border_colour: border_colour.srgba().map(unorm8),
+        }
+    }
+
+    /// Replaces `out` with the instances of `quads`
+    fn write_quads(quads: &[Quad], out: &mut Vec<Self>) {
+        out.clear();
+        out.extend(quads.iter().map(Self::from_quad));
+    }
+}
+
 impl Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array!(
+    const ATTRIBS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array!(
         // Colour
         0 => Float32x4,
-        // Position + Size
-        1 => Uint32x2,
+        // Position
+        1 => Sint32x2,
+        // Texture coordinates
+        2 => Float32x2,
+        // Position within the quad
+        3 => Float32x2,
+        // Size
+        4 => Float32x2,
+        // Border, radius, bevel, hollow
+        5 => Float32x4,
+        // Border colour
+        6 => Float32x4,
     );
 
     fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -237,64 +1044,222 @@ impl Vertex {
         }
     }
 
-    fn from_quad(
-        &Quad {
+    fn from_quad(quad: &Quad) -> [Self; 6] {
+        let &Quad {
             colour,
             x,
             y,
             width,
             height,
-        }: &Quad,
-    ) -> [Self; 6] {
-        let colour = colour.rgba();
-        let vertex = |x, y| Vertex { colour, x, y };
-        let bl = vertex(x, y + height);
-        let br = vertex(x + width, y + height);
-        let tr = vertex(x + width, y);
-        let tl = vertex(x, y);
+            uv,
+            gradient,
+            border_colour,
+            ..
+        } = quad;
+        let shape = quad.shape();
+        let border_colour = border_colour.rgba();
+        let top = colour.rgba();
+        let bottom = gradient.map_or(top, Colour::rgba);
+        let uv = uv.unwrap_or(skin::WHITE_UV);
+        let size = [width as f32, height as f32];
+        let vertex = |colour, x, y, uv, local| Vertex {
+            colour,
+            x,
+            y,
+            uv,
+            local,
+            size,
+            shape,
+            border_colour,
+        };
+        let (right, bottom_y) = (x + width as i32, y + height as i32);
+        let bl = vertex(bottom, x, bottom_y, [uv.left, uv.bottom], [0.0, size[1]]);
+        let br = vertex(bottom, right, bottom_y, [uv.right, uv.bottom], size);
+        let tr = vertex(top, right, y, [uv.right, uv.top], [size[0], 0.0]);
+        let tl = vertex(top, x, y, [uv.left, uv.top], [0.0, 0.0]);
         [tl, bl, br, tr, tl, br]
     }
 
-    fn from_quads(quads: &[Quad]) -> Vec<Self> {
-        let mut vertices = Vec::with_capacity(quads.len() * 6);
+    /// Replaces `out` with the vertices of `quads`
+    fn write_quads(quads: &[Quad], out: &mut Vec<Self>) {
+        out.clear();
+        out.reserve(quads.len() * 6);
         for v in quads.iter().map(Self::from_quad) {
-            vertices.extend_from_slice(&v);
+            out.extend_from_slice(&v);
         }
-        vertices
     }
 }
 
-const MULTISAMPLE_STATE: wgpu::MultisampleState = wgpu::MultisampleState {
-    count: 1,
-    mask: !0,
-    alpha_to_coverage_enabled: false,
-};
+/// Parses a present mode by its kebab-case name, e.g. `auto-no-vsync`
+pub fn parse_present_mode(name: &str) -> Option<wgpu::PresentMode> {
+    use wgpu::PresentMode::*;
+    Some(match name {
+        "auto-vsync" => AutoVsync,
+        "auto-no-vsync" => AutoNoVsync,
+        "fifo" => Fifo,
+        "fifo-relaxed" => FifoRelaxed,
+        "mailbox" => Mailbox,
+        "immediate" => Immediate,
+        _ => return None,
+    })
+}
+
+/// Parses backends by name, `auto` being all of them
+pub fn parse_backends(name: &str) -> Option<wgpu::Backends> {
+    use wgpu::Backends;
+    Some(match name {
+        "auto" => Backends::all(),
+        "vulkan" => Backends::VULKAN,
+        "dx12" => Backends::DX12,
+        "metal" => Backends::METAL,
+        "gl" => Backends::GL,
+        _ => return None,
+    })
+}
+
+/// Parses a power preference, `low` or `high`
+pub fn parse_power_preference(name: &str) -> Option<wgpu::PowerPreference> {
+    match name {
+        "low" => Some(wgpu::PowerPreference::LowPower),
+        "high" => Some(wgpu::PowerPreference::HighPerformance),
+        _ => None,
+    }
+}
+
+/// The modes tried in order when a present mode is unsupported
+fn present_mode_fallbacks(present_mode: wgpu::PresentMode) -> &'static [wgpu::PresentMode] {
+    use wgpu::PresentMode::*;
+    match present_mode {
+        AutoVsync => &[AutoVsync],
+        AutoNoVsync => &[AutoNoVsync],
+        Fifo => &[Fifo],
+        FifoRelaxed => &[FifoRelaxed, Fifo],
+        Mailbox => &[Mailbox, Immediate, AutoNoVsync],
+        Immediate => &[Immediate, Mailbox, AutoNoVsync],
+    }
+}
+
+fn multisample_state(count: u32) -> wgpu::MultisampleState {
+    wgpu::MultisampleState {
+        count,
+        mask: !0,
+        alpha_to_coverage_enabled: false,
+    }
+}
+
+/// Falls back to a single sample when the count isn't supported
+fn valid_sample_count(flags: wgpu::TextureFormatFeatureFlags, sample_count: u32) -> u32 {
+    if sample_count <= 1 || flags.sample_count_supported(sample_count) {
+        sample_count.max(1)
+    } else {
+        log::warn!("MSAA sample count {sample_count} is unsupported, falling back to 1");
+        1
+    }
+}
+
+fn create_msaa_view(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    size: ScreenSize,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("wgputris.rend.msaa"),
+        size: wgpu::Extent3d {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
+
+/// Creates the flat, textured & gradient quad pipelines
+///
+/// The gradient pipeline always takes vertices, see [`QuadPipeline::quad_mode`].
+fn create_pipelines(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    uniform_bind: &wgpu::BindGroupLayout,
+    skin_bind: &wgpu::BindGroupLayout,
+    multisample: wgpu::MultisampleState,
+    quad_mode: QuadMode,
+) -> (
+    wgpu::RenderPipeline,
+    wgpu::RenderPipeline,
+    wgpu::RenderPipeline,
+) {
+    let flat = create_pipeline(
+        device,
+        format,
+        &[uniform_bind],
+        multisample,
+        quad_mode,
+        "wgputtris.qrend",
+        include_str!("./shaders/quad.wgsl"),
+    );
+    let textured = create_pipeline(
+        device,
+        format,
+        &[uniform_bind, skin_bind],
+        multisample,
+        quad_mode,
+        "wgputtris.qrend.textured",
+        include_str!("./shaders/textured_quad.wgsl"),
+    );
+    // the flat shader's vertex entry already passes each vertex's colour on
+    let gradient = create_pipeline(
+        device,
+        format,
+        &[uniform_bind],
+        multisample,
+        QuadPipeline::Gradient.quad_mode(quad_mode),
+        "wgputtris.qrend.gradient",
+        include_str!("./shaders/quad.wgsl"),
+    );
+    (flat, textured, gradient)
+}
 
 fn create_pipeline(
     device: &wgpu::Device,
     format: wgpu::TextureFormat,
-    uniform_bind: wgpu::BindGroupLayout,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    multisample: wgpu::MultisampleState,
+    quad_mode: QuadMode,
+    label: &str,
+    shader: &'static str,
 ) -> wgpu::RenderPipeline {
+    let (entry_point, buffer) = match quad_mode {
+        QuadMode::Instanced => ("vs_instance", Instance::desc()),
+        QuadMode::Vertices => ("vs_main", Vertex::desc()),
+    };
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("wgputtris.qrend.pipeline_layout"),
+        label: Some(&format!("{label}.pipeline_layout")),
         push_constant_ranges: &[],
-        bind_group_layouts: &[&uniform_bind],
+        bind_group_layouts,
     });
 
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("wgputtris.qrend.shader"),
-        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(include_str!(
-            "./shaders/quad.wgsl"
-        ))),
+        label: Some(&format!("{label}.shader")),
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(shader)),
     });
 
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("wgputtris.qrend.pipeline"),
+        label: Some(&format!("{label}.pipeline")),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &shader,
-            entry_point: Some("vs_main"),
-            buffers: &[Vertex::desc()],
+            entry_point: Some(entry_point),
+            buffers: &[buffer],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         },
         fragment: Some(wgpu::FragmentState {
@@ -317,33 +1282,82 @@ fn create_pipeline(
             conservative: false,
         },
         depth_stencil: None,
-        multisample: MULTISAMPLE_STATE,
+        multisample,
         multiview: None,
         cache: None,
     })
 }
 
 impl QRend {
-    fn render(&self, render_pass: &mut wgpu::RenderPass<'_>) {
-        for layer in self.layers.values().filter(|l| !l.is_empty()) {
-            layer.render(render_pass)
+    fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        size: ScreenSize,
+        quad_mode: QuadMode,
+        flat: &wgpu::RenderPipeline,
+        textured: Option<(&wgpu::RenderPipeline, &wgpu::BindGroup)>,
+        gradient: &wgpu::RenderPipeline,
+    ) {
+        for layer in self
+            .layers
+            .values()
+            .filter(|l| l.is_visible() && !l.is_empty())
+        {
+            match layer.pipeline() {
+                QuadPipeline::Flat => render_pass.set_pipeline(flat),
+                QuadPipeline::Textured => {
+                    let Some((pipeline, skin)) = textured else {
+                        continue;
+                    };
+                    render_pass.set_pipeline(pipeline);
+                    render_pass.set_bind_group(1, skin, &[]);
+                }
+                QuadPipeline::Gradient => render_pass.set_pipeline(gradient),
+            }
+            // clamped here so a resize between setting & drawing can't
+            // leave the rect outside the surface
+            let scissor = layer.scissor().map(|s| s.clamp(size.width, size.height));
+            match scissor {
+                Some(scissor) if scissor.is_empty() => continue,
+                Some(scissor) => render_pass.set_scissor_rect(
+                    scissor.x,
+                    scissor.y,
+                    scissor.width,
+                    scissor.height,
+                ),
+                None => (),
+            }
+            layer.render(render_pass, quad_mode);
+            if scissor.is_some() {
+                render_pass.set_scissor_rect(0, 0, size.width, size.height);
+            }
         }
     }
 }
 
+/// The font bundled for systems with none of their own, see
+/// `src/fonts/DejaVu-LICENSE.txt`
+const FALLBACK_FONT: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+const FALLBACK_FAMILY: &str = "DejaVu Sans";
+
 impl TRend {
     fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         swapchain_format: wgpu::TextureFormat,
+        multisample: wgpu::MultisampleState,
     ) -> TRend {
         use glyphon::*;
-        let font_system = Rc::new(Mutex::new(FontSystem::new()));
+        let mut font_system = FontSystem::new();
+        font_system.db_mut().load_font_data(FALLBACK_FONT.to_vec());
+        // layers default to sans serif, see `TextLayerDesc::attrs`
+        font_system.db_mut().set_sans_serif_family(FALLBACK_FAMILY);
+        let font_system = Arc::new(Mutex::new(font_system));
         let swash_cache = SwashCache::new();
         let cache = Cache::new(&device);
         let viewport = Viewport::new(&device, &cache);
         let mut atlas = TextAtlas::new(&device, &queue, &cache, swapchain_format);
-        let text_renderer = TextRenderer::new(&mut atlas, &device, MULTISAMPLE_STATE, None);
+        let text_renderer = TextRenderer::new(&mut atlas, &device, multisample, None);
 
         // let mut text_buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 26.0));
         // let physical_width = (size.width as f64 * size.scale) as f32;
@@ -366,6 +1380,7 @@ impl TRend {
         // text_buffer.shape_until_scroll(&mut font_system, false);
 
         TRend {
+            multisample,
             font_system,
             swash_cache,
             viewport,
@@ -376,6 +1391,8 @@ impl TRend {
     }
 
     pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        // layers only lock the font system from their setters, which need them
+        // mutably, so never while it's locked here
         self.text_renderer
             .prepare(
                 device,
@@ -383,7 +1400,10 @@ impl TRend {
                 &mut self.font_system.lock().unwrap(),
                 &mut self.atlas,
                 &self.viewport,
-                self.layers.values().map(TextLayer::to_area),
+                self.layers
+                    .values()
+                    .filter(|l| l.is_visible())
+                    .flat_map(TextLayer::areas),
                 &mut self.swash_cache,
             )
             .unwrap();
@@ -393,19 +1413,32 @@ impl TRend {
         self.atlas.trim();
     }
 
-    fn render(
-        &mut self,
-        size: &ScreenSize,
-        queue: &wgpu::Queue,
-        render_pass: &mut wgpu::RenderPass<'_>,
-    ) {
+    fn set_multisample(&mut self, device: &wgpu::Device, multisample: wgpu::MultisampleState) {
+        self.multisample = multisample;
+        self.text_renderer = glyphon::TextRenderer::new(&mut self.atlas, device, multisample, None);
+    }
+
+    /// Fits the viewport to the size drawn at, clipping every layer to it
+    /// until they're given rects of their own
+    fn resize(&mut self, queue: &wgpu::Queue, size: ScreenSize) {
         self.viewport.update(
-            &queue,
+            queue,
             glyphon::Resolution {
                 width: size.width,
                 height: size.height,
             },
         );
+        for layer in self.layers.values_mut() {
+            layer.set_rect(Rect {
+                x: 0,
+                y: 0,
+                width: size.width,
+                height: size.height,
+            });
+        }
+    }
+
+    fn render(&mut self, render_pass: &mut wgpu::RenderPass<'_>) {
         self.text_renderer
             .render(&self.atlas, &self.viewport, render_pass)
             .unwrap();
@@ -415,6 +1448,7 @@ impl TRend {
 impl std::fmt::Debug for TRend {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TRend")
+            .field("multisample", &self.multisample)
             .field("font_system", &self.font_system)
             .field("swash_cache", &self.swash_cache)
             .field("viewport", &self.viewport)
@@ -0,0 +1,144 @@
+// NOTE: captures draw the frame again into a texture of their own, as surface
+// textures can't always be copied from. The texture is copied to a buffer in
+// the frame's encoder, which is mapped & read on whichever thread calls
+// `Capture::read`, so the render loop never waits on the GPU.
+
+use anyhow::{bail, Context};
+
+use super::ScreenSize;
+
+/// A frame being copied back from the GPU
+#[derive(Debug)]
+pub struct Capture {
+    device: wgpu::Device,
+    texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    /// The bytes per row of the buffer, padded to the copy alignment
+    padded_row: u32,
+    /// Whether the texels are BGRA rather than RGBA
+    bgra: bool,
+}
+
+/// A captured frame, 8-bit RGBA from the top row down
+#[derive(Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Capture {
+    /// A texture & buffer for a frame of `size`, only 8-bit RGBA & BGRA
+    /// formats being supported
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: ScreenSize,
+    ) -> anyhow::Result<Self> {
+        use wgpu::TextureFormat::*;
+        let bgra = match format {
+            Rgba8Unorm | Rgba8UnormSrgb => false,
+            Bgra8Unorm | Bgra8UnormSrgb => true,
+            format => bail!("can't capture frames of format {format:?}"),
+        };
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("wgputris.rend.capture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let padded_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wgputris.rend.capture.buffer"),
+            size: padded_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Ok(Self {
+            device: device.clone(),
+            texture,
+            view,
+            buffer,
+            width,
+            height,
+            padded_row,
+            bgra,
+        })
+    }
+
+    /// Copies the drawn texture to the buffer, after the frame's passes
+    pub fn copy(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Waits for the copy & reads it back, blocking until the GPU is done
+    ///
+    /// Should be called once the encoder the copy was recorded in has been
+    /// submitted, off the render thread.
+    pub fn read(self) -> anyhow::Result<Image> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .context("the capture was dropped")?
+            .context("failed to map the capture")?;
+        let row = self.width as usize * 4;
+        let mut rgba = Vec::with_capacity(row * self.height as usize);
+        for padded in slice
+            .get_mapped_range()
+            .chunks_exact(self.padded_row as usize)
+        {
+            rgba.extend_from_slice(&padded[..row]);
+        }
+        self.buffer.unmap();
+        for texel in rgba.chunks_exact_mut(4) {
+            if self.bgra {
+                texel.swap(0, 2);
+            }
+            // what's behind the window doesn't show through
+            texel[3] = u8::MAX;
+        }
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            rgba,
+        })
+    }
+}
@@ -1,61 +1,227 @@
-// TODO: create ability to reserve extra.
+// NOTE: a layer's buffer grows with headroom, so quad counts that wobble don't
+// recreate it, & gives memory back once it's 4 times larger than the quads
+// need, though never below what was reserved. Either way every quad is
+// uploaded again.
+
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut, Range};
 
 use wgpu::util::DeviceExt;
 
+/// How many times larger than its quads a buffer gets before it's shrunk
+const SHRINK_RATIO: usize = 4;
+
 #[derive(Debug)]
 pub struct QuadLayer {
-    name: &'static str,
-    label: &'static str,
+    pub(super) name: Cow<'static, str>,
+    label: Cow<'static, str>,
     quads: Vec<super::Quad>,
+    /// The quads being written, swapped with `quads` once written
+    staging: Vec<super::Quad>,
+    /// Scratch space for serializing quads on prepare
+    instances: Vec<super::Instance>,
+    vertices: Vec<super::Vertex>,
     buffer: wgpu::Buffer,
     byte_cap: usize,
-    changed: bool,
+    /// The bytes the quads took up when last prepared
+    byte_len: usize,
+    /// The bytes the buffer isn't shrunk below, see [`QuadLayer::reserve`]
+    reserved: usize,
+    /// How many times the buffer was recreated after the first
+    recreated: u32,
+    /// The quads to be uploaded on the next prepare
+    dirty: Option<Range<usize>>,
+    pipeline: super::QuadPipeline,
+    visible: bool,
+    scissor: Option<super::Rect>,
 }
 
 impl QuadLayer {
     pub fn new(
-        name: &'static str,
-        label: &'static str,
+        name: impl Into<Cow<'static, str>>,
+        label: impl Into<Cow<'static, str>>,
         device: &wgpu::Device,
         quads: usize,
     ) -> Self {
+        let label = label.into();
         let byte_cap = quads * super::BYTES_PER_QUAD;
         Self {
-            name,
+            name: name.into(),
+            buffer: create_buffer(&label, device, byte_cap),
             label,
             quads: Vec::with_capacity(quads),
-            buffer: create_buffer(label, device, byte_cap),
+            staging: Vec::with_capacity(quads),
+            instances: Vec::new(),
+            vertices: Vec::new(),
+            byte_cap,
+            byte_len: 0,
+            reserved: byte_cap,
+            recreated: 0,
+            dirty: None,
+            pipeline: super::QuadPipeline::default(),
+            visible: true,
+            scissor: None,
+        }
+    }
+
+    /// A layer starting with `quads`, its buffer created already holding
+    /// them laid out for `mode`, the renderer's [`super::QuadMode`]
+    pub fn with_quads(
+        name: impl Into<Cow<'static, str>>,
+        label: impl Into<Cow<'static, str>>,
+        device: &wgpu::Device,
+        quads: Vec<super::Quad>,
+        mode: super::QuadMode,
+    ) -> Self {
+        let label = label.into();
+        let mut instances = Vec::new();
+        let mut vertices = Vec::new();
+        let contents: &[u8] = match mode {
+            super::QuadMode::Instanced => {
+                super::Instance::write_quads(&quads, &mut instances);
+                bytemuck::cast_slice(&instances)
+            }
+            super::QuadMode::Vertices => {
+                super::Vertex::write_quads(&quads, &mut vertices);
+                bytemuck::cast_slice(&vertices)
+            }
+        };
+        let byte_cap = contents.len();
+        Self {
+            name: name.into(),
+            buffer: create_buffer_init(&label, device, contents),
+            label,
+            staging: Vec::with_capacity(quads.len()),
+            quads,
+            instances,
+            vertices,
             byte_cap,
-            changed: false,
+            byte_len: byte_cap,
+            reserved: 0,
+            recreated: 0,
+            dirty: None,
+            pipeline: super::QuadPipeline::default(),
+            visible: true,
+            scissor: None,
         }
     }
 
-    pub fn render(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass<'_>, mode: super::QuadMode) {
         render_pass.set_vertex_buffer(0, self.buffer().slice(..));
-        render_pass.draw(0..self.vertices() as u32, 0..1);
+        match self.pipeline.quad_mode(mode) {
+            super::QuadMode::Instanced => {
+                render_pass.draw(0..super::VERTICES_PER_QUAD as u32, 0..self.len() as u32)
+            }
+            super::QuadMode::Vertices => render_pass.draw(0..self.vertices() as u32, 0..1),
+        }
     }
 
-    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
-        if !self.changed {
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mode: super::QuadMode) {
+        let mode = self.pipeline.quad_mode(mode);
+        let stride = mode.bytes_per_quad();
+        let byte_len = self.quads.len() * stride;
+        // quads dropped off the end leave nothing to upload, but can leave the
+        // buffer to shrink
+        let Some(dirty) = self
+            .dirty
+            .take()
+            .or((byte_len != self.byte_len).then_some(0..0))
+        else {
             return;
+        };
+        self.byte_len = byte_len;
+        let recreated = if self.byte_cap < self.byte_len {
+            // with headroom, so a layer growing a quad at a time isn't
+            // recreated every frame
+            self.recreate(device, self.byte_len + self.byte_len / 2);
+            true
+        } else if self.byte_cap > SHRINK_RATIO * self.byte_len.max(self.reserved) {
+            self.recreate(device, self.byte_len.max(self.reserved));
+            true
+        } else {
+            false
+        };
+        // a new buffer starts empty, so every quad is uploaded
+        let dirty = if recreated {
+            0..self.quads.len()
+        } else {
+            dirty.start.min(self.quads.len())..dirty.end.min(self.quads.len())
+        };
+        let quads = &self.quads[dirty.clone()];
+        // the scratch buffers are taken out & put back to be reused
+        match mode {
+            super::QuadMode::Instanced => {
+                let mut instances = std::mem::take(&mut self.instances);
+                super::Instance::write_quads(quads, &mut instances);
+                self.upload(
+                    queue,
+                    dirty.start * stride,
+                    bytemuck::cast_slice(&instances),
+                );
+                self.instances = instances;
+            }
+            super::QuadMode::Vertices => {
+                let mut vertices = std::mem::take(&mut self.vertices);
+                super::Vertex::write_quads(quads, &mut vertices);
+                self.upload(queue, dirty.start * stride, bytemuck::cast_slice(&vertices));
+                self.vertices = vertices;
+            }
         }
-        let vertices = super::Vertex::from_quads(&self.quads);
-        let contents = bytemuck::cast_slice(&vertices);
-        let byte_len = contents.len();
-        if self.byte_cap < byte_len {
-            self.buffer = create_buffer_init(self.label, device, contents);
-            self.byte_cap = byte_len;
-        } else if let Some(mut size) = wgpu::BufferSize::new(byte_len as u64)
-            .and_then(|size| queue.write_buffer_with(&self.buffer, 0, size))
+    }
+
+    fn upload(&mut self, queue: &wgpu::Queue, offset: usize, contents: &[u8]) {
+        if let Some(mut size) = wgpu::BufferSize::new(contents.len() as u64)
+            .and_then(|size| queue.write_buffer_with(&self.buffer, offset as u64, size))
         {
             size.copy_from_slice(contents);
         }
     }
 
+    /// Replaces the buffer with an empty one of at least `byte_cap` bytes
+    fn recreate(&mut self, device: &wgpu::Device, byte_cap: usize) {
+        self.byte_cap = byte_cap.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize);
+        self.buffer = create_buffer(&self.label, device, self.byte_cap);
+        self.recreated += 1;
+    }
+
+    /// Grows the buffer to fit `quads` ahead of time, & keeps it from being
+    /// shrunk below that
+    pub fn reserve(&mut self, device: &wgpu::Device, quads: usize) {
+        self.reserved = quads * super::BYTES_PER_QUAD;
+        if self.byte_cap < self.reserved {
+            self.recreate(device, self.reserved);
+            self.mark_changed();
+        }
+    }
+
+    /// Shrinks the buffer to what the quads took up when last prepared, or
+    /// to what's reserved
+    ///
+    /// Done on prepare once the buffer is 4 times larger than needed.
+    pub fn shrink_to_fit(&mut self, device: &wgpu::Device) {
+        let byte_cap = self.byte_len.max(self.reserved);
+        if self.byte_cap > byte_cap {
+            self.recreate(device, byte_cap);
+            self.mark_changed();
+        }
+    }
+
+    /// The size of the buffer in bytes
     pub fn byte_cap(&self) -> usize {
         self.byte_cap
     }
 
+    /// The bytes the quads took up when last prepared
+    pub fn byte_len(&self) -> usize {
+        self.byte_len
+    }
+
+    /// How many times the buffer was recreated, staying put while the
+    /// number of quads is stable
+    pub fn recreated(&self) -> u32 {
+        self.recreated
+    }
+
     pub fn len(&self) -> usize {
         self.quads.len()
     }
@@ -64,9 +230,75 @@ impl QuadLayer {
         self.quads.is_empty()
     }
 
+    pub fn quads(&self) -> &[super::Quad] {
+        &self.quads
+    }
+
+    /// Replaces the quads, only the ones that differ are uploaded
     pub fn set_quads(&mut self, quads: Vec<super::Quad>) {
-        self.changed = true;
-        self.quads = quads;
+        self.staging = quads;
+        self.swap_staging();
+    }
+
+    /// Replaces the quads with those written to the returned buffer
+    ///
+    /// The buffer starts empty & reuses the allocation of earlier quads. The
+    /// new quads take effect once it's dropped, only the ones that differ
+    /// being uploaded.
+    pub fn quads_mut(&mut self) -> QuadsMut<'_> {
+        self.staging.clear();
+        QuadsMut { layer: self }
+    }
+
+    fn swap_staging(&mut self) {
+        let (old, new) = (&self.quads, &self.staging);
+        let start = old
+            .iter()
+            .zip(new)
+            .position(|(old, new)| old != new)
+            .unwrap_or(old.len().min(new.len()));
+        let end = if old.len() == new.len() {
+            old.iter()
+                .zip(new)
+                .rposition(|(old, new)| old != new)
+                .map_or(start, |i| i + 1)
+        } else {
+            new.len()
+        };
+        self.mark_dirty(start..end);
+        std::mem::swap(&mut self.quads, &mut self.staging);
+    }
+
+    pub fn set_quad(&mut self, index: usize, quad: super::Quad) {
+        if self.quads[index] != quad {
+            self.quads[index] = quad;
+            self.mark_dirty(index..index + 1);
+        }
+    }
+
+    pub fn push_quad(&mut self, quad: super::Quad) {
+        self.quads.push(quad);
+        self.mark_dirty(self.quads.len() - 1..self.quads.len());
+    }
+
+    /// Removes every quad, keeping the buffer for reuse
+    pub fn clear(&mut self) {
+        self.quads.clear();
+    }
+
+    /// Forces every quad to be uploaded on the next prepare
+    pub fn mark_changed(&mut self) {
+        self.mark_dirty(0..self.quads.len());
+    }
+
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
     }
 
     pub fn buffer(&self) -> &wgpu::Buffer {
@@ -77,8 +309,106 @@ impl QuadLayer {
         self.quads.len() * super::VERTICES_PER_QUAD
     }
 
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pipeline(&self) -> super::QuadPipeline {
+        self.pipeline
+    }
+
+    /// Changing to or from [`super::QuadPipeline::Gradient`] reuploads every
+    /// quad, as its quads are laid out differently
+    pub fn set_pipeline(&mut self, pipeline: super::QuadPipeline) {
+        let mode = super::QuadMode::Instanced;
+        if pipeline.quad_mode(mode) != self.pipeline.quad_mode(mode) {
+            self.mark_changed();
+        }
+        self.pipeline = pipeline;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Hidden layers keep their quads but aren't drawn
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn scissor(&self) -> Option<super::Rect> {
+        self.scissor
+    }
+
+    /// Clips the layer to `scissor`, clamped to the surface when drawn
+    pub fn set_scissor(&mut self, scissor: Option<super::Rect>) {
+        self.scissor = scissor;
+    }
+}
+
+/// The quads being written to a layer, see [`QuadLayer::quads_mut`]
+#[derive(Debug)]
+pub struct QuadsMut<'a> {
+    layer: &'a mut QuadLayer,
+}
+
+impl Deref for QuadsMut<'_> {
+    type Target = Vec<super::Quad>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.layer.staging
+    }
+}
+
+impl DerefMut for QuadsMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.layer.staging
+    }
+}
+
+impl Drop for QuadsMut<'_> {
+    fn drop(&mut self) {
+        self.layer.swap_staging();
+    }
+}
+
+/// Where quads are drawn to, a layer or a plain list of them
+///
+/// The layer settings are left out of lists, so drawing can be checked
+/// without a device.
+pub trait QuadSink {
+    /// Replaces the quads with those `write` pushes to an empty list
+    fn write_quads(&mut self, write: impl FnOnce(&mut Vec<super::Quad>));
+
+    fn set_pipeline(&mut self, _pipeline: super::QuadPipeline) {}
+
+    fn set_visible(&mut self, _visible: bool) {}
+
+    fn set_scissor(&mut self, _scissor: Option<super::Rect>) {}
+}
+
+impl QuadSink for QuadLayer {
+    fn write_quads(&mut self, write: impl FnOnce(&mut Vec<super::Quad>)) {
+        write(&mut self.quads_mut());
+    }
+
+    fn set_pipeline(&mut self, pipeline: super::QuadPipeline) {
+        QuadLayer::set_pipeline(self, pipeline);
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        QuadLayer::set_visible(self, visible);
+    }
+
+    fn set_scissor(&mut self, scissor: Option<super::Rect>) {
+        QuadLayer::set_scissor(self, scissor);
+    }
+}
+
+impl QuadSink for Vec<super::Quad> {
+    fn write_quads(&mut self, write: impl FnOnce(&mut Vec<super::Quad>)) {
+        self.clear();
+        write(self);
     }
 }
 
@@ -101,3 +431,191 @@ fn create_buffer_init(label: &str, device: &wgpu::Device, contents: &[u8]) -> wg
         usage: BUFFER_USAGES,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODE: super::super::QuadMode = super::super::QuadMode::Instanced;
+
+    /// A device to make buffers on, `None` with a note when there's no adapter
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        match pollster::block_on(super::super::headless_device()) {
+            Ok((_, device, queue)) => Some((device, queue)),
+            Err(e) => {
+                eprintln!("skipped, nothing to draw with: {e}");
+                None
+            }
+        }
+    }
+
+    fn quads(n: usize) -> Vec<super::super::Quad> {
+        (0..n as i32)
+            .map(|x| super::super::Quad {
+                x,
+                width: 1,
+                height: 1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn stride() -> usize {
+        MODE.bytes_per_quad()
+    }
+
+    #[test]
+    fn with_quads_starts_filled() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::with_quads("test", "Test", &device, quads(10), MODE);
+        assert_eq!(layer.byte_cap(), 10 * stride());
+        assert_eq!(layer.byte_len(), 10 * stride());
+        assert_eq!(layer.dirty, None);
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 0);
+    }
+
+    #[test]
+    fn prepare_uploads_what_changed() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 10);
+        layer.set_quads(quads(10));
+        assert_eq!(layer.dirty, Some(0..10));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.dirty, None);
+        assert_eq!(layer.byte_len(), 10 * stride());
+
+        let mut changed = quads(10);
+        changed[3].y = 1;
+        changed[6].y = 1;
+        layer.set_quads(changed.clone());
+        assert_eq!(layer.dirty, Some(3..7));
+        layer.prepare(&device, &queue, MODE);
+        // the same quads again have nothing to upload
+        layer.set_quads(changed);
+        assert_eq!(layer.dirty, None);
+        // fewer quads upload nothing, more only those added
+        layer.set_quads(quads(10));
+        layer.prepare(&device, &queue, MODE);
+        layer.set_quads(quads(8));
+        assert_eq!(layer.dirty, None);
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.byte_len(), 8 * stride());
+        layer.set_quads(quads(9));
+        assert_eq!(layer.dirty, Some(8..9));
+        assert_eq!(layer.recreated(), 0);
+    }
+
+    #[test]
+    fn grows_with_headroom() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 0);
+        layer.set_quads(quads(10));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 1);
+        assert!(layer.byte_cap() >= 15 * stride());
+        // every quad's uploaded to the new buffer
+        assert_eq!(layer.byte_len(), 10 * stride());
+        for n in 11..=15 {
+            layer.set_quads(quads(n));
+            layer.prepare(&device, &queue, MODE);
+        }
+        assert_eq!(layer.recreated(), 1);
+        layer.set_quads(quads(16));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 2);
+    }
+
+    #[test]
+    fn shrinks_once_4_times_too_large() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 0);
+        layer.set_quads(quads(100));
+        layer.prepare(&device, &queue, MODE);
+        let cap = layer.byte_cap();
+        // the fewest quads the buffer's at most 4 times larger than
+        let fewest = cap.div_ceil(4 * stride());
+        layer.set_quads(quads(fewest));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!((layer.byte_cap(), layer.recreated()), (cap, 1));
+        layer.set_quads(quads(fewest - 1));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 2);
+        assert_eq!(layer.byte_cap(), (fewest - 1) * stride());
+    }
+
+    #[test]
+    fn wobbling_counts_keep_the_buffer() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 0);
+        layer.set_quads(quads(220));
+        layer.prepare(&device, &queue, MODE);
+        for n in [230, 221, 229, 220, 230, 225] {
+            layer.set_quads(quads(n));
+            layer.prepare(&device, &queue, MODE);
+        }
+        assert_eq!(layer.recreated(), 1);
+    }
+
+    #[test]
+    fn reserve_grows_ahead_of_time() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 0);
+        layer.reserve(&device, 100);
+        assert_eq!(layer.recreated(), 1);
+        assert_eq!(layer.byte_cap(), 100 * super::super::BYTES_PER_QUAD);
+        layer.set_quads(quads(100));
+        layer.prepare(&device, &queue, MODE);
+        // reserving what's already there changes nothing
+        layer.reserve(&device, 50);
+        assert_eq!(layer.recreated(), 1);
+    }
+
+    #[test]
+    fn reserved_buffers_stay_put() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 100);
+        let cap = layer.byte_cap();
+        layer.set_quads(quads(100));
+        layer.prepare(&device, &queue, MODE);
+        layer.set_quads(quads(1));
+        layer.prepare(&device, &queue, MODE);
+        layer.shrink_to_fit(&device);
+        assert_eq!((layer.byte_cap(), layer.recreated()), (cap, 0));
+    }
+
+    #[test]
+    fn shrink_to_fit_gives_back_the_rest() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut layer = QuadLayer::new("test", "Test", &device, 0);
+        layer.set_quads(quads(1000));
+        layer.prepare(&device, &queue, MODE);
+        // too few to be shrunk on prepare
+        layer.set_quads(quads(400));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 1);
+        layer.shrink_to_fit(&device);
+        assert_eq!(layer.recreated(), 2);
+        assert_eq!(layer.byte_cap(), 400 * stride());
+        // the new buffer's empty, so every quad's uploaded again
+        assert_eq!(layer.dirty, Some(0..400));
+        layer.prepare(&device, &queue, MODE);
+        assert_eq!(layer.recreated(), 2);
+    }
+}
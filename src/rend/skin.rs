@@ -0,0 +1,156 @@
+// NOTE: sprites are laid out in a single row, starting with a plain white
+// sprite used for untextured quads, followed by the empty cell and the blocks.
+
+use anyhow::{bail, Context};
+use wgpu::util::DeviceExt;
+
+use super::Uv;
+
+pub const DEFAULT_SKIN: &[u8] = include_bytes!("../skins/default.png");
+
+/// The number of sprites in a skin
+pub const SPRITES: usize = 9;
+/// The sprite for the empty cell
+pub const EMPTY_SPRITE: usize = 1;
+/// A single texel of the white sprite, for quads without their own uv
+pub const WHITE_UV: Uv = Uv {
+    left: 0.5 / SPRITES as f32,
+    top: 0.5,
+    right: 0.5 / SPRITES as f32,
+    bottom: 0.5,
+};
+
+/// Returns the embedded skin of the given name
+pub fn builtin(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "default" => Some(DEFAULT_SKIN),
+        _ => None,
+    }
+}
+
+pub fn sprite_uv(sprite: usize) -> Uv {
+    Uv {
+        left: sprite as f32 / SPRITES as f32,
+        top: 0.0,
+        right: (sprite + 1) as f32 / SPRITES as f32,
+        bottom: 1.0,
+    }
+}
+
+/// A decoded skin, in RGBA8
+#[derive(Debug, Clone)]
+pub struct SkinImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl SkinImage {
+    pub fn decode_png(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = png::Decoder::new(bytes)
+            .read_info()
+            .context("invalid skin png")?;
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut rgba).context("invalid skin png")?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            bail!("skins must be 8-bit RGBA");
+        }
+        if info.width % SPRITES as u32 != 0 {
+            bail!("skin width must be a multiple of {SPRITES}");
+        }
+        rgba.truncate(info.buffer_size());
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+}
+
+/// A skin uploaded to the GPU
+#[derive(Debug)]
+pub struct Skin {
+    pub texture: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Skin {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        image: &SkinImage,
+    ) -> Self {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("wgputris.rend.skin.texture"),
+                size: wgpu::Extent3d {
+                    width: image.width,
+                    height: image.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &image.rgba,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // nearest sampling for the pixel-art look
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("wgputris.rend.skin.sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wgputris.rend.skin.bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        Self {
+            texture,
+            bind_group,
+        }
+    }
+}
+
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("wgputris.rend.skin.bind_group.layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
@@ -0,0 +1,215 @@
+// NOTE: with a target, the scene is drawn at the target's size into a texture
+// of its own, which is then blitted to the surface, scaled to fit & centred,
+// the bars either side left the clear colour. Resizing the window only moves
+// the blit's viewport, the scene being untouched. A target larger than the
+// surface, for the render scale, is downsampled the same way.
+
+use super::ScreenSize;
+
+/// The offscreen texture the scene is drawn to
+#[derive(Debug)]
+pub struct Target {
+    size: ScreenSize,
+    pub view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Target {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: ScreenSize,
+        blit: &Blit,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("wgputris.rend.target"),
+            size: wgpu::Extent3d {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = blit.bind_group(device, &view);
+        Self {
+            size,
+            view,
+            bind_group,
+        }
+    }
+
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    /// Rebinds the texture after the blit's filter changes
+    fn rebind(&mut self, device: &wgpu::Device, blit: &Blit) {
+        self.bind_group = blit.bind_group(device, &self.view);
+    }
+}
+
+/// Draws a target to the surface
+#[derive(Debug)]
+pub struct Blit {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    filter: wgpu::FilterMode,
+}
+
+impl Blit {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgputris.rend.blit.bind_group.layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgputris.rend.blit.pipeline_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wgputris.rend.blit.shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/blit.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("wgputris.rend.blit.pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let filter = wgpu::FilterMode::Linear;
+        Self {
+            pipeline,
+            layout,
+            sampler: create_sampler(device, filter),
+            filter,
+        }
+    }
+
+    pub fn filter(&self) -> wgpu::FilterMode {
+        self.filter
+    }
+
+    /// Changes how the target is sampled when scaled, rebinding `target`
+    pub fn set_filter(
+        &mut self,
+        device: &wgpu::Device,
+        filter: wgpu::FilterMode,
+        target: Option<&mut Target>,
+    ) {
+        if filter == self.filter {
+            return;
+        }
+        self.filter = filter;
+        self.sampler = create_sampler(device, filter);
+        if let Some(target) = target {
+            target.rebind(device, self);
+        }
+    }
+
+    fn bind_group(&self, device: &wgpu::Device, view: &wgpu::TextureView) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wgputris.rend.blit.bind_group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+
+    /// Draws `target` into the `surface` sized pass, letterboxed
+    pub fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        target: &Target,
+        surface: ScreenSize,
+    ) {
+        let [x, y, width, height] = letterbox(target.size, surface);
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &target.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// The largest rect of the target's aspect ratio that fits the surface,
+/// centred, as x, y, width & height
+pub fn letterbox(target: ScreenSize, surface: ScreenSize) -> [f32; 4] {
+    let scale = (surface.width as f32 / target.width.max(1) as f32)
+        .min(surface.height as f32 / target.height.max(1) as f32);
+    // whole pixels, so the scene's edges stay sharp, rounded so a target of
+    // the surface's aspect ratio still covers it
+    let width = (target.width as f32 * scale)
+        .round()
+        .min(surface.width as f32);
+    let height = (target.height as f32 * scale)
+        .round()
+        .min(surface.height as f32);
+    let x = ((surface.width as f32 - width) / 2.0).floor();
+    let y = ((surface.height as f32 - height) / 2.0).floor();
+    [x, y, width, height]
+}
+
+fn create_sampler(device: &wgpu::Device, filter: wgpu::FilterMode) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("wgputris.rend.blit.sampler"),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        mag_filter: filter,
+        min_filter: filter,
+        ..Default::default()
+    })
+}
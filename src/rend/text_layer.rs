@@ -1,13 +1,16 @@
-// TODO: create ability to reserve extra.
+// NOTE: shaping is the costliest part of drawing text, so the text or spans &
+// the attrs last shaped are kept, setting the same again being skipped. They're
+// compared as they are rather than hashed, so a change is never missed. Changes
+// to the layer's attrs or shaping are only picked up when the text's set.
 
-use std::rc::Rc;
-use std::sync::Mutex;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug)]
 pub struct TextLayer {
     pub(super) buffer: glyphon::Buffer,
-    font_system: Rc<Mutex<glyphon::FontSystem>>,
-    name: &'static str,
+    font_system: Arc<Mutex<glyphon::FontSystem>>,
+    pub(super) name: Cow<'static, str>,
     pub attrs: glyphon::Attrs<'static>,
     pub shaping: glyphon::Shaping,
     pub left: f32,
@@ -16,11 +19,67 @@ pub struct TextLayer {
     pub bounds: glyphon::TextBounds,
     pub default_color: glyphon::Color,
     pub custom_glyphs: Vec<glyphon::CustomGlyph>,
+    /// The offset, in unscaled pixels, & colour of a shadow drawn under the
+    /// text, see [`TextLayer::areas`]
+    pub shadow: Option<(f32, glyphon::Color)>,
+    visible: bool,
+    /// What was last shaped, `None` when it has to be shaped again
+    shaped: Option<Shaped>,
+    /// How many times the text has been shaped
+    shapings: u32,
+}
+
+/// The text or spans last shaped, along with the attrs & shaping
+#[derive(Debug, Clone, PartialEq)]
+struct Shaped {
+    content: Content,
+    attrs: glyphon::Attrs<'static>,
+    shaping: glyphon::Shaping,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Content {
+    Text(String),
+    Spans(Vec<(String, SpanAttrs)>),
+}
+
+/// The attrs of a span, each set one overriding the layer's
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpanAttrs {
+    pub colour: Option<glyphon::Color>,
+    pub weight: Option<glyphon::Weight>,
+    pub family: Option<glyphon::Family<'static>>,
+}
+
+impl SpanAttrs {
+    pub fn colour(colour: impl Into<glyphon::Color>) -> Self {
+        Self {
+            colour: Some(colour.into()),
+            ..Default::default()
+        }
+    }
+
+    /// The layer's `attrs` with these over them
+    pub fn over(self, attrs: glyphon::Attrs<'static>) -> glyphon::Attrs<'static> {
+        let mut attrs = attrs;
+        if let Some(colour) = self.colour {
+            attrs = attrs.color(colour);
+        }
+        if let Some(weight) = self.weight {
+            attrs = attrs.weight(weight);
+        }
+        if let Some(family) = self.family {
+            attrs = attrs.family(family);
+        }
+        attrs
+    }
 }
 
 #[derive(Default)]
 pub struct TextLayerDesc {
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
+    /// Sans serif when `None`, the bundled font or the one loaded with
+    /// [`super::Rend::load_font`]
     pub attrs: Option<glyphon::Attrs<'static>>,
     pub shaping: Option<glyphon::Shaping>,
     pub left: f32,
@@ -29,13 +88,14 @@ pub struct TextLayerDesc {
     pub bounds: glyphon::TextBounds,
     pub default_color: Option<glyphon::Color>,
     pub custom_glyphs: Vec<glyphon::CustomGlyph>,
+    pub shadow: Option<(f32, glyphon::Color)>,
 }
 
 impl TextLayer {
     pub fn new(
         buffer: glyphon::Buffer,
         desc: TextLayerDesc,
-        font_system: Rc<Mutex<glyphon::FontSystem>>,
+        font_system: Arc<Mutex<glyphon::FontSystem>>,
     ) -> Self {
         Self {
             font_system,
@@ -49,11 +109,24 @@ impl TextLayer {
             bounds: desc.bounds,
             custom_glyphs: desc.custom_glyphs,
             default_color: desc.default_color.unwrap_or(glyphon::Color(0)),
+            shadow: desc.shadow,
+            visible: true,
+            shaped: None,
+            shapings: 0,
         }
     }
 
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Hidden layers keep their text but aren't drawn
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
     }
 
     pub fn to_area(&self) -> glyphon::TextArea<'_> {
@@ -68,12 +141,181 @@ impl TextLayer {
         }
     }
 
+    /// The text's area, after its shadow's when it has one
+    ///
+    /// The shadow's the same buffer offset down & right, clipped to the same
+    /// bounds. Spans with colours of their own keep them in the shadow.
+    pub fn areas(&self) -> impl Iterator<Item = glyphon::TextArea<'_>> {
+        let shadow = self.shadow.map(|(offset, colour)| glyphon::TextArea {
+            left: self.left + offset * self.scale,
+            top: self.top + offset * self.scale,
+            default_color: colour,
+            ..self.to_area()
+        });
+        shadow.into_iter().chain([self.to_area()])
+    }
+
+    /// Moves the text to the top left of `rect`, clipped to it
+    pub fn set_rect(&mut self, rect: super::Rect) {
+        self.left = rect.x as f32;
+        self.top = rect.y as f32;
+        self.bounds = glyphon::TextBounds {
+            left: rect.x as i32,
+            top: rect.y as i32,
+            right: (rect.x + rect.width) as i32,
+            bottom: (rect.y + rect.height) as i32,
+        };
+    }
+
+    /// Centres the shaped text in `rect`, clipped to it
+    pub fn center_in(&mut self, rect: super::Rect) {
+        let line_height = self.buffer.metrics().line_height;
+        let (width, lines) = self
+            .buffer
+            .layout_runs()
+            .fold((0.0f32, 0), |(width, lines), run| {
+                (width.max(run.line_w), lines + 1)
+            });
+        self.set_rect(rect);
+        self.left = rect.x as f32 + (rect.width as f32 - width) / 2.0;
+        self.top = rect.y as f32 + (rect.height as f32 - lines as f32 * line_height) / 2.0;
+    }
+
+    pub fn set_metrics(&mut self, metrics: glyphon::Metrics) {
+        let font_system = &mut self.font_system.lock().unwrap();
+        self.buffer.set_metrics(font_system, metrics);
+        self.buffer.shape_until_scroll(font_system, false);
+    }
+
+    /// Whether `content` matches what was last shaped, with the same attrs
+    /// & shaping
+    fn is_shaped(&self, content: impl FnOnce(&Content) -> bool) -> bool {
+        self.shaped.as_ref().is_some_and(|shaped| {
+            shaped.attrs == self.attrs && shaped.shaping == self.shaping && content(&shaped.content)
+        })
+    }
+
+    fn set_shaped(&mut self, content: Content) {
+        self.shaped = Some(Shaped {
+            content,
+            attrs: self.attrs,
+            shaping: self.shaping,
+        });
+        self.shapings += 1;
+    }
+
+    /// Shapes `text`, even when it's what was shaped last, see
+    /// [`TextLayer::set_text_if_changed`]
     pub fn set_text(&mut self, text: &str) {
-        self.buffer.set_text(
-            &mut self.font_system.lock().unwrap(),
-            text,
-            self.attrs,
-            self.shaping,
-        );
+        {
+            let font_system = &mut self.font_system.lock().unwrap();
+            self.buffer
+                .set_text(font_system, text, self.attrs, self.shaping);
+            // lays out every line, not only the first
+            self.buffer.shape_until_scroll(font_system, false);
+        }
+        self.set_shaped(Content::Text(text.to_owned()));
+    }
+
+    /// Shapes `text` unless it & the attrs are what was shaped last
+    ///
+    /// Returns whether it was shaped.
+    pub fn set_text_if_changed(&mut self, text: &str) -> bool {
+        if self.is_shaped(|shaped| matches!(shaped, Content::Text(shaped) if shaped == text)) {
+            return false;
+        }
+        self.set_text(text);
+        true
+    }
+
+    /// Shapes the spans, each with its attrs over the layer's, unless they &
+    /// the attrs are what was shaped last
+    ///
+    /// Returns whether they were shaped.
+    pub fn set_spans<S: AsRef<str>>(&mut self, spans: &[(S, SpanAttrs)]) -> bool {
+        let same = |shaped: &Content| match shaped {
+            Content::Spans(shaped) => {
+                shaped.len() == spans.len()
+                    && shaped
+                        .iter()
+                        .zip(spans)
+                        .all(|((a, a_attrs), (b, b_attrs))| a == b.as_ref() && a_attrs == b_attrs)
+            }
+            Content::Text(_) => false,
+        };
+        if self.is_shaped(same) {
+            return false;
+        }
+        {
+            let font_system = &mut self.font_system.lock().unwrap();
+            let attrs = self.attrs;
+            self.buffer.set_rich_text(
+                font_system,
+                spans
+                    .iter()
+                    .map(|(text, span)| (text.as_ref(), span.over(attrs))),
+                attrs,
+                self.shaping,
+            );
+            self.buffer.shape_until_scroll(font_system, false);
+        }
+        let spans = spans
+            .iter()
+            .map(|(text, attrs)| (text.as_ref().to_owned(), *attrs));
+        self.set_shaped(Content::Spans(spans.collect()));
+        true
+    }
+
+    /// Has the text shaped again when next set, e.g. after the font changes
+    pub fn mark_dirty(&mut self) {
+        self.shaped = None;
+    }
+
+    /// How many times the text has been shaped, to check it's only shaped
+    /// when it changes
+    pub fn shapings(&self) -> u32 {
+        self.shapings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer() -> TextLayer {
+        let font_system = Arc::new(Mutex::new(glyphon::FontSystem::new()));
+        let metrics = glyphon::Metrics::new(14.0, 20.0);
+        let buffer = glyphon::Buffer::new(&mut font_system.lock().unwrap(), metrics);
+        TextLayer::new(buffer, TextLayerDesc::default(), font_system)
+    }
+
+    #[test]
+    fn text_is_shaped_when_it_changes() {
+        let mut layer = layer();
+        assert!(layer.set_text_if_changed("Score: 0"));
+        assert!(!layer.set_text_if_changed("Score: 0"));
+        assert!(layer.set_text_if_changed("Score: 100"));
+        // bolder attrs shape the same text differently
+        layer.attrs = layer.attrs.weight(glyphon::Weight::BOLD);
+        assert!(layer.set_text_if_changed("Score: 100"));
+        layer.mark_dirty();
+        assert!(layer.set_text_if_changed("Score: 100"));
+        layer.set_text("Score: 100");
+        assert_eq!(layer.shapings(), 5);
+    }
+
+    #[test]
+    fn spans_are_shaped_when_they_change() {
+        let mut layer = layer();
+        let red = SpanAttrs::colour(glyphon::Color::rgb(255, 0, 0));
+        assert!(layer.set_spans(&[("a", red), ("b", SpanAttrs::default())]));
+        assert!(!layer.set_spans(&[("a".to_string(), red), ("b".into(), SpanAttrs::default())]));
+        // the same text, split or coloured differently
+        assert!(layer.set_spans(&[("ab", red)]));
+        assert!(layer.set_spans(&[("ab", SpanAttrs::default())]));
+        // text & spans aren't taken for each other
+        assert!(layer.set_text_if_changed("ab"));
+        assert!(layer.set_spans(&[("ab", SpanAttrs::default())]));
+        assert_eq!(layer.shapings(), 5);
     }
 }
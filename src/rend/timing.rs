@@ -0,0 +1,186 @@
+// NOTE: timestamps are written around the quad & text draws, resolved & copied
+// into one of a few readback buffers, which is mapped once its frame's done.
+// Readbacks are picked up on a later frame without waiting on the GPU, frames
+// going untimed while every readback's still in flight.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The timestamps of a frame: before the quads, between the quads & text, &
+/// after the text
+const QUERIES: u32 = 3;
+const QUERIES_SIZE: u64 = QUERIES as u64 * 8;
+/// The frames that can be in flight at once
+const READBACKS: usize = 3;
+/// The frames averaged over
+const SAMPLES: usize = 60;
+
+/// The features timing needs, timestamps being written inside the pass
+pub const FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES);
+
+/// The average GPU time of each draw over the last frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpuTimes {
+    pub quads: Duration,
+    pub text: Duration,
+}
+
+impl fmt::Display for GpuTimes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "GPU quads {:.2}ms\nGPU text {:.2}ms",
+            ms(self.quads),
+            ms(self.text)
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Readback {
+    buffer: wgpu::Buffer,
+    /// Set once the buffer's mapped
+    mapped: Arc<AtomicBool>,
+    in_flight: bool,
+}
+
+/// Times the draws of each frame, see [`Timer::times`]
+#[derive(Debug)]
+pub struct Timer {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    /// The readback of the frame being recorded, if it's timed
+    recording: Option<usize>,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    /// The quad & text times of the last frames, oldest first
+    samples: VecDeque<(Duration, Duration)>,
+}
+
+impl Timer {
+    /// `None` when the device lacks the [`FEATURES`]
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(FEATURES) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("wgputris.rend.timing"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERIES,
+        });
+        let resolve = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wgputris.rend.timing.resolve"),
+            size: QUERIES_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readbacks = (0..READBACKS)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("wgputris.rend.timing.readback"),
+                    size: QUERIES_SIZE,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                mapped: Arc::default(),
+                in_flight: false,
+            })
+            .collect();
+        Some(Self {
+            query_set,
+            resolve,
+            readbacks,
+            recording: None,
+            period: queue.get_timestamp_period(),
+            samples: VecDeque::with_capacity(SAMPLES),
+        })
+    }
+
+    /// Times the frame about to be recorded, unless every readback's in flight
+    pub fn begin(&mut self) {
+        self.recording = self.readbacks.iter().position(|r| !r.in_flight);
+    }
+
+    /// Writes the `index`th timestamp, when the frame's timed
+    pub fn write(&self, render_pass: &mut wgpu::RenderPass<'_>, index: u32) {
+        if self.recording.is_some() {
+            render_pass.write_timestamp(&self.query_set, index);
+        }
+    }
+
+    /// Copies the frame's timestamps to its readback, after the pass
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(index) = self.recording else {
+            return;
+        };
+        encoder.resolve_query_set(&self.query_set, 0..QUERIES, &self.resolve, 0);
+        let readback = &self.readbacks[index].buffer;
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, readback, 0, QUERIES_SIZE);
+    }
+
+    /// Maps the frame's readback, once it's been submitted
+    pub fn submitted(&mut self) {
+        let Some(index) = self.recording.take() else {
+            return;
+        };
+        let readback = &mut self.readbacks[index];
+        readback.in_flight = true;
+        let mapped = readback.mapped.clone();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                // a failed map leaves the readback in flight, so it's never
+                // reused
+                if result.is_ok() {
+                    mapped.store(true, Ordering::Release);
+                }
+            });
+    }
+
+    /// Takes the times of the frames done since the last call
+    pub fn collect(&mut self, device: &wgpu::Device) {
+        if !self.readbacks.iter().any(|r| r.in_flight) {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+        for readback in &mut self.readbacks {
+            if !readback.mapped.swap(false, Ordering::Acquire) {
+                continue;
+            }
+            let ticks: [u64; QUERIES as usize] = {
+                let view = readback.buffer.slice(..).get_mapped_range();
+                bytemuck::pod_read_unaligned(&view)
+            };
+            readback.buffer.unmap();
+            readback.in_flight = false;
+            let between = |from: u64, to: u64| {
+                Duration::from_nanos((to.saturating_sub(from) as f64 * self.period as f64) as u64)
+            };
+            if self.samples.len() == SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples
+                .push_back((between(ticks[0], ticks[1]), between(ticks[1], ticks[2])));
+        }
+    }
+
+    /// The average times over the last frames, `None` before any are done
+    pub fn times(&self) -> Option<GpuTimes> {
+        let frames = u32::try_from(self.samples.len()).ok().filter(|&n| n != 0)?;
+        let (quads, text) = self.samples.iter().fold(
+            (Duration::ZERO, Duration::ZERO),
+            |(q, t), &(quads, text)| (q + quads, t + text),
+        );
+        Some(GpuTimes {
+            quads: quads / frames,
+            text: text / frames,
+        })
+    }
+}
@@ -0,0 +1,963 @@
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+use crate::{daily, game, key, records, rend, styling, Action};
+
+/// What the window is showing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Screen {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+    Settings,
+    /// The keys bound to each action, rebound from the settings
+    Keys,
+    GameOver,
+}
+
+impl Screen {
+    /// The selectable entries, top to bottom
+    pub fn entries(self) -> &'static [Entry] {
+        use Entry::*;
+        match self {
+            Screen::Menu => &[Play, Daily, Mode, Settings, Quit],
+            Screen::Playing => &[],
+            Screen::Paused => &[Resume, Retry, Settings, Menu, Quit],
+            Screen::Settings => &[
+                Das,
+                Arr,
+                SoftDropArr,
+                Countdown,
+                SkipCountdown,
+                FreeFirstHold,
+                Rotate180,
+                Cascade,
+                Fade,
+                Theme,
+                Patterns,
+                GhostOutline,
+                PreviewCount,
+                Grid,
+                SmoothFall,
+                SmoothMove,
+                InputDisplay,
+                KeyPreset,
+                Gradients,
+                Letterbox,
+                ScaleFilter,
+                RenderScale,
+                MaxBlockSize,
+                HighContrast,
+                ReducedMotion,
+                PauseWhenHidden,
+                Keys,
+                Back,
+            ],
+            Screen::Keys => &[
+                Bind(Action::MoveLeft),
+                Bind(Action::MoveRight),
+                Bind(Action::MoveDown),
+                Bind(Action::Place),
+                Bind(Action::SonicDrop),
+                Bind(Action::RotateLeft),
+                Bind(Action::RotateRight),
+                Bind(Action::Rotate180),
+                Bind(Action::Hold),
+                Bind(Action::Back),
+                Bind(Action::Quit),
+                Back,
+            ],
+            Screen::GameOver => &[Retry, Menu],
+        }
+    }
+
+    /// Whether the game thread runs
+    pub fn is_playing(self) -> bool {
+        self == Screen::Playing
+    }
+}
+
+/// An entry of a screen's menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Entry {
+    Play,
+    Daily,
+    Resume,
+    Retry,
+    Settings,
+    Menu,
+    Quit,
+    Mode,
+    Das,
+    Arr,
+    SoftDropArr,
+    Countdown,
+    SkipCountdown,
+    FreeFirstHold,
+    Rotate180,
+    Cascade,
+    Fade,
+    Theme,
+    Patterns,
+    GhostOutline,
+    PreviewCount,
+    Grid,
+    SmoothFall,
+    SmoothMove,
+    InputDisplay,
+    KeyPreset,
+    Gradients,
+    Letterbox,
+    ScaleFilter,
+    RenderScale,
+    MaxBlockSize,
+    HighContrast,
+    ReducedMotion,
+    PauseWhenHidden,
+    Keys,
+    /// Rebinds an action, labelled by the app with [`bind_label`]
+    Bind(Action),
+    Back,
+}
+
+impl Entry {
+    pub fn label(self, out: &mut String, settings: &styling::Settings, setup: &game::Setup) {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let _ = match self {
+            Entry::Play => out.write_str("Play"),
+            Entry::Daily => out.write_str("Daily challenge"),
+            Entry::Resume => out.write_str("Resume"),
+            Entry::Retry => out.write_str("Retry"),
+            Entry::Settings => out.write_str("Settings"),
+            Entry::Menu => out.write_str("Menu"),
+            Entry::Quit => out.write_str("Quit"),
+            Entry::Mode => write!(out, "Mode: {}", setup.mode.name()),
+            Entry::Das => write!(out, "DAS: {}ms", setup.handling.das),
+            Entry::Arr => write!(out, "ARR: {}ms", setup.handling.arr),
+            Entry::SoftDropArr => write!(out, "Soft drop ARR: {}ms", setup.handling.down_arr),
+            Entry::Countdown => write!(out, "Countdown: {}s", setup.countdown / 1000),
+            Entry::SkipCountdown => {
+                write!(
+                    out,
+                    "Skip countdown with drop: {}",
+                    on_off(setup.skip_countdown)
+                )
+            }
+            Entry::FreeFirstHold => {
+                write!(out, "Free first hold: {}", on_off(setup.free_first_hold))
+            }
+            Entry::Rotate180 => write!(out, "180 rotation: {}", setup.rotate_180.name()),
+            Entry::Cascade => write!(out, "Cascade: {}", on_off(setup.cascade)),
+            Entry::Fade => match setup.fade {
+                0 => out.write_str("Fade: off"),
+                ms => write!(out, "Fade: {}s", ms / 1000),
+            },
+            Entry::Theme => write!(out, "Theme: {}", settings.theme_name()),
+            Entry::Patterns => write!(out, "Piece patterns: {}", on_off(settings.patterns)),
+            Entry::GhostOutline => write!(out, "Ghost outline: {}", on_off(settings.ghost_outline)),
+            Entry::PreviewCount => write!(out, "Next queue: {}", settings.preview_count),
+            Entry::Grid => write!(out, "Grid: {}", on_off(settings.grid)),
+            Entry::SmoothFall => write!(out, "Smooth fall: {}", on_off(settings.smooth_fall)),
+            Entry::SmoothMove => write!(out, "Smooth movement: {}", on_off(settings.smooth_move)),
+            Entry::InputDisplay => write!(out, "Input display: {}", on_off(settings.input_display)),
+            Entry::KeyPreset => write!(out, "Keys: {}", settings.key_preset.name()),
+            Entry::Gradients => write!(out, "Gradients: {}", on_off(settings.gradients)),
+            Entry::Letterbox => write!(out, "Letterbox: {}", on_off(settings.letterbox)),
+            Entry::ScaleFilter => write!(out, "Scaling: {}", settings.scale_filter.name()),
+            Entry::RenderScale => write!(out, "Render scale: {:.2}x", settings.render_scale),
+            Entry::MaxBlockSize => {
+                write!(out, "Largest blocks: {}px", settings.sizing.max_block_size)
+            }
+            Entry::HighContrast => write!(out, "High contrast: {}", on_off(settings.high_contrast)),
+            Entry::ReducedMotion => {
+                write!(out, "Reduced motion: {}", on_off(settings.reduced_motion))
+            }
+            Entry::PauseWhenHidden => {
+                write!(
+                    out,
+                    "Pause when hidden: {}",
+                    on_off(settings.pause_when_hidden)
+                )
+            }
+            Entry::Keys => out.write_str("Rebind keys"),
+            Entry::Bind(action) => out.write_str(action.name()),
+            Entry::Back => out.write_str("Back"),
+        };
+    }
+
+    /// Steps a setting up or down, toggling on & off settings
+    fn adjust(self, up: bool, settings: &mut styling::Settings, setup: &mut game::Setup) {
+        let step = |ms: &mut u32, by: u32, max: u32| {
+            *ms = if up {
+                (*ms + by).min(max)
+            } else {
+                ms.saturating_sub(by)
+            };
+        };
+        match self {
+            Entry::Mode => setup.mode = setup.mode.next(),
+            Entry::Das => step(&mut setup.handling.das, 10, 500),
+            Entry::Arr => step(&mut setup.handling.arr, 5, 200),
+            Entry::SoftDropArr => step(&mut setup.handling.down_arr, 5, 200),
+            Entry::Countdown => step(&mut setup.countdown, 1000, 5000),
+            Entry::SkipCountdown => setup.skip_countdown = !setup.skip_countdown,
+            Entry::FreeFirstHold => setup.free_first_hold = !setup.free_first_hold,
+            Entry::Rotate180 => setup.rotate_180 = setup.rotate_180.next(),
+            Entry::Cascade => setup.cascade = !setup.cascade,
+            Entry::Fade => step(&mut setup.fade, 1000, 10000),
+            // the palette is swapped by the app, which loads theme files
+            Entry::Theme => settings.next_theme(),
+            Entry::Patterns => settings.patterns = !settings.patterns,
+            Entry::GhostOutline => settings.ghost_outline = !settings.ghost_outline,
+            Entry::PreviewCount => {
+                step(&mut settings.preview_count, 1, styling::layout::NEXT_COUNT)
+            }
+            Entry::Grid => settings.grid = !settings.grid,
+            Entry::SmoothFall => settings.smooth_fall = !settings.smooth_fall,
+            Entry::SmoothMove => settings.smooth_move = !settings.smooth_move,
+            Entry::InputDisplay => settings.input_display = !settings.input_display,
+            // the binds are swapped by the app
+            Entry::KeyPreset => settings.key_preset = settings.key_preset.next(),
+            Entry::Gradients => settings.gradients = !settings.gradients,
+            Entry::Letterbox => settings.letterbox = !settings.letterbox,
+            Entry::ScaleFilter => settings.scale_filter = settings.scale_filter.next(),
+            Entry::RenderScale => {
+                let by = if up { 0.25 } else { -0.25 };
+                settings.render_scale = (settings.render_scale + by).clamp(1.0, 2.0);
+            }
+            Entry::MaxBlockSize => {
+                let size = &mut settings.sizing.max_block_size;
+                step(size, 8, styling::MAX_BLOCK_SIZE);
+                *size = (*size).max(styling::MIN_BLOCK_SIZE);
+            }
+            // the palette is swapped by the app
+            Entry::HighContrast => settings.high_contrast = !settings.high_contrast,
+            Entry::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            Entry::PauseWhenHidden => settings.pause_when_hidden = !settings.pause_when_hidden,
+            _ => (),
+        }
+    }
+}
+
+/// An action waiting on a key to be bound to, see [`Screen::Keys`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rebind {
+    pub action: Action,
+    /// A key pressed that's bound to another action, stolen from it once it's
+    /// pressed again
+    pub conflict: Option<(key::Key, Action)>,
+}
+
+/// What a key pressed while rebinding did, see [`Rebind::capture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capture {
+    /// Escape gave up on rebinding
+    Cancelled,
+    /// The key's bound to another action, waiting on it again to steal it
+    Conflict,
+    /// The key's now bound to the action
+    Bound,
+}
+
+impl Rebind {
+    pub fn new(action: Action) -> Self {
+        Self {
+            action,
+            conflict: None,
+        }
+    }
+
+    /// Binds the key to the action, Escape cancelling
+    ///
+    /// A key bound to another action is only stolen once it's pressed again.
+    /// Keys are bound without modifiers, chords being left to the config.
+    pub fn capture(&mut self, key: key::Key, keys: &key::KeyStore) -> Capture {
+        if key == key::Key::Code(winit::keyboard::KeyCode::Escape) {
+            return Capture::Cancelled;
+        }
+        let bound = keys.action(key.into());
+        if bound.is_some_and(|bound| bound != self.action)
+            && self.conflict.is_none_or(|(conflict, _)| conflict != key)
+        {
+            self.conflict = bound.map(|bound| (key, bound));
+            return Capture::Conflict;
+        }
+        keys.bind(key.into(), self.action);
+        Capture::Bound
+    }
+}
+
+// NOTE: the texts are written into strings kept from frame to frame, the app
+// clearing them first, so a frame showing what the last did allocates nothing.
+
+/// An action's entry on the keys screen, its keys or how rebinding it is going
+pub fn bind_label(
+    out: &mut String,
+    action: Action,
+    keys: impl IntoIterator<Item = key::Chord>,
+    rebind: Option<&Rebind>,
+) {
+    let name = action.name();
+    match rebind.filter(|rebind| rebind.action == action) {
+        Some(Rebind {
+            conflict: Some((key, bound)),
+            ..
+        }) => {
+            let _ = write!(out, "{name}: {key} is {}, again to steal", bound.name());
+        }
+        Some(_) => {
+            let _ = write!(out, "{name}: press a key, Esc to cancel");
+        }
+        None => {
+            out.push_str(name);
+            let start = out.len();
+            for key in keys {
+                let sep = if out.len() == start { ": " } else { ", " };
+                let _ = write!(out, "{sep}{key}");
+            }
+            if out.len() == start {
+                out.push_str(": unbound");
+            }
+        }
+    }
+}
+
+/// The results of a finished game, a line each, after the daily challenge's
+/// date & whether it was the day's official run
+///
+/// A span each for the labels, in the palette's `fg2`, & their values, in
+/// `fg`.
+pub fn summary(
+    spans: &mut Vec<(String, rend::SpanAttrs)>,
+    game: &game::RenderSnapshot,
+    records: &records::Records,
+    new_record: bool,
+    daily: Option<(daily::Date, bool)>,
+    palette: &styling::Palette,
+) {
+    let label = rend::SpanAttrs::colour(palette.fg2);
+    let value = rend::SpanAttrs::colour(palette.fg);
+    let mut n = 0;
+    if let Some((date, official)) = daily {
+        let run = if official { "official run" } else { "practice" };
+        let _ = writeln!(
+            span(spans, n, rend::SpanAttrs::default()),
+            "Daily {date}, {run}"
+        );
+        n += 1;
+    }
+    let stats = game.stats();
+    let elapsed = game.active_elapsed();
+    for (name, stat) in [
+        ("Score", &stats.score() as &dyn fmt::Display),
+        ("Lines", &stats.lines()),
+        ("Level", &stats.level()),
+        ("Time", &Clock(elapsed)),
+        ("PPS", &format_args!("{:.2}", stats.pps(elapsed))),
+        ("Seed", &game.seed()),
+    ] {
+        let _ = write!(span(spans, n, label), "{name} ");
+        let _ = writeln!(span(spans, n + 1, value), "{stat}");
+        n += 2;
+    }
+    let out = span(spans, n, value);
+    match records.best(game.mode()) {
+        _ if new_record => out.push_str("New record!"),
+        Some(best) => {
+            out.push_str("Best ");
+            record(out, best);
+        }
+        None => (),
+    }
+    out.push_str("\n\nR to retry, Esc for menu");
+    spans.truncate(n + 1);
+}
+
+/// The `i`th span, emptied & given `attrs`, reusing its string
+fn span(
+    spans: &mut Vec<(String, rend::SpanAttrs)>,
+    i: usize,
+    attrs: rend::SpanAttrs,
+) -> &mut String {
+    if i == spans.len() {
+        spans.push((String::new(), attrs));
+    }
+    let (text, span_attrs) = &mut spans[i];
+    text.clear();
+    *span_attrs = attrs;
+    text
+}
+
+/// The best run of a mode, shown on the menu
+pub fn best_run(out: &mut String, records: &records::Records, mode: game::Mode) {
+    let Some(best) = records.best(mode) else {
+        return;
+    };
+    out.push_str("Best ");
+    record(out, best);
+    let time = Clock(Duration::from_millis(best.time));
+    let _ = write!(out, "\nLines {}\nTime {time}", best.lines);
+}
+
+/// What a run's ranked by, a finished sprint's time or otherwise its score
+fn record(out: &mut String, run: &records::Run) {
+    let _ = if run.mode == game::Mode::Sprint && run.lines >= game::SPRINT_LINES {
+        write!(out, "{}", Clock(Duration::from_millis(run.time)))
+    } else {
+        write!(out, "{}", run.score)
+    };
+}
+
+/// The attack & defense of a game in versus, a line each
+pub fn versus_text(out: &mut String, game: &game::RenderSnapshot) {
+    let stats = game.stats();
+    let _ = write!(
+        out,
+        "Sent {}\nReceived {}\nCancelled {}\nAPM {:.1}",
+        stats.sent(),
+        stats.received(),
+        stats.cancelled(),
+        stats.apm(game.active_elapsed()),
+    );
+}
+
+/// The day's challenge & whether it's been played, shown on the menu
+pub fn daily_text(out: &mut String, records: &records::Records, date: daily::Date) {
+    let _ = write!(
+        out,
+        "Daily {date}\nSeed {}\nUltra, {}\n",
+        date.seed(),
+        Clock(game::ULTRA_TIME)
+    );
+    let _ = match records.daily(date) {
+        Some(run) => write!(out, "Played, score {}", run.score),
+        None => out.write_str("Not played yet"),
+    };
+}
+
+/// How far through the puzzles the records are
+pub fn puzzle_progress(out: &mut String, records: &records::Records, puzzles: &[game::Puzzle]) {
+    let solved = puzzles
+        .iter()
+        .filter(|puzzle| records.is_solved(&puzzle.name))
+        .count();
+    let _ = write!(out, "Solved {solved}/{}", puzzles.len());
+}
+
+/// The objective of the puzzle being played & how it's going
+pub fn objective_text(out: &mut String, puzzle: &game::Puzzle, game: &game::RenderSnapshot) {
+    let _ = match puzzle.objective {
+        game::Objective::Lines { lines } => write!(
+            out,
+            "{}\nClear {lines} lines\nLines {}/{lines}",
+            puzzle.name,
+            game.stats().lines()
+        ),
+        game::Objective::PerfectClear => write!(out, "{}\nPerfect clear", puzzle.name),
+    };
+    let left = game.pieces_left().unwrap_or_default();
+    let _ = write!(out, "\nMinos left {left}");
+}
+
+/// How long "GO" stays up after a countdown
+pub const GO_SHOWN: Duration = Duration::from_millis(500);
+
+/// The countdown before a game, whole seconds then "GO"
+///
+/// Returns whether there's a countdown to show.
+pub fn countdown_text(out: &mut String, game: &game::RenderSnapshot) -> bool {
+    let _ = match game.countdown() {
+        Some(left) => write!(out, "{}", left.as_millis().div_ceil(1000)),
+        None if game.counted_down() && game.active_elapsed() < GO_SHOWN => out.write_str("GO"),
+        None => return false,
+    };
+    true
+}
+
+/// A duration shown as `m:ss.cc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock(pub Duration);
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let centis = self.0.as_millis() / 10;
+        write!(
+            f,
+            "{}:{:02}.{:02}",
+            centis / 6000,
+            centis / 100 % 60,
+            centis % 100
+        )
+    }
+}
+
+/// What the window's title shows
+///
+/// Kept apart from the text, so the title is only set when it changes, which
+/// is slow on some platforms. The timer moves in half seconds for the same
+/// reason.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Title {
+    #[default]
+    Menu,
+    Playing {
+        mode: game::Mode,
+        half_secs: u64,
+        lines: u32,
+    },
+    Paused,
+    GameOver {
+        score: u32,
+    },
+}
+
+impl Title {
+    pub fn new(screen: Screen, game: &game::RenderSnapshot) -> Self {
+        match screen {
+            Screen::Menu | Screen::Settings | Screen::Keys => Title::Menu,
+            Screen::Playing => Title::Playing {
+                mode: game.mode(),
+                half_secs: game.active_elapsed().as_millis() as u64 / 500,
+                lines: game.stats().lines(),
+            },
+            Screen::Paused => Title::Paused,
+            Screen::GameOver => Title::GameOver {
+                score: game.stats().score(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Title {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("wgputris")?;
+        match *self {
+            Title::Menu => Ok(()),
+            Title::Playing {
+                mode,
+                half_secs,
+                lines,
+            } => {
+                let secs = half_secs as f32 / 2.0;
+                write!(f, " — {} {secs:.1}s / {lines} lines", mode.name())
+            }
+            Title::Paused => f.write_str(" — Paused"),
+            Title::GameOver { score } => {
+                write!(f, " — Game Over (score {})", thousands(score))
+            }
+        }
+    }
+}
+
+/// A number with its thousands split by commas, e.g. `123,456`
+pub fn thousands(n: u32) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i != 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// What the app has to do after a screen change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Start a new game & play it
+    Start,
+    /// Start the day's challenge & play it
+    Daily,
+    /// Play the last game again from the start
+    Retry,
+    /// Wait on a key to bind to the action, see [`Rebind`]
+    Rebind(Action),
+    Quit,
+}
+
+/// The current screen & its selected entry
+#[derive(Debug, Default)]
+pub struct Screens {
+    screen: Screen,
+    /// Where the settings screen goes back to
+    back: Screen,
+    selected: usize,
+}
+
+impl Screens {
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    /// The index of the selected entry
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_entry(&self) -> Option<Entry> {
+        self.screen.entries().get(self.selected).copied()
+    }
+
+    /// Whether the game is shown, behind the menus of a paused game
+    pub fn shows_game(&self) -> bool {
+        match self.screen {
+            Screen::Menu => false,
+            Screen::Settings | Screen::Keys => self.back != Screen::Menu,
+            _ => true,
+        }
+    }
+
+    pub fn set_screen(&mut self, screen: Screen) {
+        // the keys screen goes back to the settings, which keep their way back
+        if screen == Screen::Settings && self.screen != Screen::Keys {
+            self.back = self.screen;
+        }
+        self.screen = screen;
+        self.selected = 0;
+    }
+
+    /// Moves through the screens, settings changing in place
+    pub fn navigate(
+        &mut self,
+        nav: key::Nav,
+        settings: &mut styling::Settings,
+        setup: &mut game::Setup,
+    ) -> Option<Command> {
+        let entries = self.screen.entries();
+        let entry = entries.get(self.selected).copied();
+        match nav {
+            key::Nav::Up if !entries.is_empty() => {
+                self.selected = (self.selected + entries.len() - 1) % entries.len();
+            }
+            key::Nav::Down if !entries.is_empty() => {
+                self.selected = (self.selected + 1) % entries.len();
+            }
+            key::Nav::Left | key::Nav::Right => {
+                if let Some(entry) = entry {
+                    entry.adjust(nav == key::Nav::Right, settings, setup);
+                }
+            }
+            key::Nav::Select => return self.select(entry?, settings, setup),
+            key::Nav::Retry if self.screen == Screen::GameOver => {
+                return self.select(Entry::Retry, settings, setup);
+            }
+            key::Nav::Back => match self.screen {
+                Screen::Menu => return Some(Command::Quit),
+                Screen::Playing => self.set_screen(Screen::Paused),
+                Screen::Paused => self.set_screen(Screen::Playing),
+                Screen::Settings => self.set_screen(self.back),
+                Screen::Keys => self.set_screen(Screen::Settings),
+                Screen::GameOver => self.set_screen(Screen::Menu),
+            },
+            _ => (),
+        }
+        None
+    }
+
+    fn select(
+        &mut self,
+        entry: Entry,
+        settings: &mut styling::Settings,
+        setup: &mut game::Setup,
+    ) -> Option<Command> {
+        match entry {
+            Entry::Play => {
+                self.set_screen(Screen::Playing);
+                return Some(Command::Start);
+            }
+            Entry::Daily => {
+                self.set_screen(Screen::Playing);
+                return Some(Command::Daily);
+            }
+            Entry::Retry => {
+                self.set_screen(Screen::Playing);
+                return Some(Command::Retry);
+            }
+            Entry::Resume => self.set_screen(Screen::Playing),
+            Entry::Settings => self.set_screen(Screen::Settings),
+            Entry::Menu => self.set_screen(Screen::Menu),
+            Entry::Quit => return Some(Command::Quit),
+            Entry::Keys => self.set_screen(Screen::Keys),
+            Entry::Bind(action) => return Some(Command::Rebind(action)),
+            Entry::Back if self.screen == Screen::Keys => self.set_screen(Screen::Settings),
+            Entry::Back => self.set_screen(self.back),
+            _ => entry.adjust(true, settings, setup),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+
+    fn code(code: KeyCode) -> key::Key {
+        key::Key::Code(code)
+    }
+
+    fn action(keys: &key::KeyStore, code: KeyCode) -> Option<Action> {
+        keys.action(key::Key::Code(code).into())
+    }
+
+    #[test]
+    fn free_keys_bind_straight_away() {
+        let keys = key::KeyStore::default();
+        let mut rebind = Rebind::new(Action::Hold);
+        assert_eq!(rebind.capture(code(KeyCode::KeyH), &keys), Capture::Bound);
+        assert_eq!(action(&keys, KeyCode::KeyH), Some(Action::Hold));
+        // in place of its old key
+        assert_eq!(action(&keys, KeyCode::KeyC), None);
+        assert_eq!(keys.keys_of(Action::Hold), [code(KeyCode::KeyH).into()]);
+        assert!(keys
+            .custom()
+            .contains(&(code(KeyCode::KeyH).into(), Action::Hold)));
+    }
+
+    #[test]
+    fn scancodes_bind() {
+        let keys = key::KeyStore::default();
+        let mut rebind = Rebind::new(Action::Hold);
+        assert_eq!(rebind.capture(key::Key::Numeric(97), &keys), Capture::Bound);
+        assert_eq!(
+            keys.action(key::Key::Numeric(97).into()),
+            Some(Action::Hold)
+        );
+    }
+
+    #[test]
+    fn a_key_of_the_same_action_binds() {
+        let keys = key::KeyStore::default();
+        let mut rebind = Rebind::new(Action::RotateRight);
+        assert_eq!(rebind.capture(code(KeyCode::KeyX), &keys), Capture::Bound);
+        assert_eq!(
+            keys.keys_of(Action::RotateRight),
+            [code(KeyCode::KeyX).into()]
+        );
+    }
+
+    #[test]
+    fn bound_keys_are_stolen_when_pressed_again() {
+        let keys = key::KeyStore::default();
+        let mut rebind = Rebind::new(Action::Hold);
+        let space = code(KeyCode::Space);
+        assert_eq!(rebind.capture(space, &keys), Capture::Conflict);
+        assert_eq!(rebind.conflict, Some((space, Action::Place)));
+        assert_eq!(action(&keys, KeyCode::Space), Some(Action::Place));
+
+        assert_eq!(rebind.capture(space, &keys), Capture::Bound);
+        assert_eq!(action(&keys, KeyCode::Space), Some(Action::Hold));
+        assert!(keys.keys_of(Action::Place).is_empty());
+    }
+
+    #[test]
+    fn another_conflict_starts_over() {
+        let keys = key::KeyStore::default();
+        let mut rebind = Rebind::new(Action::Hold);
+        assert_eq!(
+            rebind.capture(code(KeyCode::Space), &keys),
+            Capture::Conflict
+        );
+        let z = code(KeyCode::KeyZ);
+        assert_eq!(rebind.capture(z, &keys), Capture::Conflict);
+        assert_eq!(rebind.conflict, Some((z, Action::RotateLeft)));
+        // space needs pressing twice again
+        assert_eq!(
+            rebind.capture(code(KeyCode::Space), &keys),
+            Capture::Conflict
+        );
+        assert_eq!(action(&keys, KeyCode::Space), Some(Action::Place));
+    }
+
+    #[test]
+    fn escape_cancels() {
+        let keys = key::KeyStore::default();
+        let binds = keys.custom();
+        let mut rebind = Rebind::new(Action::Hold);
+        assert_eq!(
+            rebind.capture(code(KeyCode::Escape), &keys),
+            Capture::Cancelled
+        );
+        // even part way through stealing
+        let mut rebind = Rebind::new(Action::Hold);
+        assert_eq!(
+            rebind.capture(code(KeyCode::Space), &keys),
+            Capture::Conflict
+        );
+        assert_eq!(
+            rebind.capture(code(KeyCode::Escape), &keys),
+            Capture::Cancelled
+        );
+        assert_eq!(keys.custom(), binds);
+        assert_eq!(action(&keys, KeyCode::Space), Some(Action::Place));
+        assert_eq!(action(&keys, KeyCode::Escape), Some(Action::Back));
+    }
+
+    #[test]
+    fn bound_keys_stay_unique() {
+        let keys = key::KeyStore::default();
+        for (action, key) in [
+            (Action::Hold, KeyCode::KeyH),
+            (Action::MoveLeft, KeyCode::KeyH),
+            (Action::MoveLeft, KeyCode::KeyH),
+        ] {
+            let mut rebind = Rebind::new(action);
+            if rebind.capture(code(key), &keys) == Capture::Conflict {
+                assert_eq!(rebind.capture(code(key), &keys), Capture::Bound);
+            }
+        }
+        assert_eq!(action(&keys, KeyCode::KeyH), Some(Action::MoveLeft));
+        assert!(keys.keys_of(Action::Hold).is_empty());
+        let chords: Vec<_> = keys.custom().into_iter().map(|(chord, _)| chord).collect();
+        let mut unique = chords.clone();
+        unique.sort_by_key(ToString::to_string);
+        unique.dedup();
+        assert_eq!(unique.len(), chords.len());
+    }
+
+    fn run(mode: game::Mode, score: u32, lines: u32, time: u64) -> records::Run {
+        records::Run {
+            mode,
+            score,
+            lines,
+            time,
+            seed: 0,
+            rotate_180: Default::default(),
+            at: 0,
+        }
+    }
+
+    /// Writes the text twice into the same string, returning it & the
+    /// allocations of the second time
+    fn rewrite(mut write: impl FnMut(&mut String)) -> (String, usize) {
+        let mut out = String::new();
+        write(&mut out);
+        out.clear();
+        let allocations = crate::alloc_count::allocations();
+        write(&mut out);
+        (out, crate::alloc_count::allocations() - allocations)
+    }
+
+    #[track_caller]
+    fn assert_steady(name: &str, write: impl FnMut(&mut String)) {
+        let (text, allocations) = rewrite(write);
+        assert!(!text.is_empty(), "{name} is empty");
+        assert_eq!(allocations, 0, "{name} allocated");
+    }
+
+    #[test]
+    fn clocks_show_centiseconds() {
+        let clock = |ms| Clock(Duration::from_millis(ms)).to_string();
+        assert_eq!(clock(0), "0:00.00");
+        assert_eq!(clock(83_456), "1:23.45");
+        assert_eq!(clock(600_000), "10:00.00");
+    }
+
+    #[test]
+    fn rewritten_texts_allocate_nothing() {
+        let game = game::Game::new(Some(0), crate::time::DEFAULT_TICK_RATE);
+        let game = game::RenderSnapshot::new(&game);
+        let mut records = records::Records::default();
+        records.update(game::Mode::Sprint, run(game::Mode::Sprint, 900, 40, 83_456));
+        let date = daily::Date::from_days(20_000);
+        records.play_daily(date, run(game::Mode::Daily, 1234, 10, 120_000));
+        let puzzle = game::Puzzle {
+            name: "Four".into(),
+            board: game::Board::default(),
+            queue: vec![game::Block::I],
+            hold: None,
+            objective: game::Objective::Lines { lines: 4 },
+        };
+        let settings = styling::Settings::default();
+        let setup = game::Setup::default();
+        let keys = [
+            key::Key::Code(KeyCode::KeyC).into(),
+            key::Chord::from(key::Key::Code(KeyCode::KeyH)),
+        ];
+        assert_steady("versus", |out| versus_text(out, &game));
+        assert_steady("best run", |out| {
+            best_run(out, &records, game::Mode::Sprint)
+        });
+        assert_steady("daily", |out| daily_text(out, &records, date));
+        assert_steady("objective", |out| objective_text(out, &puzzle, &game));
+        assert_steady("bind", |out| bind_label(out, Action::Hold, keys, None));
+        let screens = [
+            Screen::Menu,
+            Screen::Paused,
+            Screen::Settings,
+            Screen::Keys,
+            Screen::GameOver,
+        ];
+        assert_steady("labels", |out| {
+            for entry in screens.into_iter().flat_map(Screen::entries) {
+                entry.label(out, &settings, &setup);
+            }
+        });
+        assert_eq!(
+            rewrite(|out| best_run(out, &records, game::Mode::Sprint)).0,
+            "Best 1:23.45\nLines 40\nTime 1:23.45"
+        );
+        assert_eq!(
+            rewrite(|out| bind_label(out, Action::Hold, keys, None)).0,
+            "Hold: C, H"
+        );
+        assert_eq!(
+            rewrite(|out| bind_label(out, Action::Hold, [], None)).0,
+            "Hold: unbound"
+        );
+    }
+
+    #[test]
+    fn summaries_reuse_their_spans() {
+        let game = game::Game::new(Some(0), crate::time::DEFAULT_TICK_RATE);
+        let game = game::RenderSnapshot::new(&game);
+        let records = records::Records::default();
+        let palette = styling::Palette::light();
+        let date = daily::Date::from_days(20_000);
+        let mut spans = Vec::new();
+        summary(
+            &mut spans,
+            &game,
+            &records,
+            false,
+            Some((date, true)),
+            &palette,
+        );
+        assert_eq!(spans.len(), 14);
+        assert_eq!(spans[0].0, format!("Daily {date}, official run\n"));
+
+        let allocations = crate::alloc_count::allocations();
+        summary(
+            &mut spans,
+            &game,
+            &records,
+            false,
+            Some((date, true)),
+            &palette,
+        );
+        assert_eq!(crate::alloc_count::allocations() - allocations, 0);
+
+        summary(&mut spans, &game, &records, true, None, &palette);
+        let text: String = spans.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(
+            text,
+            "Score 0\nLines 0\nLevel 1\nTime 0:00.00\nPPS 0.00\nSeed 0\n\
+             New record!\n\nR to retry, Esc for menu"
+        );
+        assert_eq!(spans[0].1, rend::SpanAttrs::colour(palette.fg2));
+        assert_eq!(spans[1].1, rend::SpanAttrs::colour(palette.fg));
+    }
+
+    #[test]
+    fn the_keys_entry_switches_presets() {
+        let mut settings = styling::Settings::default();
+        let mut setup = game::Setup::default();
+        Entry::KeyPreset.adjust(true, &mut settings, &mut setup);
+        assert_eq!(settings.key_preset, key::Preset::Guideline);
+        let mut label = String::new();
+        Entry::KeyPreset.label(&mut label, &settings, &setup);
+        assert_eq!(label, "Keys: Guideline");
+    }
+}
@@ -0,0 +1,71 @@
+// NOTE: screenshots are read back & encoded on a thread of their own, see
+// `rend::capture`, the path being handed back once the file's written.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::{daily, rend};
+
+/// Where screenshots are saved
+pub fn dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("wgputris").join("screenshots"))
+}
+
+/// The file name of a screenshot taken now, by its UTC time
+fn file_name() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let secs = (millis / 1000) as u64;
+    let date = daily::Date::from_days((secs / 86_400) as i64);
+    let (h, m, s) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    format!(
+        "wgputris-{date}-{h:02}-{m:02}-{s:02}-{:03}.png",
+        millis % 1000
+    )
+}
+
+/// Reads back & saves a capture on a thread of its own, calling `saved` with
+/// its path once it's written
+pub fn save(capture: rend::capture::Capture, saved: impl FnOnce(PathBuf) + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name("screenshot".into())
+        .spawn(move || {
+            let saving = || -> anyhow::Result<PathBuf> {
+                let dir = dir().context("no data directory to save screenshots in")?;
+                let image = capture.read()?;
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("failed to create {}", dir.display()))?;
+                let path = dir.join(file_name());
+                write_png(&path, &image)?;
+                Ok(path)
+            };
+            match saving() {
+                Ok(path) => {
+                    log::info!("saved screenshot to {}", path.display());
+                    saved(path);
+                }
+                Err(err) => log::error!("failed to save screenshot: {err:#}"),
+            }
+        });
+    if let Err(err) = spawned {
+        log::error!("failed to start saving screenshot: {err}");
+    }
+}
+
+fn write_png(path: &Path, image: &rend::capture::Image) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.rgba)?;
+    writer.finish()?;
+    Ok(())
+}
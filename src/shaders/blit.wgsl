@@ -0,0 +1,26 @@
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// a single triangle covering the viewport, its uv going from 0 to 1 over it
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var out: VertexOutput;
+
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+  out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+  out.uv = uv;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return textureSample(scene, scene_sampler, in.uv);
+}
@@ -0,0 +1,141 @@
+struct Uniforms {
+  bounds: vec2<u32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var skin: texture_2d<f32>;
+@group(1) @binding(1)
+var skin_sampler: sampler;
+
+struct VertexInput {
+  @location(0) colour: vec4<f32>, // r,g,b,a
+  @location(1) pos: vec2<i32>,  // x, y
+  @location(2) uv: vec2<f32>,  // u, v
+  @location(3) local: vec2<f32>, // position within the quad
+  @location(4) size: vec2<f32>, // width, height
+  @location(5) shape: vec4<f32>, // border, radius, bevel, hollow
+  @location(6) border_colour: vec4<f32>, // r,g,b,a
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) colour: vec4<f32>, // r,g,b,a
+  @location(1) uv: vec2<f32>,
+  @location(2) local: vec2<f32>,
+  @location(3) @interpolate(flat) size: vec2<f32>,
+  @location(4) @interpolate(flat) shape: vec4<f32>,
+  @location(5) @interpolate(flat) border_colour: vec4<f32>,
+};
+
+@vertex
+fn vs_main(rect: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+
+  let ndc = ((vec2<f32>(rect.pos) / vec2<f32>(uniforms.bounds)) * 2.0) - vec2<f32>(1.0, 1.0);
+
+  out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+  out.colour = rect.colour;
+  out.uv = rect.uv;
+  out.local = rect.local;
+  out.size = rect.size;
+  out.shape = rect.shape;
+  out.border_colour = rect.border_colour;
+  return out;
+}
+
+struct InstanceInput {
+  @location(0) rect: vec4<i32>, // x, y, width, height, off screen up & left when negative
+  @location(1) colour: vec4<f32>, // r,g,b,a
+  @location(2) uv: vec4<f32>, // left, top, right, bottom
+  @location(3) shape: vec4<f32>, // border, radius, bevel, hollow
+  @location(4) border_colour: vec4<f32>, // r,g,b,a
+};
+
+// the corners of the two triangles, matching the expanded vertices
+var<private> CORNERS: array<vec2<i32>, 6> = array<vec2<i32>, 6>(
+  vec2<i32>(0, 0), // tl
+  vec2<i32>(0, 1), // bl
+  vec2<i32>(1, 1), // br
+  vec2<i32>(1, 0), // tr
+  vec2<i32>(0, 0), // tl
+  vec2<i32>(1, 1), // br
+);
+
+@vertex
+fn vs_instance(@builtin(vertex_index) index: u32, rect: InstanceInput) -> VertexOutput {
+  var out: VertexOutput;
+
+  let corner = CORNERS[index];
+  let pos = rect.rect.xy + corner * rect.rect.zw;
+  let ndc = ((vec2<f32>(pos) / vec2<f32>(uniforms.bounds)) * 2.0) - vec2<f32>(1.0, 1.0);
+
+  out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+  out.colour = vec4<f32>(srgb_to_linear(rect.colour.rgb), rect.colour.a);
+  out.uv = mix(rect.uv.xy, rect.uv.zw, vec2<f32>(corner));
+  out.local = vec2<f32>(corner * rect.rect.zw);
+  out.size = vec2<f32>(rect.rect.zw);
+  out.shape = rect.shape;
+  out.border_colour = vec4<f32>(srgb_to_linear(rect.border_colour.rgb), rect.border_colour.a);
+  return out;
+}
+
+// instance colours are sRGB bytes, vertex colours being decoded already
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+  let low = c / 12.92;
+  let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+  return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+// the signed distance to the quad's rounded rect, negative inside
+fn rounded_dist(local: vec2<f32>, size: vec2<f32>, radius: f32) -> f32 {
+  let half = size * 0.5;
+  let r = min(radius, min(half.x, half.y));
+  let q = abs(local - half) - half + r;
+  return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
+
+// shape: border width, corner radius, bevel strength, hollow
+fn shade(
+  colour: vec4<f32>,
+  local: vec2<f32>,
+  size: vec2<f32>,
+  shape: vec4<f32>,
+  border_colour: vec4<f32>,
+) -> vec4<f32> {
+  let border = shape.x;
+  let bevel = shape.z;
+  let hollow = shape.w > 0.5;
+  let dist = rounded_dist(local, size, shape.y);
+  let edge = -dist;
+
+  var rgb = colour.rgb;
+  // top-lit, lighter along the top & left, darker along the bottom & right
+  if bevel > 0.0 && edge < min(size.x, size.y) * 0.15 {
+    if min(local.x, local.y) < min(size.x - local.x, size.y - local.y) {
+      rgb = mix(rgb, vec3<f32>(1.0), bevel * 0.5);
+    } else {
+      rgb = mix(rgb, vec3<f32>(0.0), bevel * 0.5);
+    }
+  }
+
+  // coverage is 1 everywhere for square corners
+  var alpha = colour.a * clamp(0.5 - dist, 0.0, 1.0);
+  // a border of 0 draws none, not even over the antialiased edge
+  if border > 0.0 && edge < border {
+    if !hollow {
+      rgb = mix(rgb, border_colour.rgb, border_colour.a);
+    }
+  } else if hollow {
+    alpha = 0.0;
+  }
+  return vec4<f32>(rgb, alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let colour = textureSample(skin, skin_sampler, in.uv) * in.colour; // tint the sprite
+  return shade(colour, in.local, in.size, in.shape, in.border_colour);
+}
@@ -0,0 +1,224 @@
+// NOTE: the stats window is a second window showing the game's stats & a graph
+// of its PPS, e.g. to keep on another monitor while streaming. It has a
+// renderer of its own on the main window's device & queue, & is redrawn after
+// each frame of the main window. It only reads the game's snapshots, never
+// taking its events, which are left for the main window.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use winit::dpi::LogicalSize;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::Window;
+
+use crate::rend::{self, ScreenSize};
+use crate::{game, screen, styling};
+
+const TEXT_METRICS: glyphon::Metrics = glyphon::Metrics::new(20.0, 28.0);
+/// The lines of stats above the graph
+const TEXT_LINES: u32 = 5;
+/// The space around the window's edges, in logical px
+const MARGIN: f32 = 16.0;
+/// The thickness of the graph's line, in logical px
+const LINE: f32 = 2.0;
+/// How much active play time each sample is apart
+const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+/// The samples graphed, the oldest dropping off the left
+const SAMPLES: usize = 120;
+
+pub struct StatsWindow {
+    rend: rend::Rend,
+    game: Arc<Mutex<game::GameState>>,
+    /// The PPS at every sample so far, oldest first
+    pps: VecDeque<f32>,
+    /// The active play time of the last sample
+    sampled: Duration,
+    // NOTE: should be dropped last
+    window: Arc<Window>,
+}
+
+impl StatsWindow {
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        instance: &wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        game: Arc<Mutex<game::GameState>>,
+    ) -> anyhow::Result<Self> {
+        let attributes = Window::default_attributes()
+            .with_title("wgputris stats")
+            .with_inner_size(LogicalSize::new(480, 360));
+        let window = Arc::new(event_loop.create_window(attributes)?);
+        let surface = instance.create_surface(window.clone())?;
+        let caps = surface.get_capabilities(adapter);
+        let format = caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .or(caps.formats.first().copied())
+            .context("the adapter can't draw to the window")?;
+        let scale = window.scale_factor();
+        let mut rend = rend::Rend::new(
+            ScreenSize::new(window.inner_size(), scale),
+            device,
+            queue,
+            format,
+            rend::Output::Surface(surface),
+            1,
+            adapter.get_texture_format_features(format).flags,
+        );
+        rend.gen_text_layer(
+            TEXT_METRICS.scale(scale as f32),
+            rend::TextLayerDesc {
+                name: "stats".into(),
+                scale: 1.0,
+                ..Default::default()
+            },
+        );
+        rend.gen_quad_layer("graph");
+        Ok(Self {
+            rend,
+            game,
+            pps: VecDeque::with_capacity(SAMPLES),
+            sampled: Duration::ZERO,
+            window,
+        })
+    }
+
+    pub fn window(&self) -> &Arc<Window> {
+        &self.window
+    }
+
+    pub fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let scale = self.window.scale_factor();
+        self.rend.resize(ScreenSize::new(size, scale));
+        if let Some(layer) = self.rend.get_text_mut("stats") {
+            layer.set_metrics(TEXT_METRICS.scale(scale as f32));
+        }
+    }
+
+    /// Samples the PPS once a sample's worth of play has gone by, starting
+    /// over when a new game starts
+    fn sample(&mut self, snapshot: &game::RenderSnapshot) {
+        let elapsed = snapshot.active_elapsed();
+        if elapsed < self.sampled {
+            self.pps.clear();
+            self.sampled = Duration::ZERO;
+        }
+        while elapsed >= self.sampled + SAMPLE_EVERY {
+            self.sampled += SAMPLE_EVERY;
+            if self.pps.len() == SAMPLES {
+                self.pps.pop_front();
+            }
+            self.pps.push_back(snapshot.stats().pps(self.sampled));
+        }
+    }
+
+    /// Renders a frame, skipped while the window has no area
+    pub fn render(&mut self, settings: &styling::Settings) -> Result<(), wgpu::SurfaceError> {
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        let snapshot = self.game.lock().unwrap().snapshot();
+        self.sample(&snapshot);
+        let scale = self.window.scale_factor() as f32;
+        let palette = &settings.palette;
+        let margin = (MARGIN * scale) as u32;
+        let text_height = (TEXT_METRICS.line_height * scale) as u32 * TEXT_LINES;
+        let text_rect = rend::Rect {
+            x: margin,
+            y: margin,
+            width: size.width.saturating_sub(margin * 2),
+            height: text_height,
+        };
+        let graph_rect = rend::Rect {
+            x: margin,
+            y: margin * 2 + text_height,
+            width: text_rect.width,
+            height: size.height.saturating_sub(margin * 3 + text_height),
+        };
+
+        if let Some(layer) = self.rend.get_text_mut("stats") {
+            let stats = snapshot.stats();
+            let elapsed = snapshot.active_elapsed();
+            layer.set_rect(text_rect);
+            layer.default_color = palette.fg.into();
+            layer.set_text_if_changed(&format!(
+                "Score {}\nLines {}\nLevel {}\nTime {}\nPPS {:.2}",
+                stats.score(),
+                stats.lines(),
+                stats.level(),
+                screen::Clock(elapsed),
+                stats.pps(elapsed),
+            ));
+        }
+        if let Some(layer) = self.rend.get_quad_mut("graph") {
+            draw_graph(
+                &self.pps,
+                graph_rect,
+                (LINE * scale).max(1.0) as u32,
+                palette,
+                layer,
+            );
+        }
+        self.rend.render_frame(palette.bg.into())
+    }
+}
+
+/// The PPS as a stepped line over a baseline, scaled so its peak reaches the
+/// top of the rect
+fn draw_graph(
+    pps: &VecDeque<f32>,
+    rect: rend::Rect,
+    line: u32,
+    palette: &styling::Palette,
+    layer: &mut rend::QuadLayer,
+) {
+    let mut quads = layer.quads_mut();
+    if rect.width == 0 || rect.height <= line {
+        return;
+    }
+    let bottom = rect.y + rect.height - line;
+    quads.push(crate::draw::quad(
+        palette.e,
+        rect.x as i32,
+        bottom as i32,
+        rect.width,
+        line,
+    ));
+    let peak = pps.iter().copied().fold(1.0, f32::max);
+    let span = (rect.height - line) as f32;
+    let step = rect.width as f32 / SAMPLES as f32;
+    let mut last_y = None;
+    for (i, &pps) in pps.iter().enumerate() {
+        let x = rect.x + (i as f32 * step) as u32;
+        let width = (step.ceil() as u32).max(1);
+        let y = bottom - (pps / peak * span) as u32;
+        quads.push(crate::draw::quad(
+            palette.fg2,
+            x as i32,
+            y as i32,
+            width,
+            line,
+        ));
+        if let Some(last_y) = last_y {
+            let (top, bottom) = if last_y < y { (last_y, y) } else { (y, last_y) };
+            quads.push(crate::draw::quad(
+                palette.fg2,
+                x as i32,
+                top as i32,
+                line,
+                bottom - top + line,
+            ));
+        }
+        last_y = Some(y);
+    }
+}
@@ -1,15 +1,168 @@
+use std::fmt;
 use std::ops::Mul;
+use std::str::FromStr;
 
-use crate::game;
+use serde::{Deserialize, Serialize};
+
+use crate::{game, key};
+
+pub use self::layout::Layout;
+
+pub mod layout;
+pub mod srgb;
 
 #[derive(Debug)]
 pub struct Settings {
+    /// The palette is picked from, unless there's a theme file
+    pub theme: Theme,
+    /// The theme file the palette is loaded from, by name, see [`crate::themes`]
+    pub theme_file: Option<String>,
+    /// The names of the theme files found
+    pub themes: Vec<String>,
     pub palette: Palette,
     pub sizing: Sizing,
+    /// Smoothly animate the mino falling between rows
+    pub smooth_fall: bool,
+    /// Ease the mino & ghost into place after moves
+    pub smooth_move: bool,
+    /// MSAA sample count, one of 1, 2 or 4
+    pub sample_count: u32,
+    /// How frames are presented, falling back when unsupported
+    pub present_mode: wgpu::PresentMode,
+    /// The name of the skin blocks are drawn with, flat colours when `None`
+    pub skin: Option<&'static str>,
+    /// The corner radius of blocks in pixels, square when 0
+    pub block_radius: f32,
+    /// The strength of the top-lit bevel on blocks, from 0 to 1
+    pub block_bevel: f32,
+    /// Draw the ghost as an outline rather than a dimmed block
+    pub ghost_outline: bool,
+    /// How many minos of the next queue are shown, at most
+    /// [`layout::NEXT_COUNT`]
+    pub preview_count: u32,
+    /// Draw grid lines over the empty board
+    pub grid: bool,
+    /// Show the actions held, e.g. for streaming
+    pub input_display: bool,
+    /// The binds played with
+    pub key_preset: key::Preset,
+    /// Shade the board's background & the garbage meter top to bottom
+    pub gradients: bool,
+    /// Mark each piece's cells with a pattern of its own, so pieces are told
+    /// apart without colour
+    pub patterns: bool,
+    /// Draw at a fixed size, scaled to fit the window with bars either side,
+    /// see [`Sizing::target_size`]
+    pub letterbox: bool,
+    /// How the letterboxed scene is filtered when scaled
+    pub scale_filter: ScaleFilter,
+    /// How many times larger than the window the scene is drawn, from 1 to 2,
+    /// downsampled to smooth out thin edges
+    pub render_scale: f32,
+    /// Pure black & white backgrounds, fully saturated blocks & thick
+    /// borders, see [`Palette::high_contrast`]
+    pub high_contrast: bool,
+    /// Leave out animations, see [`Settings::animates`]
+    pub reduced_motion: bool,
+    /// Pause a game being played when the window's minimized or occluded
+    pub pause_when_hidden: bool,
+}
+
+/// How a scaled image is sampled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleFilter {
+    /// Blocky, every pixel kept sharp
+    Nearest,
+    /// Smooth, blending neighbouring pixels
+    #[default]
+    Linear,
+}
+
+impl ScaleFilter {
+    pub fn filter_mode(self) -> wgpu::FilterMode {
+        match self {
+            ScaleFilter::Nearest => wgpu::FilterMode::Nearest,
+            ScaleFilter::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+
+    /// The other filter
+    pub fn next(self) -> Self {
+        match self {
+            ScaleFilter::Nearest => ScaleFilter::Linear,
+            ScaleFilter::Linear => ScaleFilter::Nearest,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleFilter::Nearest => "nearest",
+            ScaleFilter::Linear => "linear",
+        }
+    }
+}
+
+/// Which palette is used
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Light or dark, following the system as it changes, the other themes
+    /// being pinned
+    #[default]
+    System,
+    Light,
+    Dark,
+    /// Block colours told apart with red-green colour blindness, also
+    /// suiting protanopia, over the system's light or dark
+    Deuteranopia,
+    /// Block colours told apart with blue-yellow colour blindness, over the
+    /// system's light or dark
+    Tritanopia,
+}
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::System if system_is_dark() => Palette::dark(),
+            Theme::System => Palette::light(),
+            Theme::Light => Palette::light(),
+            Theme::Dark => Palette::dark(),
+            Theme::Deuteranopia => Palette::deuteranopia(Theme::System.palette()),
+            Theme::Tritanopia => Palette::tritanopia(Theme::System.palette()),
+        }
+    }
+
+    /// The next theme, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Theme::System => Theme::Light,
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Deuteranopia,
+            Theme::Deuteranopia => Theme::Tritanopia,
+            Theme::Tritanopia => Theme::System,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Deuteranopia => "deuteranopia",
+            Theme::Tritanopia => "tritanopia",
+        }
+    }
+}
+
+/// Whether the system is in dark mode, light when it can't be told
+pub fn system_is_dark() -> bool {
+    dark_light::detect().is_ok_and(|mode| mode == dark_light::Mode::Dark)
 }
 
 // TODO: move to using textures for blocks
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Palette {
     pub fg: Colour,
     pub bg: Colour,
@@ -24,9 +177,22 @@ pub struct Palette {
     pub s: Colour,
     pub t: Colour,
     pub z: Colour,
+    /// grid lines
+    pub grid: Colour,
+    /// The bottom of the board's background with gradients on, `bg2` being
+    /// its top
+    pub board_bottom: Colour,
+    /// The ends of the garbage meter with gradients on
+    pub garbage_top: Colour,
+    pub garbage_bottom: Colour,
+    /// What the ghost's colour is blended towards, by this colour's alpha
+    pub ghost: Colour,
+    /// Blended over the edges of blocks, by this colour's alpha
+    pub block_border: Colour,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+/// An sRGB colour, its channels from 0 to 1 & alpha being linear
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Colour {
     pub r: f32,
     pub g: f32,
@@ -34,58 +200,370 @@ pub struct Colour {
     pub a: f32,
 }
 
+/// Scales the light of the colour, in linear space
 impl Mul<f32> for Colour {
     type Output = Self;
 
     fn mul(self, rhs: f32) -> Self::Output {
+        let scale = |c: f32| srgb::from_linear(srgb::to_linear(c) * rhs);
         Self {
-            r: self.r * rhs,
-            g: self.g * rhs,
-            b: self.b * rhs,
+            r: scale(self.r),
+            g: scale(self.g),
+            b: scale(self.b),
             a: self.a,
         }
     }
 }
 
+/// The colour is decoded to linear, the same as quad colours are, so a clear
+/// matches a quad of the same colour.
+impl From<Colour> for wgpu::Color {
+    fn from(colour: Colour) -> Self {
+        let [r, g, b, a] = colour.rgba().map(f64::from);
+        wgpu::Color { r, g, b, a }
+    }
+}
+
+/// Why a hex colour couldn't be parsed, see [`Colour::from_hex`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseColourError {
+    /// Not 3, 4, 6 or 8 digits
+    Length(usize),
+    /// Not a hex digit
+    Digit(char),
+}
+
+impl fmt::Display for ParseColourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColourError::Length(len) => {
+                write!(f, "expected 3, 4, 6 or 8 hex digits, found {len}")
+            }
+            ParseColourError::Digit(c) => write!(f, "{c:?} isn't a hex digit"),
+        }
+    }
+}
+
+impl std::error::Error for ParseColourError {}
+
+impl FromStr for Colour {
+    type Err = ParseColourError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+/// Colours are written as hex strings, see [`Colour::from_hex`]
+impl Serialize for Colour {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Colour {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Self::from_hex(&hex).map_err(serde::de::Error::custom)
+    }
+}
+
+/// glyphon takes sRGB bytes, decoding them itself
+impl From<Colour> for glyphon::Color {
+    fn from(Colour { r, g, b, a }: Colour) -> Self {
+        let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        glyphon::Color::rgba(byte(r), byte(g), byte(b), byte(a))
+    }
+}
+
 impl Colour {
+    /// The linear channels, as the GPU blends them
     pub fn rgb(self) -> [f32; 3] {
-        [self.r, self.g, self.b]
+        [self.r, self.g, self.b].map(srgb::to_linear)
     }
+    /// The linear channels & alpha, see [`Colour::rgb`]
     pub fn rgba(self) -> [f32; 4] {
+        let [r, g, b] = self.rgb();
+        [r, g, b, self.a]
+    }
+    /// The sRGB channels & alpha, as they're stored
+    pub fn srgba(self) -> [f32; 4] {
         [self.r, self.g, self.b, self.a]
     }
+
+    /// The colour with its alpha replaced
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Blends towards white by `f`, from 0 to 1, keeping the alpha
+    pub fn lighten(self, f: f32) -> Self {
+        self.lerp(colours::PURE_WHITE.with_alpha(self.a), f)
+    }
+
+    /// Blends towards black by `f`, from 0 to 1, keeping the alpha
+    pub fn darken(self, f: f32) -> Self {
+        self * (1.0 - f)
+    }
+
+    /// Parses `#RRGGBB`, `#RRGGBBAA` or the short `#RGB` & `#RGBA` forms,
+    /// the `#` being optional
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColourError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(ParseColourError::Digit(c));
+        }
+        // every char is an ascii hex digit, so slicing by byte is safe
+        let channel = |i: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            // a short form digit is repeated, `f` meaning `ff`
+            let value = if width == 1 { value * 17 } else { value };
+            value as f32 / 255.0
+        };
+        let (width, channels) = match digits.len() {
+            3 | 4 => (1, digits.len()),
+            6 | 8 => (2, digits.len() / 2),
+            len => return Err(ParseColourError::Length(len)),
+        };
+        Ok(Self {
+            r: channel(0, width),
+            g: channel(1, width),
+            b: channel(2, width),
+            a: if channels == 4 {
+                channel(3, width)
+            } else {
+                1.0
+            },
+        })
+    }
+
+    /// Formats as `#RRGGBB`, or `#RRGGBBAA` when not opaque
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self
+            .srgba()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        if a == u8::MAX {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+
+    /// Stretches the channels to span 0 to 1, the same hue at its most
+    /// saturated, greys being left alone
+    pub fn saturate(self) -> Self {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        if max - min <= f32::EPSILON {
+            return self;
+        }
+        let stretch = |c: f32| (c - min) / (max - min);
+        Self {
+            r: stretch(self.r),
+            g: stretch(self.g),
+            b: stretch(self.b),
+            a: self.a,
+        }
+    }
+
+    /// Blends towards `other` in linear space, `t` going from 0 to 1, the
+    /// same as a gradient quad is blended
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let blend = |a: f32, b: f32| srgb::from_linear(mix(srgb::to_linear(a), srgb::to_linear(b)));
+        Self {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+}
+
+/// Theme files missing colours fall back to the light palette's
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::light()
+    }
 }
 
 impl Palette {
+    /// The ghost of a piece of `colour`, see [`Palette::ghost`]
+    pub fn ghost_colour(&self, colour: Colour) -> Colour {
+        colour.lerp(self.ghost.with_alpha(colour.a), self.ghost.a)
+    }
+
+    /// The colour of a block's piece, see [`game::Piece::colour`]
     pub fn colour_block(&self, block: Option<game::Block>) -> Colour {
-        match block {
-            Some(game::Block::I) => self.i,
-            Some(game::Block::L) => self.l,
-            Some(game::Block::J) => self.j,
-            Some(game::Block::O) => self.o,
-            Some(game::Block::S) => self.s,
-            Some(game::Block::T) => self.t,
-            Some(game::Block::Z) => self.z,
-            None => self.e,
-        }
+        let Some(block) = block else {
+            return self.e;
+        };
+        [self.i, self.j, self.l, self.o, self.s, self.t, self.z][block.colour()]
     }
 }
 
 #[derive(Debug)]
 pub struct Sizing {
-    pub game_x: u32,
-    pub game_y: u32,
+    /// Where everything is drawn, recomputed on resize
+    pub layout: Layout,
+    /// In physical pixels, fit to the window on resize
     pub block_size: u32,
+    /// The largest block size the board grows to, in logical pixels
+    pub max_block_size: u32,
+    /// The space between cells, in logical pixels
+    pub block_gap: u32,
+    /// The width of block borders in logical pixels, none when 0
+    pub border_width: u32,
+    /// The ratio of drawn to logical pixels, the window's scale factor times
+    /// the render scale
+    pub scale_factor: f64,
 }
 
+/// The block size everything else is scaled relative to
+pub const DEFAULT_BLOCK_SIZE: u32 = 30;
+/// The smallest block size the window can be shrunk to, in logical pixels
+pub const MIN_BLOCK_SIZE: u32 = 8;
+/// The largest block size, in logical pixels
+pub const MAX_BLOCK_SIZE: u32 = 96;
+/// The share of the window's height taken by the board
+const BOARD_HEIGHT_SHARE: f32 = 0.8;
+/// The blocks of horizontal room beside the board, for the held & next minos
+const SIDE_PANEL_BLOCKS: f32 = 4.5 + 5.0;
+
 impl Sizing {
-    pub fn resize(&mut self, width: u32, height: u32) {
-        let n_wide = game::BOARD_WIDTH as u32;
-        let n_tall = game::BOARD_VISIBLE_HEIGHT as u32;
-        let board_width = self.block_size * n_wide;
-        let board_height = self.block_size * n_tall;
-        self.game_x = (width / 2).saturating_sub(board_width / 2);
-        self.game_y = (height / 2).saturating_sub(board_height / 2);
+    /// Fits the board to the window, scaling the block size with it
+    ///
+    /// `width` & `height` are in physical pixels.
+    pub fn resize(&mut self, dims: game::BoardDims, width: u32, height: u32) {
+        let n_wide = dims.width as u32;
+        let n_tall = dims.visible_height as u32;
+        let gap = self.gap() as f32;
+        let by_height = (height as f32 * BOARD_HEIGHT_SHARE + gap) / n_tall as f32 - gap;
+        let by_width = width as f32 / (n_wide as f32 + SIDE_PANEL_BLOCKS) - gap;
+        let max = self.physical(self.max_block_size.clamp(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE));
+        // whole pixels, so borders stay sharp, shrinking past the minimum
+        // rather than overflowing a window that's too small
+        self.block_size = (by_height.min(by_width).max(0.0) as u32).clamp(1, max.max(1));
+        self.layout = Layout::new(self, dims, width, height);
+    }
+
+    /// The smallest window that fits the board at [`MIN_BLOCK_SIZE`]
+    pub fn min_window_size(&self, dims: game::BoardDims) -> winit::dpi::LogicalSize<u32> {
+        let n_wide = dims.width as f32 + SIDE_PANEL_BLOCKS;
+        let n_tall = dims.visible_height as f32 / BOARD_HEIGHT_SHARE;
+        let pitch = (MIN_BLOCK_SIZE + self.block_gap) as f32;
+        winit::dpi::LogicalSize::new((n_wide * pitch) as u32, (n_tall * pitch) as u32)
+    }
+
+    /// The size the board fits at [`DEFAULT_BLOCK_SIZE`], letterboxed scenes
+    /// being drawn at it
+    pub fn target_size(&self, dims: game::BoardDims) -> winit::dpi::PhysicalSize<u32> {
+        let n_wide = dims.width as f32 + SIDE_PANEL_BLOCKS;
+        let n_tall = dims.visible_height as f32 / BOARD_HEIGHT_SHARE;
+        let pitch = (self.physical(DEFAULT_BLOCK_SIZE) + self.gap()) as f32;
+        winit::dpi::PhysicalSize::new(
+            (n_wide * pitch).ceil() as u32,
+            (n_tall * pitch).ceil() as u32,
+        )
+    }
+
+    /// Converts logical pixels to whole physical pixels
+    pub fn physical(&self, logical: u32) -> u32 {
+        (logical as f64 * self.scale_factor).round() as u32
+    }
+
+    /// The space between cells, in physical pixels
+    pub fn gap(&self) -> u32 {
+        self.physical(self.block_gap)
+    }
+
+    /// The distance from one cell to the next
+    pub fn pitch(&self) -> u32 {
+        self.block_size + self.gap()
+    }
+
+    /// The length of a run of cells, without a trailing gap
+    pub fn span(&self, blocks: u32) -> u32 {
+        (blocks * self.pitch()).saturating_sub(self.gap())
+    }
+
+    /// How much larger than the default everything is drawn
+    pub fn scale(&self) -> f32 {
+        self.block_size as f32 / DEFAULT_BLOCK_SIZE as f32
+    }
+}
+
+impl Settings {
+    /// The name of the theme in use
+    pub fn theme_name(&self) -> &str {
+        self.theme_file
+            .as_deref()
+            .unwrap_or_else(|| self.theme.name())
+    }
+
+    /// Moves on to the next theme, the built-in ones then the theme files,
+    /// wrapping around
+    pub fn next_theme(&mut self) {
+        let next_file = match &self.theme_file {
+            None if self.theme.next() != Theme::System => {
+                self.theme = self.theme.next();
+                return;
+            }
+            None => self.themes.first(),
+            Some(name) => {
+                let at = self.themes.iter().position(|theme| theme == name);
+                at.and_then(|at| self.themes.get(at + 1))
+            }
+        };
+        self.theme_file = next_file.cloned();
+        if self.theme_file.is_none() {
+            self.theme = Theme::System;
+        }
+    }
+
+    /// Uses `palette`, made high contrast when that's on
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = if self.high_contrast {
+            Palette::high_contrast(palette)
+        } else {
+            palette
+        };
+    }
+
+    /// Whether anything moves that doesn't need to, which reduced motion
+    /// turns off
+    ///
+    /// Every animation that's only for show checks this, the game playing
+    /// the same either way.
+    pub fn animates(&self) -> bool {
+        !self.reduced_motion
+    }
+
+    /// The width of block borders in physical pixels, at least 2 logical
+    /// pixels in high contrast
+    ///
+    /// Borders are at most half a block wide, where they meet in the middle.
+    pub fn block_border(&self) -> f32 {
+        let logical = if self.high_contrast {
+            self.sizing.border_width.max(2)
+        } else {
+            self.sizing.border_width
+        };
+        let width = self
+            .sizing
+            .physical(logical)
+            .min(self.sizing.block_size / 2);
+        width as f32
+    }
+
+    /// How the target is filtered onto the window, always smoothly when
+    /// it's drawn larger to be downsampled
+    pub fn target_filter(&self) -> wgpu::FilterMode {
+        if self.render_scale > 1.0 {
+            wgpu::FilterMode::Linear
+        } else {
+            self.scale_filter.filter_mode()
+        }
     }
 }
 
@@ -93,14 +571,37 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             sizing: Sizing {
-                game_x: 0,
-                game_y: 0,
-                block_size: 30,
+                layout: Layout::default(),
+                block_size: DEFAULT_BLOCK_SIZE,
+                max_block_size: MAX_BLOCK_SIZE,
+                block_gap: 0,
+                border_width: 1,
+                scale_factor: 1.0,
             },
-            palette: dark_light::detect()
-                .is_ok_and(|m| m == dark_light::Mode::Dark)
-                .then(Palette::dark)
-                .unwrap_or_else(Palette::light),
+            theme: Theme::System,
+            theme_file: None,
+            themes: Vec::new(),
+            palette: Theme::System.palette(),
+            smooth_fall: false,
+            smooth_move: false,
+            sample_count: 1,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            skin: None,
+            block_radius: 0.0,
+            block_bevel: 0.0,
+            ghost_outline: true,
+            preview_count: layout::NEXT_COUNT,
+            grid: false,
+            input_display: false,
+            key_preset: key::Preset::default(),
+            gradients: false,
+            patterns: false,
+            letterbox: false,
+            scale_filter: ScaleFilter::default(),
+            render_scale: 1.0,
+            high_contrast: false,
+            reduced_motion: false,
+            pause_when_hidden: true,
         }
     }
 }
@@ -120,6 +621,12 @@ impl Palette {
             s: colours::GREEN,
             t: colours::PURPLE,
             z: colours::RED,
+            grid: colours::FAINT_BLACK,
+            board_bottom: colours::DIM_WHITE,
+            garbage_top: colours::YELLOW,
+            garbage_bottom: colours::RED,
+            ghost: colours::GHOST,
+            block_border: colours::DARK_BORDER,
         }
     }
     pub fn dark() -> Self {
@@ -129,9 +636,72 @@ impl Palette {
             bg: palette.fg,
             fg2: palette.bg2,
             bg2: palette.fg2,
+            grid: colours::FAINT_WHITE,
+            board_bottom: colours::DEEP_BLACK,
+            // dark borders vanish into the background
+            block_border: colours::LIGHT_BORDER,
             ..palette
         }
     }
+    /// The Okabe-Ito colours, over `base`
+    pub fn deuteranopia(base: Palette) -> Self {
+        Palette {
+            i: colours::SKY_BLUE,
+            j: colours::DEEP_BLUE,
+            l: colours::AMBER,
+            o: colours::PALE_YELLOW,
+            s: colours::BLUISH_GREEN,
+            t: colours::REDDISH_PURPLE,
+            z: colours::VERMILLION,
+            garbage_top: colours::PALE_YELLOW,
+            garbage_bottom: colours::VERMILLION,
+            ..base
+        }
+    }
+    /// `base` on a pure black or white background, whichever it's closer
+    /// to, its colours fully saturated
+    pub fn high_contrast(base: Palette) -> Self {
+        let (fg, bg) = if base.bg.rgb().iter().sum::<f32>() < 1.5 {
+            (colours::PURE_WHITE, colours::PURE_BLACK)
+        } else {
+            (colours::PURE_BLACK, colours::PURE_WHITE)
+        };
+        Palette {
+            fg,
+            bg,
+            fg2: fg,
+            bg2: bg,
+            e: colours::GREY,
+            i: base.i.saturate(),
+            j: base.j.saturate(),
+            l: base.l.saturate(),
+            o: base.o.saturate(),
+            s: base.s.saturate(),
+            t: base.t.saturate(),
+            z: base.z.saturate(),
+            grid: fg.with_alpha(0.3),
+            board_bottom: bg,
+            garbage_top: base.garbage_top.saturate(),
+            garbage_bottom: base.garbage_bottom.saturate(),
+            ghost: base.ghost,
+            block_border: fg,
+        }
+    }
+    /// Reds, teals & lightness rather than blue against yellow, over `base`
+    pub fn tritanopia(base: Palette) -> Self {
+        Palette {
+            i: colours::LIGHT_CYAN,
+            j: colours::DARK_TEAL,
+            l: colours::PINK,
+            o: colours::CRIMSON,
+            s: colours::SLATE,
+            t: colours::PLUM,
+            z: colours::RED,
+            garbage_top: colours::PINK,
+            garbage_bottom: colours::CRIMSON,
+            ..base
+        }
+    }
 }
 
 pub mod colours {
@@ -159,6 +729,18 @@ pub mod colours {
         BLACK(30.0, 30.0, 30.0, 1.0),
         SOFT_WHITE(200.0, 200.0, 200.0, 1.0),
         OFF_BLACK(50.0, 50.0, 50.0, 1.0),
+        PURE_WHITE(255.0, 255.0, 255.0, 1.0),
+        PURE_BLACK(0.0, 0.0, 0.0, 1.0),
+        GREY(128.0, 128.0, 128.0, 1.0),
+        FAINT_WHITE(230.0, 230.0, 230.0, 0.15),
+        FAINT_BLACK(30.0, 30.0, 30.0, 0.15),
+        DIM_WHITE(180.0, 180.0, 180.0, 1.0),
+        DEEP_BLACK(38.0, 38.0, 38.0, 1.0),
+        // blended over, by its alpha
+        GHOST(0.0, 0.0, 0.0, 0.7),
+        // blended over block edges, by their alpha
+        DARK_BORDER(0.0, 0.0, 0.0, 0.5),
+        LIGHT_BORDER(255.0, 255.0, 255.0, 0.4),
         // block colours
         SILVER(160.0, 160.0, 160.0, 1.0),
         CYAN(0.0, 255.0, 255.0, 1.0),
@@ -168,5 +750,120 @@ pub mod colours {
         GREEN(0.0, 255.0, 0.0, 1.0),
         PURPLE(160.0, 32.0, 240.0, 1.0),
         RED(255.0, 0.0, 0.0, 1.0),
+        // deuteranopia block colours, from Okabe & Ito
+        SKY_BLUE(86.0, 180.0, 233.0, 1.0),
+        DEEP_BLUE(0.0, 114.0, 178.0, 1.0),
+        AMBER(230.0, 159.0, 0.0, 1.0),
+        PALE_YELLOW(240.0, 228.0, 66.0, 1.0),
+        BLUISH_GREEN(0.0, 158.0, 115.0, 1.0),
+        REDDISH_PURPLE(204.0, 121.0, 167.0, 1.0),
+        VERMILLION(213.0, 94.0, 0.0, 1.0),
+        // tritanopia block colours
+        LIGHT_CYAN(120.0, 220.0, 230.0, 1.0),
+        DARK_TEAL(0.0, 95.0, 110.0, 1.0),
+        PINK(245.0, 140.0, 160.0, 1.0),
+        CRIMSON(170.0, 0.0, 40.0, 1.0),
+        SLATE(130.0, 130.0, 140.0, 1.0),
+        PLUM(110.0, 40.0, 100.0, 1.0),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moving an 800x600 window between monitors, as `State::rescale` does,
+    /// the board's 24 logical pixels a block at each scale
+    #[test]
+    fn rescaling_follows_the_scale_factor() {
+        let mut sizing = Settings::default().sizing;
+        let dims = game::BoardDims::default();
+        for scale in [1.0, 2.0, 1.25] {
+            sizing.scale_factor = scale;
+            let (width, height) = ((800.0 * scale) as u32, (600.0 * scale) as u32);
+            sizing.resize(dims, width, height);
+            assert_eq!(sizing.block_size, (24.0 * scale) as u32, "at {scale}");
+            assert_eq!(
+                sizing.layout.board.height,
+                sizing.block_size * 20,
+                "at {scale}"
+            );
+            // text is shaped at the same size relative to the blocks
+            let metrics = crate::TEXT_METRICS.scale(sizing.scale());
+            let expected = 24.0 * 0.8 * scale as f32;
+            assert!((metrics.font_size - expected).abs() < 1e-3, "at {scale}");
+            assert!(
+                (metrics.line_height - expected * 1.5).abs() < 1e-3,
+                "at {scale}"
+            );
+        }
+    }
+
+    fn close(a: Colour, b: Colour) -> bool {
+        let [a, b] = [a, b].map(Colour::srgba);
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+
+    #[test]
+    fn hex_forms() {
+        let orange = Colour::from_hex("#ff8800").unwrap();
+        assert_eq!(orange.srgba(), [1.0, 136.0 / 255.0, 0.0, 1.0]);
+        // a short form repeats each digit
+        assert_eq!(Colour::from_hex("#f80"), Ok(orange));
+        assert_eq!(Colour::from_hex("#F80"), Ok(orange));
+        // the `#` is optional
+        assert_eq!(Colour::from_hex("ff8800"), Ok(orange));
+        assert_eq!(Colour::from_hex("f80"), Ok(orange));
+        assert_eq!(Colour::from_hex("#f808").unwrap().a, 136.0 / 255.0);
+        assert_eq!(Colour::from_hex("#ff880080").unwrap().a, 128.0 / 255.0);
+    }
+
+    #[test]
+    fn hex_errors() {
+        assert_eq!(Colour::from_hex("#ff880"), Err(ParseColourError::Length(5)));
+        assert_eq!(Colour::from_hex(""), Err(ParseColourError::Length(0)));
+        assert_eq!(Colour::from_hex("#"), Err(ParseColourError::Length(0)));
+        assert_eq!(
+            Colour::from_hex("#ff88zz"),
+            Err(ParseColourError::Digit('z'))
+        );
+        assert_eq!(
+            Colour::from_hex("##ff8800"),
+            Err(ParseColourError::Digit('#'))
+        );
+        // a sign parses as a number, but isn't a digit
+        assert_eq!(Colour::from_hex("+f8"), Err(ParseColourError::Digit('+')));
+        assert_eq!(
+            Colour::from_hex("#ff8 00"),
+            Err(ParseColourError::Digit(' '))
+        );
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        for hex in ["#000000", "#ffffff", "#ff8800", "#12345678"] {
+            assert_eq!(Colour::from_hex(hex).unwrap().to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn lerp_ends() {
+        let from = Colour::from_hex("#ff880080").unwrap();
+        let to = Colour::from_hex("#2040c0").unwrap();
+        assert!(close(from.lerp(to, 0.0), from));
+        assert!(close(from.lerp(to, 1.0), to));
+    }
+
+    #[test]
+    fn lerp_blends_linear_light() {
+        let black = Colour::from_hex("#00000000").unwrap();
+        let white = Colour::from_hex("#ffffff").unwrap();
+        let mid = black.lerp(white, 0.5);
+        // half the light is brighter than half the sRGB value
+        assert!((mid.r - srgb::from_linear(0.5)).abs() < 1e-5);
+        assert_eq!(mid.r, mid.g);
+        assert_eq!(mid.r, mid.b);
+        // alpha is already linear
+        assert_eq!(mid.a, 0.5);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::game;
+use crate::rend::Rect;
+
+use super::Sizing;
+
+/// The minos shown in the next queue
+pub const NEXT_COUNT: u32 = 5;
+
+/// Where everything on screen goes, computed once per resize
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// The visible board
+    pub board: Rect,
+    /// The held mino, left of the board or above it when there's no room
+    pub hold: Rect,
+    /// The whole next queue, right of the board & clipped to its height
+    pub next_queue: Rect,
+    /// The distance from one mino of the next queue to the next
+    pub next_step: u32,
+    pub score_text: Rect,
+    /// A puzzle's objective & progress, below the score
+    pub objective: Rect,
+    /// A thin strip along the left edge of the board
+    pub garbage_meter: Rect,
+    /// The input display, at the bottom of the next queue's column
+    pub inputs: Rect,
+    /// The entries of the menus, over the board
+    pub menu: Rect,
+    /// The results of a finished game, below the menu
+    pub summary: Rect,
+}
+
+impl Layout {
+    pub fn new(sizing: &Sizing, dims: game::BoardDims, width: u32, height: u32) -> Self {
+        let pitch = sizing.pitch();
+        let board_width = sizing.span(dims.width as u32);
+        let board_height = sizing.span(dims.visible_height as u32);
+        let board = Rect {
+            x: (width / 2).saturating_sub(board_width / 2),
+            y: (height / 2).saturating_sub(board_height / 2),
+            width: board_width,
+            height: board_height,
+        };
+
+        // 4.5 * pitch left of the board, or above it when there's no room
+        let (hold_x, hold_y) = match board.x.checked_sub(pitch * 4 + pitch / 2) {
+            Some(hold_x) => (hold_x, board.y),
+            None => (board.x, board.y.saturating_sub(pitch * 2 + pitch / 2)),
+        };
+        let hold = Rect {
+            x: hold_x,
+            y: hold_y,
+            width: sizing.span(4),
+            height: sizing.span(2),
+        };
+        let next_queue = Rect {
+            x: board.x + board.width + pitch / 2,
+            y: board.y,
+            width: sizing.span(4),
+            height: board.height,
+        };
+        let left_column = board.height.saturating_sub(pitch * 3);
+        let score_text = Rect {
+            x: hold.x,
+            y: hold.y + pitch * 3,
+            width: hold.width,
+            height: left_column / 2,
+        };
+        let objective = Rect {
+            y: score_text.y + score_text.height,
+            height: left_column - left_column / 2,
+            ..score_text
+        };
+        let meter_width = (pitch / 3).max(1);
+        let garbage_meter = Rect {
+            x: board.x.saturating_sub(pitch / 2),
+            y: board.y,
+            width: meter_width,
+            height: board.height,
+        };
+        let inputs = Rect {
+            x: next_queue.x,
+            y: (board.y + board.height).saturating_sub(sizing.span(2)),
+            width: sizing.span(4),
+            height: sizing.span(2),
+        };
+        let menu = Rect {
+            x: board.x + pitch / 2,
+            y: board.y + pitch * 2,
+            width: board.width.saturating_sub(pitch),
+            height: board.height.saturating_sub(pitch * 2),
+        };
+        let summary = Rect {
+            y: board.y + board.height / 2,
+            height: board.height - board.height / 2,
+            ..menu
+        };
+        Self {
+            board,
+            hold,
+            next_queue,
+            // 2.5 * pitch
+            next_step: pitch * 2 + pitch / 2,
+            score_text,
+            objective,
+            garbage_meter,
+            inputs,
+            menu,
+            summary,
+        }
+    }
+
+    /// The `i`th mino of the next queue
+    pub fn next(&self, i: u32) -> Rect {
+        Rect {
+            y: self.next_queue.y + i * self.next_step,
+            height: self.next_step,
+            ..self.next_queue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default sizing fit to `dims` in a `width` x `height` window
+    fn sizing(dims: game::BoardDims, width: u32, height: u32) -> Sizing {
+        let mut sizing = crate::styling::Settings::default().sizing;
+        sizing.resize(dims, width, height);
+        sizing
+    }
+
+    fn overlaps(a: Rect, b: Rect) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    /// A 24x10 board & a 20x8 one, 4 rows of each hidden above the top
+    #[test]
+    fn boards_fit_whatever_their_size() {
+        for (rows, width) in [(24, 10), (20, 8)] {
+            let dims = game::BoardDims {
+                width,
+                visible_height: rows - game::VISIBLE_START,
+            };
+            for (window_width, window_height) in [(1280, 720), (500, 500), (400, 900)] {
+                let sizing = sizing(dims, window_width, window_height);
+                let layout = sizing.layout;
+                let case = format!("{rows}x{width} in {window_width}x{window_height}");
+                let rects = [layout.board, layout.hold, layout.next_queue];
+                for rect in rects {
+                    assert!(!rect.is_empty(), "{case}: {rect:?} is empty");
+                    let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+                    assert!(
+                        right <= window_width && bottom <= window_height,
+                        "{case}: {rect:?} is off the window"
+                    );
+                }
+                for (i, &a) in rects.iter().enumerate() {
+                    for &b in &rects[i + 1..] {
+                        assert!(!overlaps(a, b), "{case}: {a:?} overlaps {b:?}");
+                    }
+                }
+                // the board's centred, its rows & columns fitting exactly
+                let board = layout.board;
+                assert_eq!(board.width, sizing.span(width as u32), "{case}");
+                assert_eq!(
+                    board.height,
+                    sizing.span(dims.visible_height as u32),
+                    "{case}"
+                );
+                let right = window_width - board.x - board.width;
+                let bottom = window_height - board.y - board.height;
+                assert!(
+                    board.x.abs_diff(right) <= 1,
+                    "{case}: {board:?} isn't centred"
+                );
+                assert!(
+                    board.y.abs_diff(bottom) <= 1,
+                    "{case}: {board:?} isn't centred"
+                );
+            }
+        }
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// 20px blocks, the board's 200x400 in the middle
+    #[test]
+    fn golden_500x500() {
+        let layout = sizing(game::BoardDims::default(), 500, 500).layout;
+        assert_eq!(
+            layout,
+            Layout {
+                board: rect(150, 50, 200, 400),
+                hold: rect(60, 50, 80, 40),
+                next_queue: rect(360, 50, 80, 400),
+                next_step: 50,
+                score_text: rect(60, 110, 80, 170),
+                objective: rect(60, 280, 80, 170),
+                garbage_meter: rect(140, 50, 6, 400),
+                inputs: rect(360, 410, 80, 40),
+                menu: rect(160, 90, 180, 360),
+                summary: rect(160, 250, 180, 200),
+            }
+        );
+        assert_eq!(layout.next(2), rect(360, 150, 80, 50));
+    }
+
+    /// 28px blocks, the board taking up to 80% of the height
+    #[test]
+    fn golden_1280x720() {
+        let layout = sizing(game::BoardDims::default(), 1280, 720).layout;
+        assert_eq!(
+            layout,
+            Layout {
+                board: rect(500, 80, 280, 560),
+                hold: rect(374, 80, 112, 56),
+                next_queue: rect(794, 80, 112, 560),
+                next_step: 70,
+                score_text: rect(374, 164, 112, 238),
+                objective: rect(374, 402, 112, 238),
+                garbage_meter: rect(486, 80, 9, 560),
+                inputs: rect(794, 584, 112, 56),
+                menu: rect(514, 136, 252, 504),
+                summary: rect(514, 360, 252, 280),
+            }
+        );
+        assert_eq!(layout.next(4), rect(794, 360, 112, 70));
+    }
+}
@@ -0,0 +1,61 @@
+// NOTE: palette colours are sRGB, as picked in an image editor, while blending
+// & the surface's encoding work on linear light, so colours are decoded on
+// their way to the GPU. Instances keep sRGB bytes, as 8 bits are too few for
+// dark linear values, & are decoded by the shaders instead.
+
+/// Decodes an sRGB channel, from 0 to 1, to linear light
+pub fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a channel of linear light, from 0 to 1, as sRGB
+pub fn from_linear(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        for byte in 0..=u8::MAX {
+            let c = byte as f32 / 255.0;
+            let back = (from_linear(to_linear(c)) * 255.0).round();
+            assert_eq!(back as u8, byte, "{byte} didn't round trip");
+        }
+    }
+
+    #[test]
+    fn linear_round_trips() {
+        for i in 0..=1000 {
+            let c = i as f32 / 1000.0;
+            assert!((to_linear(from_linear(c)) - c).abs() < 1e-5, "{c}");
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(to_linear(0.0), 0.0);
+        assert!((to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((from_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((to_linear(0.5) - 0.214_041).abs() < 1e-5);
+        // either side of the linear segment's end
+        assert!((to_linear(0.04045) - 0.003_130_8).abs() < 1e-6);
+        assert!((to_linear(0.04046) - 0.003_131_6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn decoding_keeps_order() {
+        let linear: Vec<f32> = (0..=u8::MAX).map(|b| to_linear(b as f32 / 255.0)).collect();
+        assert!(linear.windows(2).all(|w| w[0] < w[1]));
+    }
+}
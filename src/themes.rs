@@ -0,0 +1,75 @@
+// NOTE: theme files are TOML in the config dir's `themes/`, a hex colour for
+// each field of `Palette`, any left out being the light palette's. The
+// built-in themes are always there to fall back on. The directory, & the
+// system's dark mode for the system theme, are polled for changes on a thread
+// of their own, as the game thread is paused in the menus, where themes are
+// picked.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::styling;
+use crate::time::SystemTime;
+
+/// How often the theme files & the system's mode are checked for changes
+const POLL: Duration = Duration::from_secs(1);
+
+pub fn dir() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("wgputris").join("themes"))
+}
+
+/// The names of the theme files, in order
+pub fn list() -> Vec<String> {
+    let Some(entries) = dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Loads the palette of a theme file by name
+pub fn load(name: &str) -> anyhow::Result<styling::Palette> {
+    let path = dir()
+        .context("no config directory")?
+        .join(name)
+        .with_extension("toml");
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("invalid theme {}", path.display()))
+}
+
+/// The latest change to the theme files, the directory's own time catching
+/// files being added or removed
+fn modified() -> Option<SystemTime> {
+    let dir = dir()?;
+    let files = std::fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok());
+    files.chain(dir.metadata().ok()?.modified().ok()).max()
+}
+
+/// Calls `changed` whenever the theme files change or the system switches
+/// between light & dark, until it returns false
+pub fn watch(mut changed: impl FnMut() -> bool + Send + 'static) {
+    let state = || (modified(), styling::system_is_dark());
+    std::thread::spawn(move || {
+        let mut last = state();
+        loop {
+            std::thread::sleep(POLL);
+            let state = state();
+            if state != last {
+                last = state;
+                if !changed() {
+                    return;
+                }
+            }
+        }
+    });
+}
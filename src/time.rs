@@ -1,13 +1,38 @@
+use std::fmt;
 use std::ops::ControlFlow;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-pub const TICK_RATE: usize = 120;
-pub static TICK_DURATION: Duration = Duration::from_nanos(8333333);
+// browsers have no clock of std's, so the web's is used there
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_TICK_RATE: usize = 120;
+/// The default limit of ticks & renders caught up on in a single iteration
+pub const DEFAULT_MAX_CATCH_UP: u32 = 10;
+/// How long a paused run loop sleeps before checking whether it has been
+/// resumed, unless it's woken first, see [`run`]
+pub const PAUSED_POLL: Duration = Duration::from_millis(250);
+/// How long before a deadline sleeping stops and spinning begins
+#[cfg(windows)]
+pub const SPIN_MARGIN: Duration = Duration::from_millis(2);
+/// How long before a deadline sleeping stops and spinning begins
+#[cfg(not(windows))]
+pub const SPIN_MARGIN: Duration = Duration::from_millis(1);
 
 #[derive(Debug, Clone, Copy)]
 pub struct TimeAction {
     pub render: bool,
     pub ticks: u32,
+    /// How far between the last tick and the next this is, from 0 to 1
+    pub alpha: f32,
+    /// Ticks discarded after a stall, beyond the catch up limit
+    pub dropped_ticks: u32,
+    /// Renders discarded after a stall, beyond the catch up limit
+    pub dropped_renders: u32,
     pub elapsed: Duration,
     pub sleep: Instant,
     pub now: Instant,
@@ -18,6 +43,9 @@ impl TimeAction {
         Self {
             render,
             ticks,
+            alpha: 0.0,
+            dropped_ticks: 0,
+            dropped_renders: 0,
             elapsed,
             sleep,
             now,
@@ -26,49 +54,101 @@ impl TimeAction {
 }
 
 pub struct Timer {
+    tick_rate: usize,
+    tick_duration: Duration,
     render_rate: usize,
     render_duration: Duration,
     elapsed: Duration,
     now: Instant,
     start: Instant,
+    /// When the render rate was last set, renders being counted from then
+    render_start: Instant,
     next_tick: Instant,
     next_render: Instant,
     ticks: u32,
     renders: u32,
+    dropped_ticks: u32,
+    dropped_renders: u32,
+    max_catch_up: u32,
+    paused: Option<Instant>,
+    oversleep: Oversleep,
     tick_calls: u32,
     total_sleep_time: Duration,
 }
 
+/// How late the run loop woke up from its sleeps
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Oversleep {
+    pub min: Duration,
+    pub max: Duration,
+    pub total: Duration,
+    pub count: u32,
+}
+
 impl Timer {
-    pub fn new(render_rate: usize) -> Self {
+    pub fn new(tick_rate: usize, render_rate: usize) -> Self {
         let now = Instant::now();
-        let render_duration = Duration::from_secs_f64(1.0 / render_rate as f64);
+        let tick_duration = rate_duration(tick_rate);
+        let render_duration = rate_duration(render_rate);
         Self {
+            tick_duration,
+            tick_rate,
             render_duration,
             render_rate,
             elapsed: Duration::default(),
             now,
             start: now,
-            next_tick: now + TICK_DURATION,
+            render_start: now,
+            next_tick: now + tick_duration,
             next_render: now + render_duration,
             ticks: 0,
             renders: 0,
+            dropped_ticks: 0,
+            dropped_renders: 0,
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+            paused: None,
+            oversleep: Oversleep::default(),
             tick_calls: 0,
             total_sleep_time: Duration::default(),
         }
     }
 
     pub fn tick(&mut self) -> TimeAction {
-        let now = Instant::now();
+        self.tick_at(Instant::now())
+    }
+
+    fn tick_at(&mut self, now: Instant) -> TimeAction {
         let elapsed = now - self.now;
         self.elapsed += elapsed;
         self.now = now;
 
         // TODO: move sleep to after checking ticks & render
-        let (render, ticks) = self.tick_count(now);
+        let (renders, ticks) = self.tick_count(now);
+
+        // after a stall, drop the backlog rather than grinding through it
+        let dropped_ticks = ticks.saturating_sub(self.max_catch_up);
+        let dropped_renders = renders.saturating_sub(self.max_catch_up);
+        let ticks = ticks - dropped_ticks;
+        let render = renders != 0;
+        if dropped_ticks != 0 {
+            self.next_tick = now + self.tick_duration;
+            self.dropped_ticks += dropped_ticks;
+        }
+        if dropped_renders != 0 {
+            self.next_render = now + self.render_duration;
+            self.dropped_renders += dropped_renders;
+        }
+
         let sleep = self.next_tick.min(self.next_render);
+        let last_tick = self
+            .next_tick
+            .checked_sub(self.tick_duration)
+            .unwrap_or(now);
+        let alpha = (now.saturating_duration_since(last_tick).as_secs_f32()
+            / self.tick_duration.as_secs_f32())
+        .clamp(0.0, 1.0);
         if ticks != 0 {
-            self.next_tick = diff_time(now, TICK_DURATION, self.next_tick);
+            self.next_tick = diff_time(now, self.tick_duration, self.next_tick);
             self.ticks += ticks;
         }
         if render {
@@ -82,24 +162,28 @@ impl Timer {
         TimeAction {
             render,
             ticks,
+            alpha,
+            dropped_ticks,
+            dropped_renders,
             elapsed,
             sleep,
             now,
         }
     }
 
-    fn tick_count(&mut self, now: Instant) -> (bool, u32) {
-        let mut render = false;
+    /// Counts the pending renders & ticks
+    fn tick_count(&mut self, now: Instant) -> (u32, u32) {
+        let mut renders = 0;
         let mut ticks = 0;
         while self.next_render < now {
-            render = true;
+            renders += 1;
             self.next_render += self.render_duration;
         }
         while self.next_tick < now {
             ticks += 1;
-            self.next_tick += TICK_DURATION;
+            self.next_tick += self.tick_duration;
         }
-        (render, ticks)
+        (renders, ticks)
     }
 
     pub fn sleep_until(&self) -> Instant {
@@ -114,25 +198,82 @@ impl Timer {
         self.render_rate
     }
 
-    /// utility method
+    /// Renders at a new rate from now on, leaving the ticks as they were
+    ///
+    /// The next render's a period of the new rate after the last one, or
+    /// straight away when that's already passed.
+    pub fn set_render_rate(&mut self, render_rate: usize) {
+        let render_rate = render_rate.max(1);
+        if render_rate == self.render_rate {
+            return;
+        }
+        let last_render = self
+            .next_render
+            .checked_sub(self.render_duration)
+            .unwrap_or(self.now);
+        self.render_rate = render_rate;
+        self.render_duration = rate_duration(render_rate);
+        self.next_render = (last_render + self.render_duration).max(self.now);
+        self.render_start = self.now;
+        self.renders = 0;
+        self.dropped_renders = 0;
+    }
+
     pub fn tick_rate(&self) -> usize {
-        TICK_RATE
+        self.tick_rate
+    }
+
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// Stops the timer, nothing is scheduled until [`Timer::resume`]
+    pub fn pause(&mut self) {
+        if self.paused.is_none() {
+            self.paused = Some(Instant::now());
+        }
+    }
+
+    /// Resumes the timer, shifting the schedule by the time spent paused
+    pub fn resume(&mut self) {
+        let Some(paused) = self.paused.take() else {
+            return;
+        };
+        let pause = paused.elapsed();
+        self.now += pause;
+        self.start += pause;
+        self.render_start += pause;
+        self.next_tick += pause;
+        self.next_render += pause;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Sets the limit of ticks & renders caught up on in a single iteration
+    pub fn set_max_catch_up(&mut self, max: u32) {
+        self.max_catch_up = max.max(1);
+    }
+
+    pub fn max_catch_up(&self) -> u32 {
+        self.max_catch_up
     }
 
     pub fn tick_drift(&self) -> i32 {
-        self.ticks as i32 - self.est_ticks() as i32
+        (self.ticks + self.dropped_ticks) as i32 - self.est_ticks() as i32
     }
 
     pub fn render_drift(&self) -> i32 {
-        self.renders as i32 - self.est_renders() as i32
+        (self.renders + self.dropped_renders) as i32 - self.est_renders() as i32
     }
 
     pub fn est_renders(&self) -> u32 {
-        (self.start.elapsed().as_nanos() / self.render_duration.as_nanos()) as u32
+        (self.render_start.elapsed().as_nanos() / self.render_duration.as_nanos()) as u32
     }
 
     pub fn est_ticks(&self) -> u32 {
-        (self.start.elapsed().as_nanos() / TICK_DURATION.as_nanos()) as u32
+        (self.start.elapsed().as_nanos() / self.tick_duration.as_nanos()) as u32
     }
 
     pub fn ticks(&self) -> u32 {
@@ -143,9 +284,106 @@ impl Timer {
         self.renders
     }
 
+    pub fn dropped_ticks(&self) -> u32 {
+        self.dropped_ticks
+    }
+
+    pub fn dropped_renders(&self) -> u32 {
+        self.dropped_renders
+    }
+
     pub fn elapsed(&self) -> Duration {
         self.elapsed
     }
+
+    pub fn oversleep(&self) -> Oversleep {
+        self.oversleep
+    }
+
+    pub fn record_oversleep(&mut self, oversleep: Duration) {
+        self.oversleep.record(oversleep);
+    }
+}
+
+impl Oversleep {
+    fn record(&mut self, oversleep: Duration) {
+        if self.count == 0 || oversleep < self.min {
+            self.min = oversleep;
+        }
+        self.max = self.max.max(oversleep);
+        self.total += oversleep;
+        self.count += 1;
+    }
+
+    pub fn avg(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Oversleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "Oversleep {:.2}/{:.2}/{:.2}ms",
+            ms(self.min),
+            ms(self.avg()),
+            ms(self.max)
+        )
+    }
+}
+
+/// Sleeps until shortly before `until` then spins the rest of the way
+///
+/// Returns how late the wake up was.
+pub fn precise_sleep(until: Instant) -> Duration {
+    let coarse = until.checked_sub(SPIN_MARGIN);
+    if let Some(coarse) = coarse {
+        let sleep = coarse.saturating_duration_since(Instant::now());
+        if !sleep.is_zero() {
+            std::thread::sleep(sleep);
+        }
+    }
+    while Instant::now() < until {
+        std::hint::spin_loop();
+    }
+    Instant::now() - until
+}
+
+/// Raises the system timer resolution while alive
+///
+/// Windows sleeps at a ~15ms granularity by default.
+struct TimerResolution;
+
+#[cfg(windows)]
+#[link(name = "winmm")]
+extern "system" {
+    fn timeBeginPeriod(period: u32) -> u32;
+    fn timeEndPeriod(period: u32) -> u32;
+}
+
+impl TimerResolution {
+    fn raise() -> Self {
+        #[cfg(windows)]
+        unsafe {
+            timeBeginPeriod(1);
+        }
+        Self
+    }
+}
+
+impl Drop for TimerResolution {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        unsafe {
+            timeEndPeriod(1);
+        }
+    }
+}
+
+/// The duration of a single period at the given rate per second
+pub fn rate_duration(rate: usize) -> Duration {
+    Duration::from_secs_f64(1.0 / rate as f64)
 }
 
 #[inline]
@@ -153,42 +391,129 @@ pub fn diff_time(now: Instant, diff: Duration, time: Instant) -> Instant {
     now + diff - diff.saturating_sub(time.saturating_duration_since(now))
 }
 
+/// Runs the timer on a new thread
+///
+/// While `paused` is set no ticks or renders happen, and the timer resumes
+/// as if no time had passed. The paused thread's parked, so unparking it
+/// once `paused` is cleared resumes it without waiting out [`PAUSED_POLL`].
+/// Renders follow `render_rate` as it changes, see [`Timer::set_render_rate`].
 pub fn run<B, C, Tick, Render>(
     tick: Tick,
     render: Render,
-    render_rate: usize,
+    timer: Timer,
+    paused: Arc<AtomicBool>,
+    render_rate: Arc<AtomicUsize>,
 ) -> std::thread::JoinHandle<B>
 where
     Tick: Fn(TimeAction, &Timer) -> ControlFlow<B, C> + Send + 'static,
     Render: Fn(TimeAction, &Timer) + Send + 'static,
     B: Send + 'static,
 {
-    let mut timer = Timer::new(render_rate);
+    std::thread::spawn(move || {
+        let _resolution = TimerResolution::raise();
+        run_loop(tick, render, timer, &paused, &render_rate)
+    })
+}
+
+/// Steps a timer from the event loop, for browsers, which have no threads to
+/// run it on, see [`run`]
+///
+/// Each step runs the ticks & render due, never sleeping.
+#[cfg(target_arch = "wasm32")]
+pub struct Stepper<B> {
+    tick: Box<dyn Fn(TimeAction, &Timer) -> ControlFlow<B>>,
+    render: Box<dyn Fn(TimeAction, &Timer)>,
+    timer: Timer,
+    paused: Arc<AtomicBool>,
+    render_rate: Arc<AtomicUsize>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<B> Stepper<B> {
+    pub fn new(
+        tick: impl Fn(TimeAction, &Timer) -> ControlFlow<B> + 'static,
+        render: impl Fn(TimeAction, &Timer) + 'static,
+        timer: Timer,
+        paused: Arc<AtomicBool>,
+        render_rate: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            tick: Box::new(tick),
+            render: Box::new(render),
+            timer,
+            paused,
+            render_rate,
+        }
+    }
+
+    /// Runs what's due, `Some` once the tick breaks
+    pub fn step(&mut self) -> Option<B> {
+        if self.paused.load(Ordering::Relaxed) {
+            self.timer.pause();
+            return None;
+        } else if self.timer.is_paused() {
+            self.timer.resume();
+        }
+        self.timer
+            .set_render_rate(self.render_rate.load(Ordering::Relaxed));
+        if self.timer.sleep_until() > Instant::now() {
+            return None;
+        }
+        let action = self.timer.tick();
+        if action.ticks != 0 {
+            if let ControlFlow::Break(b) = (self.tick)(action, &self.timer) {
+                return Some(b);
+            }
+        }
+        if action.render {
+            (self.render)(action, &self.timer);
+        }
+        None
+    }
+
+    /// When the next step's due, for the event loop to wake at, or when a
+    /// paused timer next checks whether it's resumed
+    pub fn next_step(&self) -> Instant {
+        if self.timer.is_paused() {
+            Instant::now() + PAUSED_POLL
+        } else {
+            self.timer.sleep_until()
+        }
+    }
+}
+
+fn run_loop<B, C, Tick, Render>(
+    tick: Tick,
+    render: Render,
+    mut timer: Timer,
+    paused: &AtomicBool,
+    render_rate: &AtomicUsize,
+) -> B
+where
+    Tick: Fn(TimeAction, &Timer) -> ControlFlow<B, C>,
+    Render: Fn(TimeAction, &Timer),
+{
     let mut sleep = timer.sleep_until();
-    std::thread::spawn(move || loop {
-        let now = Instant::now();
-        let sleep_dur = sleep.saturating_duration_since(now);
-        if !sleep_dur.is_zero() {
-            std::thread::sleep(sleep_dur);
+    loop {
+        if paused.load(Ordering::Relaxed) {
+            timer.pause();
+            std::thread::park_timeout(PAUSED_POLL);
+            continue;
+        } else if timer.is_paused() {
+            timer.resume();
+            sleep = timer.sleep_until();
+        }
+        let rate = render_rate.load(Ordering::Relaxed);
+        if rate != timer.render_rate() {
+            timer.set_render_rate(rate);
+            sleep = timer.sleep_until();
+        }
+        if sleep > Instant::now() {
+            let oversleep = precise_sleep(sleep);
+            timer.record_oversleep(oversleep);
         }
         let action = timer.tick();
 
-        // log::info!(
-        //     "sleep duration {}\n\
-        //      tick drift: {} diff {}\n\
-        //      render drift: {} diff {}\n\
-        //      elapsed: {}, actual: {}\n\
-        //      avg sleep {}",
-        //     now.elapsed().as_secs_f32(),
-        //     timer.ticks(),
-        //     timer.tick_drift(),
-        //     timer.renders(),
-        //     timer.render_drift(),
-        //     timer.elapsed().as_millis(),
-        //     timer.start().elapsed().as_millis(),
-        //     (timer.total_sleep_time / timer.tick_calls).as_secs_f32()
-        // );
-
         if action.ticks != 0 {
             match tick(action, &timer) {
                 ControlFlow::Continue(_) => (),
@@ -200,5 +525,46 @@ where
         }
 
         sleep = action.sleep;
-    })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stalls_drop_the_backlog() {
+        let mut timer = Timer::new(120, 60);
+        let tick = timer.tick_duration();
+        let stalled = timer.now + Duration::from_secs(5);
+        let action = timer.tick_at(stalled);
+        assert_eq!(action.ticks, DEFAULT_MAX_CATCH_UP);
+        // 5s of ticks & renders were due
+        assert!((599..=600).contains(&(action.ticks + action.dropped_ticks)));
+        assert!(action.render);
+        assert!((289..=290).contains(&action.dropped_renders));
+        assert_eq!(timer.dropped_ticks(), action.dropped_ticks);
+        // the schedule starts over from the stall
+        assert_eq!(timer.tick_at(stalled + tick / 2).ticks, 0);
+        assert_eq!(timer.tick_at(stalled + tick * 3 / 2).ticks, 1);
+    }
+
+    #[test]
+    fn short_gaps_catch_up_in_full() {
+        let mut timer = Timer::new(120, 60);
+        let gap = timer.tick_duration() * 5 + timer.tick_duration() / 2;
+        let action = timer.tick_at(timer.now + gap);
+        assert_eq!(action.ticks, 5);
+        assert_eq!(action.dropped_ticks, 0);
+    }
+
+    #[test]
+    fn oversleep_stats() {
+        let mut timer = Timer::new(120, 60);
+        assert_eq!(timer.oversleep().to_string(), "Oversleep 0.00/0.00/0.00ms");
+        for ms in [2, 1, 6] {
+            timer.record_oversleep(Duration::from_millis(ms));
+        }
+        assert_eq!(timer.oversleep().to_string(), "Oversleep 1.00/3.00/6.00ms");
+    }
 }